@@ -0,0 +1,897 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// The instructions and modes a case relies on; interpreters run the cases
+/// up to the level they support
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Add, multiply and halt in position mode, with non-negative values
+    AddMul,
+    /// Every instruction but relative base adjustment, in position and
+    /// immediate modes, with values that fit 32 bits and no access past the
+    /// end of the program
+    Basic,
+    /// Relative mode, 64-bit values and memory past the end of the program
+    Full,
+}
+
+/// What a program outputs and leaves at position 0 when it halts
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome {
+    pub outputs: Vec<i64>,
+    pub position0: i64,
+}
+
+/// A program, the inputs it is given and how it must end
+#[derive(Clone, Debug, PartialEq)]
+pub struct Case {
+    pub name: &'static str,
+    pub level: Level,
+    pub program: Vec<i64>,
+    pub inputs: Vec<i64>,
+    /// `None` if the program must fail
+    pub expected: Option<Outcome>,
+}
+
+impl Case {
+    fn new(
+        name: &'static str,
+        level: Level,
+        program: &[i64],
+        inputs: &[i64],
+        outputs: &[i64],
+        position0: i64,
+    ) -> Self {
+        Self {
+            name,
+            level,
+            program: program.to_vec(),
+            inputs: inputs.to_vec(),
+            expected: Some(Outcome {
+                outputs: outputs.to_vec(),
+                position0,
+            }),
+        }
+    }
+
+    fn failing(
+        name: &'static str,
+        level: Level,
+        program: &[i64],
+        inputs: &[i64],
+    ) -> Self {
+        Self {
+            name,
+            level,
+            program: program.to_vec(),
+            inputs: inputs.to_vec(),
+            expected: None,
+        }
+    }
+}
+
+/// The part of an outcome an interpreter lets us see
+#[derive(Clone, Debug, PartialEq)]
+pub enum Observation {
+    Position0(i64),
+    LastOutput(Option<i64>),
+    /// The last value output, 0 if there was none
+    Signal(i64),
+    Outputs(Vec<i64>),
+    All(Outcome),
+}
+
+impl fmt::Display for Observation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Position0(value) => write!(f, "position 0 = {}", value),
+            Self::LastOutput(Some(value)) => write!(f, "last output {}", value),
+            Self::LastOutput(None) => write!(f, "no output"),
+            Self::Signal(value) => write!(f, "signal {}", value),
+            Self::Outputs(outputs) => write!(f, "outputs {:?}", outputs),
+            Self::All(outcome) => write!(
+                f,
+                "outputs {:?}, position 0 = {}",
+                outcome.outputs, outcome.position0
+            ),
+        }
+    }
+}
+
+impl Observation {
+    pub fn view(&self) -> View {
+        match self {
+            Self::Position0(_) => View::Position0,
+            Self::LastOutput(_) => View::LastOutput,
+            Self::Signal(_) => View::Signal,
+            Self::Outputs(_) => View::Outputs,
+            Self::All(_) => View::All,
+        }
+    }
+
+    /// What the observation shows through a narrower view, `None` if it
+    /// doesn't show that much
+    pub fn narrow(&self, view: View) -> Option<Observation> {
+        let last_output = match self {
+            Self::All(outcome) => return Some(view.observe(outcome)),
+            _ if self.view() == view => return Some(self.clone()),
+            Self::Outputs(outputs) => outputs.last().copied(),
+            Self::LastOutput(output) => *output,
+            _ => return None,
+        };
+        match view {
+            View::LastOutput => Some(Self::LastOutput(last_output)),
+            View::Signal => Some(Self::Signal(last_output.unwrap_or(0))),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum View {
+    Position0,
+    LastOutput,
+    Signal,
+    Outputs,
+    All,
+}
+
+impl View {
+    pub fn observe(self, outcome: &Outcome) -> Observation {
+        match self {
+            Self::Position0 => Observation::Position0(outcome.position0),
+            Self::LastOutput => {
+                Observation::LastOutput(outcome.outputs.last().copied())
+            }
+            Self::Signal => Observation::Signal(
+                outcome.outputs.last().copied().unwrap_or(0),
+            ),
+            Self::Outputs => Observation::Outputs(outcome.outputs.clone()),
+            Self::All => Observation::All(outcome.clone()),
+        }
+    }
+}
+
+pub trait Interpreter {
+    fn name(&self) -> &'static str;
+    fn level(&self) -> Level;
+    fn view(&self) -> View;
+
+    fn supports(&self, case: &Case) -> bool {
+        case.level <= self.level()
+    }
+
+    /// Runs the case, `None` if the interpreter reports an error
+    fn run(&self, case: &Case) -> Option<Observation>;
+}
+
+/// How a run ended
+#[derive(Clone, Debug, PartialEq)]
+pub enum Behaviour {
+    Halted(Observation),
+    Failed,
+    Panicked(String),
+}
+
+impl fmt::Display for Behaviour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Halted(observation) => write!(f, "{}", observation),
+            Self::Failed => write!(f, "an error"),
+            Self::Panicked(message) => write!(f, "a panic: {}", message),
+        }
+    }
+}
+
+/// An interpreter not behaving as a case expects
+#[derive(Clone, Debug, PartialEq)]
+pub struct Disagreement {
+    pub case: &'static str,
+    pub interpreter: &'static str,
+    pub expected: Behaviour,
+    pub actual: Behaviour,
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} on {}: expected {}, got {}",
+            self.interpreter, self.case, self.expected, self.actual
+        )
+    }
+}
+
+/// Runs the case on the interpreter; `None` if it doesn't support the case
+pub fn check(
+    case: &Case,
+    interpreter: &dyn Interpreter,
+) -> Option<Result<(), Disagreement>> {
+    if !interpreter.supports(case) {
+        return None;
+    }
+    let expected = match &case.expected {
+        Some(outcome) => Behaviour::Halted(interpreter.view().observe(outcome)),
+        None => Behaviour::Failed,
+    };
+    let actual = behaviour(case, interpreter);
+    if actual == expected {
+        Some(Ok(()))
+    } else {
+        Some(Err(Disagreement {
+            case: case.name,
+            interpreter: interpreter.name(),
+            expected,
+            actual,
+        }))
+    }
+}
+
+fn behaviour(case: &Case, interpreter: &dyn Interpreter) -> Behaviour {
+    match catch_unwind(AssertUnwindSafe(|| interpreter.run(case))) {
+        Ok(Some(observation)) => Behaviour::Halted(observation),
+        Ok(None) => Behaviour::Failed,
+        Err(payload) => Behaviour::Panicked(
+            payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+/// Two interpreters behaving differently on the same case, whatever the
+/// case expects
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    pub case: &'static str,
+    pub interpreters: [(&'static str, Behaviour); 2],
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [(first, first_behaviour), (second, second_behaviour)] =
+            &self.interpreters;
+        write!(
+            f,
+            "{}: {} gives {}, {} gives {}",
+            self.case, first, first_behaviour, second, second_behaviour
+        )
+    }
+}
+
+/// Whether two runs are alike as far as both interpreters let us see; any
+/// two errors or panics are
+fn alike(first: &Behaviour, second: &Behaviour) -> bool {
+    match (first, second) {
+        (Behaviour::Halted(first), Behaviour::Halted(second)) => {
+            match first.narrow(second.view()) {
+                Some(first) => first == *second,
+                None => second
+                    .narrow(first.view())
+                    .is_none_or(|second| second == *first),
+            }
+        }
+        (Behaviour::Failed, Behaviour::Failed)
+        | (Behaviour::Panicked(_), Behaviour::Panicked(_)) => true,
+        _ => false,
+    }
+}
+
+/// Every pair of interpreters that run a case they both support differently
+pub fn conflicts(
+    cases: &[Case],
+    interpreters: &[Box<dyn Interpreter>],
+) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    for case in cases {
+        let runs: Vec<_> = interpreters
+            .iter()
+            .filter(|interpreter| interpreter.supports(case))
+            .map(|interpreter| {
+                (interpreter.name(), behaviour(case, &**interpreter))
+            })
+            .collect();
+        for (i, first) in runs.iter().enumerate() {
+            for second in &runs[i + 1..] {
+                if !alike(&first.1, &second.1) {
+                    conflicts.push(Conflict {
+                        case: case.name,
+                        interpreters: [first.clone(), second.clone()],
+                    });
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+/// Every case that an interpreter supports but doesn't run as expected
+pub fn disagreements(
+    cases: &[Case],
+    interpreters: &[Box<dyn Interpreter>],
+) -> Vec<Disagreement> {
+    cases
+        .iter()
+        .flat_map(|case| {
+            interpreters
+                .iter()
+                .filter_map(move |interpreter| check(case, &**interpreter))
+        })
+        .filter_map(Result::err)
+        .collect()
+}
+
+pub struct Day02;
+
+impl Interpreter for Day02 {
+    fn name(&self) -> &'static str {
+        "day02"
+    }
+
+    fn level(&self) -> Level {
+        Level::AddMul
+    }
+
+    fn view(&self) -> View {
+        View::Position0
+    }
+
+    fn run(&self, case: &Case) -> Option<Observation> {
+        let program = convert::<usize>(&case.program)?;
+        let position0 = day02::execute(&program, None, None)?;
+        Some(Observation::Position0(i64::try_from(position0).ok()?))
+    }
+}
+
+/// The `intcode` engine with the given memory backend
+pub struct Intcode<M> {
+    name: &'static str,
+    memory: PhantomData<M>,
+}
+
+impl<M> Intcode<M> {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            memory: PhantomData,
+        }
+    }
+}
+
+impl<M> Interpreter for Intcode<M>
+where
+    M: intcode::memory::Memory<Word = i64>,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn level(&self) -> Level {
+        Level::Full
+    }
+
+    fn view(&self) -> View {
+        View::Outputs
+    }
+
+    fn run(&self, case: &Case) -> Option<Observation> {
+        let mut io = intcode::SimpleInputOutput::new(&case.inputs);
+        intcode::execute_with_memory::<M>(&case.program, &mut io).ok()?;
+        Some(Observation::Outputs(io.get_output()))
+    }
+}
+
+/// The `intcode2` computer with the given memory backend
+pub struct Intcode2<M> {
+    name: &'static str,
+    memory: PhantomData<M>,
+}
+
+impl<M> Intcode2<M> {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            memory: PhantomData,
+        }
+    }
+}
+
+impl<M> Interpreter for Intcode2<M>
+where
+    M: intcode2::memory::Memory<Word = i64>,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn level(&self) -> Level {
+        Level::Full
+    }
+
+    fn view(&self) -> View {
+        View::All
+    }
+
+    fn run(&self, case: &Case) -> Option<Observation> {
+        let mut computer =
+            intcode2::IntcodeComputer::<M>::with_memory(&case.program, false);
+        let mut io = intcode2::SimpleInputOutput::new(&case.inputs);
+        computer.run(&mut io).ok()?;
+        Some(Observation::All(Outcome {
+            outputs: io.get_output(),
+            position0: computer.read_memory(0),
+        }))
+    }
+}
+
+fn convert<T: TryFrom<i64>>(values: &[i64]) -> Option<Vec<T>> {
+    values
+        .iter()
+        .map(|&value| T::try_from(value).ok())
+        .collect()
+}
+
+/// Every interpreter in the workspace, with each memory backend; days 5 and
+/// 7 aren't listed as they run their programs on the engines
+pub fn interpreters() -> Vec<Box<dyn Interpreter>> {
+    use intcode::memory as memory1;
+    use intcode2::memory as memory2;
+
+    vec![
+        Box::new(Day02),
+        Box::new(Intcode::<memory1::PagedMemory>::new("intcode")),
+        Box::new(Intcode::<memory1::HashMemory>::new("intcode (hash)")),
+        Box::new(Intcode2::<memory2::PagedMemory>::new("intcode2")),
+        Box::new(Intcode2::<memory2::HashMemory>::new("intcode2 (hash)")),
+        Box::new(Intcode2::<memory2::CowMemory>::new("intcode2 (cow)")),
+    ]
+}
+
+const LARGER_THAN_8: [i64; 47] = [
+    3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106,
+    0, 36, 98, 0, 0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1,
+    46, 1101, 1000, 1, 20, 4, 20, 1105, 1, 46, 98, 99,
+];
+
+const QUINE: [i64; 16] = [
+    109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+];
+
+/// Programs covering every instruction in every mode, with the edge cases
+/// where interpreters are most likely to differ
+pub fn suite() -> Vec<Case> {
+    use Level::*;
+
+    vec![
+        // Add, multiply and halt
+        Case::new("halt", AddMul, &[99], &[], &[], 99),
+        Case::new("add", AddMul, &[1, 0, 0, 0, 99], &[], &[], 2),
+        Case::new("multiply", AddMul, &[2, 4, 4, 0, 99], &[], &[], 9801),
+        Case::new(
+            "day 2 sample",
+            AddMul,
+            &[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50],
+            &[],
+            &[],
+            3500,
+        ),
+        Case::new(
+            "self-modifying",
+            AddMul,
+            &[1, 1, 1, 4, 99, 5, 6, 0, 99],
+            &[],
+            &[],
+            30,
+        ),
+        Case::failing("invalid opcode", AddMul, &[42], &[]),
+        Case::failing("opcode 0", AddMul, &[0, 99], &[]),
+        Case::failing("no halt", AddMul, &[1, 0, 0, 0], &[]),
+        // Immediate mode
+        Case::new(
+            "add immediate",
+            Basic,
+            &[1101, 100, -1, 4, 0],
+            &[],
+            &[],
+            1101,
+        ),
+        Case::new(
+            "multiply mixed modes",
+            Basic,
+            &[1002, 4, 3, 4, 33],
+            &[],
+            &[],
+            1002,
+        ),
+        Case::new("first immediate", Basic, &[101, 5, 0, 0, 99], &[], &[], 106),
+        Case::new(
+            "second immediate",
+            Basic,
+            &[1001, 0, 5, 0, 99],
+            &[],
+            &[],
+            1006,
+        ),
+        Case::new(
+            "negative values",
+            Basic,
+            &[1101, -5, -7, 0, 4, 0, 99],
+            &[],
+            &[-12],
+            -12,
+        ),
+        // Input and output
+        Case::new("input output", Basic, &[3, 0, 4, 0, 99], &[42], &[42], 42),
+        Case::new("output immediate", Basic, &[104, 7, 99], &[], &[7], 104),
+        Case::new(
+            "several inputs",
+            Basic,
+            &[3, 0, 3, 1, 4, 0, 4, 1, 99],
+            &[5, 6],
+            &[5, 6],
+            5,
+        ),
+        // Comparisons
+        Case::new(
+            "equals position",
+            Basic,
+            &[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[8],
+            &[1],
+            3,
+        ),
+        Case::new(
+            "not equals position",
+            Basic,
+            &[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[7],
+            &[0],
+            3,
+        ),
+        Case::new(
+            "less than position",
+            Basic,
+            &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[5],
+            &[1],
+            3,
+        ),
+        Case::new(
+            "not less than position",
+            Basic,
+            &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[8],
+            &[0],
+            3,
+        ),
+        Case::new(
+            "equals immediate",
+            Basic,
+            &[3, 3, 1108, -1, 8, 3, 4, 3, 99],
+            &[8],
+            &[1],
+            3,
+        ),
+        Case::new(
+            "not equals immediate",
+            Basic,
+            &[3, 3, 1108, -1, 8, 3, 4, 3, 99],
+            &[9],
+            &[0],
+            3,
+        ),
+        Case::new(
+            "less than immediate",
+            Basic,
+            &[3, 3, 1107, -1, 8, 3, 4, 3, 99],
+            &[7],
+            &[1],
+            3,
+        ),
+        Case::new(
+            "not less than immediate",
+            Basic,
+            &[3, 3, 1107, -1, 8, 3, 4, 3, 99],
+            &[8],
+            &[0],
+            3,
+        ),
+        // Jumps
+        Case::new(
+            "jump if true position",
+            Basic,
+            &[3, 11, 5, 11, 12, 4, 13, 99, 4, 14, 99, -1, 8, 0, 1],
+            &[7],
+            &[1],
+            3,
+        ),
+        Case::new(
+            "no jump if true position",
+            Basic,
+            &[3, 11, 5, 11, 12, 4, 13, 99, 4, 14, 99, -1, 8, 0, 1],
+            &[0],
+            &[0],
+            3,
+        ),
+        Case::new(
+            "jump if true immediate",
+            Basic,
+            &[3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1],
+            &[2],
+            &[1],
+            3,
+        ),
+        Case::new(
+            "no jump if true immediate",
+            Basic,
+            &[3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1],
+            &[0],
+            &[0],
+            3,
+        ),
+        Case::new(
+            "jump if false position",
+            Basic,
+            &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9],
+            &[0],
+            &[0],
+            3,
+        ),
+        Case::new(
+            "no jump if false position",
+            Basic,
+            &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9],
+            &[2],
+            &[1],
+            3,
+        ),
+        Case::new(
+            "jump if false mixed modes",
+            Basic,
+            &[3, 11, 1006, 11, 8, 4, 13, 99, 4, 14, 99, -1, 0, 0, 1],
+            &[0],
+            &[1],
+            3,
+        ),
+        Case::new(
+            "no jump if false mixed modes",
+            Basic,
+            &[3, 11, 1006, 11, 8, 4, 13, 99, 4, 14, 99, -1, 0, 0, 1],
+            &[5],
+            &[0],
+            3,
+        ),
+        Case::new("equal to 8", Basic, &LARGER_THAN_8, &[8], &[1000], 3),
+        Case::new("larger than 8", Basic, &LARGER_THAN_8, &[9], &[1001], 3),
+        // Errors
+        Case::failing("no input", Basic, &[3, 0, 99], &[]),
+        Case::failing("negative address", Basic, &[1, -1, 0, 0, 99], &[]),
+        Case::failing("negative jump", Basic, &[1105, 1, -1], &[]),
+        Case::failing("invalid mode", Basic, &[301, 0, 0, 0, 99], &[]),
+        Case::failing("immediate write", Basic, &[11101, 1, 1, 0, 99], &[]),
+        Case::failing("immediate input", Basic, &[103, 0, 99], &[1]),
+        // Memory past the end of the program
+        Case::new("read past end", Full, &[1, 0, 10, 0, 99], &[], &[], 1),
+        Case::new(
+            "write past end",
+            Full,
+            &[1101, 1, 2, 100, 4, 100, 99],
+            &[],
+            &[3],
+            1101,
+        ),
+        Case::new("output past end", Full, &[4, 1000, 99], &[], &[0], 4),
+        // Large values
+        Case::new(
+            "large product",
+            Full,
+            &[1102, 34_915_192, 34_915_192, 7, 4, 7, 99, 0],
+            &[],
+            &[1_219_070_632_396_864],
+            1102,
+        ),
+        Case::new(
+            "large immediate",
+            Full,
+            &[104, 1_125_899_906_842_624, 99],
+            &[],
+            &[1_125_899_906_842_624],
+            104,
+        ),
+        Case::failing(
+            "add overflows",
+            Full,
+            &[1101, i64::MAX, 1, 0, 4, 0, 99],
+            &[],
+        ),
+        Case::failing(
+            "multiply overflows",
+            Full,
+            &[1102, i64::MAX, 2, 0, 99],
+            &[],
+        ),
+        // Relative mode
+        Case::new("quine", Full, &QUINE, &[], &QUINE, 109),
+        Case::new(
+            "relative output",
+            Full,
+            &[109, 5, 204, -4, 99],
+            &[],
+            &[5],
+            109,
+        ),
+        Case::new(
+            "relative input",
+            Full,
+            &[109, 10, 203, 0, 204, 0, 99],
+            &[7],
+            &[7],
+            109,
+        ),
+        Case::new(
+            "relative write",
+            Full,
+            &[109, 20, 21101, 2, 3, 0, 204, 0, 99],
+            &[],
+            &[5],
+            109,
+        ),
+        Case::new(
+            "relative operands",
+            Full,
+            &[109, 9, 22201, 0, 1, 2, 204, 2, 99, 5, 7, 0],
+            &[],
+            &[12],
+            109,
+        ),
+        Case::new(
+            "relative compare",
+            Full,
+            &[109, 10, 21108, 5, 5, 0, 204, 0, 99],
+            &[],
+            &[1],
+            109,
+        ),
+        Case::new(
+            "relative jump",
+            Full,
+            &[109, 8, 2105, 1, 0, 104, 0, 99, 9, 104, 1, 99],
+            &[],
+            &[1],
+            109,
+        ),
+        Case::new(
+            "adjust base position",
+            Full,
+            &[9, 5, 204, -1, 99, 3],
+            &[],
+            &[204],
+            9,
+        ),
+        Case::new(
+            "adjust base twice",
+            Full,
+            &[109, 10, 109, -4, 204, 0, 99],
+            &[],
+            &[99],
+            109,
+        ),
+        Case::new(
+            "adjust base relative",
+            Full,
+            &[109, 1, 209, 6, 204, -1, 99, 4],
+            &[],
+            &[204],
+            109,
+        ),
+        Case::failing("negative relative address", Full, &[204, -1, 99], &[]),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn unique_names() {
+        let cases = suite();
+        let names: HashSet<_> = cases.iter().map(|case| case.name).collect();
+        assert_eq!(names.len(), cases.len());
+    }
+
+    #[test]
+    fn every_level_runs_somewhere() {
+        let interpreters = interpreters();
+        for case in suite() {
+            assert!(
+                interpreters.iter().any(|interp| interp.supports(&case)),
+                "{} is not run",
+                case.name
+            );
+        }
+    }
+
+    #[test]
+    fn engines_conform() {
+        let engines: Vec<_> = interpreters()
+            .into_iter()
+            .filter(|interp| interp.level() == Level::Full)
+            .collect();
+        let found = disagreements(&suite(), &engines);
+        assert!(found.is_empty(), "{:#?}", found);
+    }
+
+    #[test]
+    fn day_interpreters() {
+        let days: Vec<_> = interpreters()
+            .into_iter()
+            .filter(|interp| interp.level() < Level::Full)
+            .collect();
+        let found = disagreements(&suite(), &days);
+        assert!(found.is_empty(), "{:#?}", found);
+    }
+
+    #[test]
+    fn interpreters_agree() {
+        let found = conflicts(&suite(), &interpreters());
+        assert!(found.is_empty(), "{:#?}", found);
+    }
+
+    /// Outputs the sum of its inputs, but only halts on programs that start
+    /// with 99
+    struct Summer;
+
+    impl Interpreter for Summer {
+        fn name(&self) -> &'static str {
+            "summer"
+        }
+
+        fn level(&self) -> Level {
+            Level::Full
+        }
+
+        fn view(&self) -> View {
+            View::Signal
+        }
+
+        fn run(&self, case: &Case) -> Option<Observation> {
+            if case.program.first() != Some(&99) {
+                return None;
+            }
+            Some(Observation::Signal(case.inputs.iter().sum()))
+        }
+    }
+
+    #[test]
+    fn conflicting_interpreters() {
+        let cases = [
+            Case::new("halt", Level::Full, &[99], &[1, 2], &[], 99),
+            Case::new("output", Level::Full, &[104, 3, 99], &[], &[3], 104),
+            Case::new("silent", Level::Full, &[99, 104, 3], &[], &[], 99),
+        ];
+        let interpreters: Vec<Box<dyn Interpreter>> = vec![
+            Box::new(Summer),
+            Box::new(Intcode2::<intcode2::memory::PagedMemory>::new(
+                "intcode2",
+            )),
+        ];
+        let found = conflicts(&cases, &interpreters)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                "halt: summer gives signal 3, intcode2 gives outputs [], \
+                 position 0 = 99",
+                "output: summer gives an error, intcode2 gives outputs [3], \
+                 position 0 = 104",
+            ]
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use conformance::{check, conflicts, interpreters, suite};
+use std::panic::set_hook;
+use std::process::exit;
+
+fn main() {
+    println!(env!("CARGO_PKG_DESCRIPTION"));
+    let cases = suite();
+    let interpreters = interpreters();
+
+    // Panics are caught and reported as disagreements
+    set_hook(Box::new(|_| {}));
+    let mut failed = 0;
+    for interpreter in &interpreters {
+        let (mut passed, mut skipped) = (0, 0);
+        for case in &cases {
+            match check(case, &**interpreter) {
+                Some(Ok(())) => passed += 1,
+                Some(Err(disagreement)) => {
+                    println!("  {}", disagreement);
+                    failed += 1;
+                }
+                None => skipped += 1,
+            }
+        }
+        println!(
+            "{}: {} passed, {} skipped",
+            interpreter.name(),
+            passed,
+            skipped
+        );
+    }
+
+    // Interpreters that miss a case the same way still agree with each other
+    let conflicts = conflicts(&cases, &interpreters);
+    if !conflicts.is_empty() {
+        println!("\nBetween interpreters:");
+        for conflict in &conflicts {
+            println!("  {}", conflict);
+        }
+    }
+    println!(
+        "\n{} cases, {} disagreements, {} conflicts between interpreters",
+        cases.len(),
+        failed,
+        conflicts.len()
+    );
+    if failed > 0 || !conflicts.is_empty() {
+        exit(1);
+    }
+}
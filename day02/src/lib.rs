@@ -1,8 +1,14 @@
+use intcode2::symbolic::{closed_form, solve, LinearExpr, Unknown};
+use std::convert::TryFrom;
+
 const ADD_OP: usize = 1;
 const MULT_OP: usize = 2;
 const HALT_OP: usize = 99;
+const TARGET: i64 = 19_690_720;
 
-fn execute(
+/// Runs the program, optionally replacing the values at positions 1 and 2,
+/// and returns the value it leaves at position 0
+pub fn execute(
     input: &[usize],
     param1: Option<usize>,
     param2: Option<usize>,
@@ -51,15 +57,31 @@ pub fn part1(input: &[usize]) -> Option<usize> {
     execute(input, Some(12), Some(2))
 }
 
+/// The noun and verb replace the values at positions 1 and 2
+fn unknowns() -> [Unknown; 2] {
+    [
+        Unknown::new("noun", 1, 0..=99),
+        Unknown::new("verb", 2, 0..=99),
+    ]
+}
+
+fn to_program(input: &[usize]) -> Option<Vec<i64>> {
+    input
+        .iter()
+        .map(|&value| i64::try_from(value).ok())
+        .collect()
+}
+
+/// What the program leaves at position 0, in terms of the noun and verb
+pub fn output_expr(input: &[usize]) -> Result<LinearExpr, String> {
+    let program = to_program(input).ok_or("Value out of range")?;
+    closed_form(&program, &unknowns(), 0).map_err(|err| err.to_string())
+}
+
 pub fn part2(input: &[usize]) -> Option<usize> {
-    for noun in 0..=99 {
-        for verb in 0..=99 {
-            if let Some(19_690_720) = execute(input, Some(noun), Some(verb)) {
-                return Some(100 * noun + verb);
-            }
-        }
-    }
-    None
+    let program = to_program(input)?;
+    let solution = solve(&program, &unknowns(), 0, TARGET)?;
+    usize::try_from(100 * solution["noun"] + solution["verb"]).ok()
 }
 
 #[cfg(test)]
@@ -102,4 +124,17 @@ mod tests {
         assert_eq!(execute(&[2, 0, 5, 0, 99], None, None), None);
         assert_eq!(execute(&[2, 0, 0, 5, 99], None, None), None);
     }
+
+    #[test]
+    fn symbolic() {
+        // Sets position 0 to the noun plus the verb, doubles it and adds 3
+        let program =
+            [1, 0, 0, 0, 1, 1, 2, 0, 2, 0, 17, 0, 1, 0, 18, 0, 99, 2, 3];
+        assert_eq!(
+            output_expr(&program).map(|expr| expr.to_string()),
+            Ok("2*noun + 2*verb + 3".to_string())
+        );
+        assert_eq!(part2(&program), None);
+        assert!(output_expr(&[2, 1, 2, 0, 99]).is_err());
+    }
 }
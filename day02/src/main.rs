@@ -1,5 +1,5 @@
 use clap::{crate_description, App, Arg};
-use day02::{part1, part2};
+use day02::{output_expr, part1, part2};
 use std::fs::read_to_string;
 use std::num::ParseIntError;
 use std::process::exit;
@@ -12,6 +12,12 @@ fn main() {
                 .required(true)
                 .index(1),
         )
+        .arg(
+            Arg::with_name("SYMBOLIC")
+                .help("Prints the output in terms of the noun and verb")
+                .short("s")
+                .long("symbolic"),
+        )
         .get_matches();
 
     println!(crate_description!());
@@ -21,6 +27,12 @@ fn main() {
     } else {
         println!("Part 1: Invalid input");
     }
+    if args.is_present("SYMBOLIC") {
+        match output_expr(&input) {
+            Ok(expr) => println!("Output: {}", expr),
+            Err(err) => println!("Output: {}", err),
+        }
+    }
     if let Some(result1) = part2(&input) {
         println!("Part 2: {}", result1);
     } else {
@@ -1,121 +1,34 @@
-use std::convert::TryFrom;
-
-const ADD_OP: i32 = 1;
-const MULT_OP: i32 = 2;
-const IN_OP: i32 = 3;
-const OUT_OP: i32 = 4;
-const JIT_OP: i32 = 5;
-const JIF_OP: i32 = 6;
-const LT_OP: i32 = 7;
-const EQ_OP: i32 = 8;
-const HALT_OP: i32 = 99;
-
-const POSITION_MODE: i32 = 0;
-const IMMEDIATE_MODE: i32 = 1;
-
-fn parse_instr(instr: i32) -> (i32, i32, i32) {
-    let mut opcode = instr;
-    let mode2 = opcode / 1_000;
-    opcode -= mode2 * 1_000;
-    let mode1 = opcode / 100;
-    opcode -= mode1 * 100;
-    (opcode, mode1, mode2)
-}
-
-fn get_value(mem: &[i32], ptr: usize, mode: i32) -> Option<i32> {
-    match mode {
-        POSITION_MODE => {
-            let pos = mem.get(ptr).and_then(|&p| usize::try_from(p).ok())?;
-            mem.get(pos).cloned()
-        }
-        IMMEDIATE_MODE => mem.get(ptr).cloned(),
-        _ => None,
-    }
-}
-
-fn get_addr(mem: &[i32], ptr: usize) -> Option<usize> {
-    mem.get(ptr).and_then(|&a| usize::try_from(a).ok())
+use intcode::limits::Limits;
+use intcode::{execute_limited, IntcodeError, SimpleInputOutput};
+
+/// Runs the program with the given input and returns the last value it
+/// outputs, if any
+pub fn execute(
+    program: &[i32],
+    input: i32,
+    limits: Limits,
+) -> Result<Option<i32>, IntcodeError> {
+    let mut prog_io = SimpleInputOutput::new(&[input]);
+    execute_limited(program, &mut prog_io, limits)?;
+    Ok(prog_io.get_output().last().cloned())
 }
 
-fn execute(program: &[i32], input: i32) -> Option<i32> {
-    let mut instr_ptr = 0;
-    let mut memory = program.to_vec();
-    let mut output = None;
-
-    loop {
-        match memory.get(instr_ptr).map(|&i| parse_instr(i)) {
-            Some((HALT_OP, _, _)) => break,
-            Some((ADD_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, mode2)?;
-                let addr = get_addr(&memory, instr_ptr + 3)?;
-                *memory.get_mut(addr)? = val1 + val2;
-                instr_ptr += 4;
-            }
-            Some((MULT_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, mode2)?;
-                let addr = get_addr(&memory, instr_ptr + 3)?;
-                *memory.get_mut(addr)? = val1 * val2;
-                instr_ptr += 4;
-            }
-            Some((IN_OP, _, _)) => {
-                let addr = get_addr(&memory, instr_ptr + 1)?;
-                *memory.get_mut(addr)? = input;
-                instr_ptr += 2;
-            }
-            Some((OUT_OP, _, _)) => {
-                let addr = get_addr(&memory, instr_ptr + 1)?;
-                output = memory.get_mut(addr).cloned();
-                instr_ptr += 2;
-            }
-            Some((JIT_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, mode2)?;
-                if val1 != 0 {
-                    instr_ptr = usize::try_from(val2).ok()?;
-                } else {
-                    instr_ptr += 3;
-                }
-            }
-            Some((JIF_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, mode2)?;
-                if val1 == 0 {
-                    instr_ptr = usize::try_from(val2).ok()?;
-                } else {
-                    instr_ptr += 3;
-                }
-            }
-            Some((LT_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, mode2)?;
-                let addr = get_addr(&memory, instr_ptr + 3)?;
-                *memory.get_mut(addr)? = i32::from(val1 < val2);
-                instr_ptr += 4;
-            }
-            Some((EQ_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, mode2)?;
-                let addr = get_addr(&memory, instr_ptr + 3)?;
-                *memory.get_mut(addr)? = i32::from(val1 == val2);
-                instr_ptr += 4;
-            }
-            _ => {
-                // Invalid instruction
-                return None;
-            }
-        }
-    }
-    output
+pub fn part1(program: &[i32], limits: Limits) -> Result<i32, String> {
+    run_diagnostic(program, 1, limits)
 }
 
-pub fn part1(program: &[i32]) -> Option<i32> {
-    execute(program, 1)
+pub fn part2(program: &[i32], limits: Limits) -> Result<i32, String> {
+    run_diagnostic(program, 5, limits)
 }
 
-pub fn part2(program: &[i32]) -> Option<i32> {
-    execute(program, 5)
+fn run_diagnostic(
+    program: &[i32],
+    system_id: i32,
+    limits: Limits,
+) -> Result<i32, String> {
+    execute(program, system_id, limits)
+        .map_err(|err| err.to_string())?
+        .ok_or_else(|| "program produced no output".to_string())
 }
 
 #[cfg(test)]
@@ -123,22 +36,20 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_parse_instr() {
-        assert_eq!(parse_instr(0), (0, 0, 0));
-        assert_eq!(parse_instr(1), (1, 0, 0));
-        assert_eq!(parse_instr(102), (2, 1, 0));
-        assert_eq!(parse_instr(1003), (3, 0, 1));
-        assert_eq!(parse_instr(1104), (4, 1, 1));
-        assert_eq!(parse_instr(1005), (5, 0, 1));
-        assert_eq!(parse_instr(1199), (99, 1, 1));
+    fn compare_to_8() {
+        let program = [
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31,
+            1106, 0, 36, 98, 0, 0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104,
+            999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105, 1, 46, 98, 99,
+        ];
+        assert_eq!(execute(&program, 7, Limits::default()), Ok(Some(999)));
+        assert_eq!(execute(&program, 8, Limits::default()), Ok(Some(1000)));
+        assert_eq!(execute(&program, 9, Limits::default()), Ok(Some(1001)));
     }
 
     #[test]
-    fn test_parse_instr_negative() {
-        // Signal must be preserved so bad instructions are detected
-        assert_eq!(parse_instr(-99), (-99, 0, 0));
-        assert_eq!(parse_instr(-101), (-1, -1, 0));
-        assert_eq!(parse_instr(-1002), (-2, 0, -1));
-        assert_eq!(parse_instr(-1102), (-2, -1, -1));
+    fn invalid_program() {
+        assert!(execute(&[1, -1, 0, 0, 99], 1, Limits::default()).is_err());
+        assert!(execute(&[-99], 1, Limits::default()).is_err());
     }
 }
@@ -1,5 +1,6 @@
 use clap::{crate_description, App, Arg};
 use day05::{part1, part2};
+use intcode::limits::Limits;
 use std::fs::read_to_string;
 use std::num::ParseIntError;
 use std::process::exit;
@@ -12,25 +13,25 @@ fn main() {
                 .required(true)
                 .index(1),
         )
+        .args(&Limits::args())
         .get_matches();
 
     println!(crate_description!());
     let program = read_input(args.value_of("INPUT").unwrap());
-    if let Some(result1) = part1(&program) {
-        println!("Part 1: {}", result1);
-    } else {
-        println!("Part 1: Program failed");
+    let limits = Limits::from_matches(&args);
+    match part1(&program, limits) {
+        Ok(result) => println!("Part 1: {}", result),
+        Err(err) => println!("Part 1: Program failed: {}", err),
     }
-    if let Some(result1) = part2(&program) {
-        println!("Part 2: {}", result1);
-    } else {
-        println!("Part 2: Program failed");
+    match part2(&program, limits) {
+        Ok(result) => println!("Part 2: {}", result),
+        Err(err) => println!("Part 2: Program failed: {}", err),
     }
 }
 
 fn read_input(filename: &str) -> Vec<i32> {
     let input = read_to_string(filename).unwrap_or_else(|err| {
-        println!("Failed to read file '{}': {}", filename, err.to_string());
+        println!("Failed to read file '{}': {}", filename, err);
         exit(3);
     });
     input
@@ -38,7 +39,7 @@ fn read_input(filename: &str) -> Vec<i32> {
         .map(|s| s.trim().parse())
         .collect::<Result<_, _>>()
         .unwrap_or_else(|err: ParseIntError| {
-            println!("Failed to parse input: {}", err.to_string());
+            println!("Failed to parse input: {}", err);
             exit(3);
         })
 }
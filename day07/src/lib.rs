@@ -1,168 +1,140 @@
-use itertools::Itertools;
-use std::convert::TryFrom;
+use intcode2::channel::{spawn_with, ChannelInputOutput};
+use intcode2::limits::Limits;
+use intcode2::memory::PagedMemory;
+use intcode2::{IntcodeComputer, IntcodeError, SimpleInputOutput};
+use itertools::{process_results, Itertools};
+use std::ops::RangeInclusive;
+use std::sync::mpsc::channel;
 
-const ADD_OP: i32 = 1;
-const MULT_OP: i32 = 2;
-const IN_OP: i32 = 3;
-const OUT_OP: i32 = 4;
-const JIT_OP: i32 = 5;
-const JIF_OP: i32 = 6;
-const LT_OP: i32 = 7;
-const EQ_OP: i32 = 8;
-const HALT_OP: i32 = 99;
+type Amplifier = IntcodeComputer<PagedMemory<i32>>;
 
-const POSITION_MODE: i32 = 0;
-const IMMEDIATE_MODE: i32 = 1;
+const STUCK: &str = "amplifier is waiting for a signal that never comes";
 
-pub fn part1(program: &[i32]) -> Option<i32> {
-    (0..=4)
-        .permutations(5)
-        .filter_map(|parameters| amplify(program, &parameters))
-        .max()
+pub fn part1(program: &[i32], limits: Limits) -> Result<i32, String> {
+    max_signal(0..=4, |parameters| amplify(program, parameters, limits))
 }
 
-fn amplify(program: &[i32], parameters: &[i32]) -> Option<i32> {
+fn amplify(
+    program: &[i32],
+    parameters: &[i32],
+    limits: Limits,
+) -> Result<i32, String> {
     let mut signal = 0;
     for param in parameters {
-        let mut memory = program.to_vec();
-        let input = [*param, signal];
-        let (result, _) = execute(&mut memory, &input, &mut 0, false)?;
-        signal = result;
+        signal = execute(program, &[*param, signal], limits)
+            .map_err(|err| err.to_string())?;
     }
-    Some(signal)
+    Ok(signal)
 }
 
-pub fn part2(program: &[i32]) -> Option<i32> {
-    (5..=9)
-        .permutations(5)
-        .filter_map(|parameters| amplify_with_feedback(program, &parameters))
-        .max()
+pub fn part2(program: &[i32], limits: Limits) -> Result<i32, String> {
+    max_signal(5..=9, |parameters| {
+        amplify_with_feedback(program, parameters, limits)
+    })
 }
 
-fn amplify_with_feedback(program: &[i32], parameters: &[i32]) -> Option<i32> {
+fn amplify_with_feedback(
+    program: &[i32],
+    parameters: &[i32],
+    limits: Limits,
+) -> Result<i32, String> {
+    // Each amplifier reads its parameter, then waits for the first signal
+    let mut amplifiers = parameters
+        .iter()
+        .map(|param| {
+            let mut amplifier = Amplifier::with_memory(program, true);
+            amplifier.set_limits(limits);
+            let mut io = SimpleInputOutput::new(&[*param]);
+            amplifier.run(&mut io)?;
+            Ok((amplifier, io))
+        })
+        .collect::<Result<Vec<_>, IntcodeError>>()
+        .map_err(|err| err.to_string())?;
     let mut signal = 0;
-    let mut amplifiers = Vec::new();
-    for param in parameters {
-        let mut memory = program.to_vec();
-        let input = [*param, signal];
-        let mut instr_ptr = 0;
-        let (result, _) = execute(&mut memory, &input, &mut instr_ptr, true)?;
-        signal = result;
-        amplifiers.push((memory, instr_ptr));
-    }
     loop {
-        for (memory, instr_ptr) in amplifiers.iter_mut() {
-            let (result, halted) = execute(memory, &[signal], instr_ptr, true)?;
-            if halted {
-                return Some(signal);
+        for (amplifier, io) in amplifiers.iter_mut() {
+            io.add_input(&[signal]);
+            let halted = amplifier.run(io).map_err(|err| err.to_string())?;
+            match io.get_output().last() {
+                Some(&output) => signal = output,
+                None if halted => return Ok(signal),
+                None => return Err(STUCK.to_string()),
             }
-            signal = result;
         }
     }
 }
 
-fn execute(
-    memory: &mut Vec<i32>,
-    input: &[i32],
-    instr_ptr: &mut usize,
-    break_on_output: bool,
-) -> Option<(i32, bool)> {
-    let mut input_iter = input.to_vec().into_iter();
-    let mut output = 0;
+/// Like `part2`, with each amplifier running on its own thread
+pub fn part2_threaded(program: &[i32], limits: Limits) -> Result<i32, String> {
+    max_signal(5..=9, |parameters| {
+        amplify_threaded(program, parameters, limits)
+    })
+}
 
-    loop {
-        match memory.get(*instr_ptr).map(|&i| parse_instr(i)) {
-            Some((HALT_OP, _, _)) => break,
-            Some((ADD_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, *instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, *instr_ptr + 2, mode2)?;
-                let addr = get_addr(&memory, *instr_ptr + 3)?;
-                *memory.get_mut(addr)? = val1 + val2;
-                *instr_ptr += 4;
-            }
-            Some((MULT_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, *instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, *instr_ptr + 2, mode2)?;
-                let addr = get_addr(&memory, *instr_ptr + 3)?;
-                *memory.get_mut(addr)? = val1 * val2;
-                *instr_ptr += 4;
-            }
-            Some((IN_OP, _, _)) => {
-                let addr = get_addr(&memory, *instr_ptr + 1)?;
-                *memory.get_mut(addr)? = input_iter.next()?;
-                *instr_ptr += 2;
-            }
-            Some((OUT_OP, _, _)) => {
-                let addr = get_addr(&memory, *instr_ptr + 1)?;
-                output = *memory.get_mut(addr)?;
-                *instr_ptr += 2;
-                if break_on_output {
-                    return Some((output, false));
-                }
-            }
-            Some((JIT_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, *instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, *instr_ptr + 2, mode2)?;
-                if val1 != 0 {
-                    *instr_ptr = usize::try_from(val2).ok()?;
-                } else {
-                    *instr_ptr += 3;
-                }
-            }
-            Some((JIF_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, *instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, *instr_ptr + 2, mode2)?;
-                if val1 == 0 {
-                    *instr_ptr = usize::try_from(val2).ok()?;
-                } else {
-                    *instr_ptr += 3;
-                }
-            }
-            Some((LT_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, *instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, *instr_ptr + 2, mode2)?;
-                let addr = get_addr(&memory, *instr_ptr + 3)?;
-                *memory.get_mut(addr)? = i32::from(val1 < val2);
-                *instr_ptr += 4;
-            }
-            Some((EQ_OP, mode1, mode2)) => {
-                let val1 = get_value(&memory, *instr_ptr + 1, mode1)?;
-                let val2 = get_value(&memory, *instr_ptr + 2, mode2)?;
-                let addr = get_addr(&memory, *instr_ptr + 3)?;
-                *memory.get_mut(addr)? = i32::from(val1 == val2);
-                *instr_ptr += 4;
-            }
-            _ => {
-                // Invalid instruction
-                return None;
-            }
+fn amplify_threaded(
+    program: &[i32],
+    parameters: &[i32],
+    limits: Limits,
+) -> Result<i32, String> {
+    // Each amplifier feeds the next one, and the last one feeds the first
+    // through this thread, which keeps the last signal
+    let (feedback, mut receiver) = channel();
+    feedback.send(parameters[0]).unwrap();
+    feedback.send(0).unwrap();
+    let mut amplifiers = Vec::new();
+    for next_param in parameters[1..].iter().map(Some).chain(Some(None)) {
+        let (sender, next_receiver) = channel();
+        if let Some(&param) = next_param {
+            sender.send(param).unwrap();
         }
+        let mut amplifier = Amplifier::with_memory(program, false);
+        amplifier.set_limits(limits);
+        let io = ChannelInputOutput::new(receiver, sender);
+        amplifiers.push(spawn_with(amplifier, io));
+        receiver = next_receiver;
     }
-    Some((output, true))
-}
-
-fn parse_instr(instr: i32) -> (i32, i32, i32) {
-    let mut opcode = instr;
-    let mode2 = opcode / 1_000;
-    opcode %= 1_000;
-    let mode1 = opcode / 100;
-    opcode %= 100;
-    (opcode, mode1, mode2)
-}
-
-fn get_value(mem: &[i32], ptr: usize, mode: i32) -> Option<i32> {
-    match mode {
-        POSITION_MODE => {
-            let pos = mem.get(ptr).and_then(|&p| usize::try_from(p).ok())?;
-            mem.get(pos).cloned()
+    let mut signal = None;
+    for value in receiver {
+        signal = Some(value);
+        // The first amplifier is done once the last one halts
+        let _ = feedback.send(value);
+    }
+    drop(feedback);
+    for amplifier in amplifiers {
+        let halted = amplifier
+            .join()
+            .expect("amplifier thread panicked")
+            .map_err(|err| err.to_string())?;
+        if !halted {
+            return Err(STUCK.to_string());
         }
-        IMMEDIATE_MODE => mem.get(ptr).cloned(),
-        _ => None,
     }
+    signal.ok_or_else(|| STUCK.to_string())
 }
 
-fn get_addr(mem: &[i32], ptr: usize) -> Option<usize> {
-    mem.get(ptr).and_then(|&a| usize::try_from(a).ok())
+/// The highest signal `amplify` gets out of any order of the phases
+fn max_signal<F>(phases: RangeInclusive<i32>, amplify: F) -> Result<i32, String>
+where
+    F: Fn(&[i32]) -> Result<i32, String>,
+{
+    let signals = phases
+        .permutations(5)
+        .map(|parameters| amplify(&parameters));
+    process_results(signals, |signals| signals.max().unwrap_or(0))
+}
+
+/// Runs the program with the given inputs until it halts; returns the last
+/// value it outputs, 0 if none
+pub fn execute(
+    program: &[i32],
+    input: &[i32],
+    limits: Limits,
+) -> Result<i32, IntcodeError> {
+    let mut amplifier = Amplifier::with_memory(program, false);
+    amplifier.set_limits(limits);
+    let mut io = SimpleInputOutput::new(input);
+    amplifier.run(&mut io)?;
+    Ok(io.get_output().last().cloned().unwrap_or(0))
 }
 
 #[cfg(test)]
@@ -196,7 +168,7 @@ mod tests {
             ),
         ];
         for (prog, result) in &tests {
-            assert_eq!(part1(prog), Some(*result));
+            assert_eq!(part1(prog, Limits::default()), Ok(*result));
         }
     }
 
@@ -221,27 +193,23 @@ mod tests {
             ),
         ];
         for (prog, result) in &tests {
-            assert_eq!(part2(prog), Some(*result));
+            assert_eq!(part2(prog, Limits::default()), Ok(*result));
+            assert_eq!(part2_threaded(prog, Limits::default()), Ok(*result));
         }
     }
 
     #[test]
-    fn test_parse_instr() {
-        assert_eq!(parse_instr(0), (0, 0, 0));
-        assert_eq!(parse_instr(1), (1, 0, 0));
-        assert_eq!(parse_instr(102), (2, 1, 0));
-        assert_eq!(parse_instr(1003), (3, 0, 1));
-        assert_eq!(parse_instr(1104), (4, 1, 1));
-        assert_eq!(parse_instr(1005), (5, 0, 1));
-        assert_eq!(parse_instr(1199), (99, 1, 1));
-    }
-
-    #[test]
-    fn test_parse_instr_negative() {
-        // Signal must be preserved so bad instructions are detected
-        assert_eq!(parse_instr(-99), (-99, 0, 0));
-        assert_eq!(parse_instr(-101), (-1, -1, 0));
-        assert_eq!(parse_instr(-1002), (-2, 0, -1));
-        assert_eq!(parse_instr(-1102), (-2, -1, -1));
+    fn overflow() {
+        // Doubles the biggest 32-bit value after reading both inputs
+        let program = [
+            1101, 0, 2147483647, 13, 3, 14, 3, 14, 1002, 13, 2, 13, 99, 0, 0,
+        ];
+        let limits = Limits::default();
+        let err = execute(&program, &[0, 0], limits).unwrap_err();
+        assert!(matches!(err, IntcodeError::Overflow(_)));
+        let message = Err(err.to_string());
+        assert_eq!(part1(&program, limits), message);
+        assert_eq!(part2(&program, limits), message);
+        assert_eq!(part2_threaded(&program, limits), message);
     }
 }
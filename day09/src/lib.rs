@@ -1,13 +1,25 @@
-use intcode::{execute, SimpleInputOutput};
+use intcode::limits::Limits;
+use intcode::{execute_limited, SimpleInputOutput};
 
-pub fn part1(program: &[i64]) -> Option<i64> {
-    let mut prog_io = SimpleInputOutput::new(&[1]);
-    execute(program, &mut prog_io);
-    prog_io.get_output().iter().last().cloned()
+pub fn part1(program: &[i64], limits: Limits) -> Result<i64, String> {
+    run_boost(program, 1, limits)
 }
 
-pub fn part2(program: &[i64]) -> Option<i64> {
-    let mut prog_io = SimpleInputOutput::new(&[2]);
-    execute(program, &mut prog_io);
-    prog_io.get_output().iter().last().cloned()
+pub fn part2(program: &[i64], limits: Limits) -> Result<i64, String> {
+    run_boost(program, 2, limits)
+}
+
+fn run_boost(
+    program: &[i64],
+    mode: i64,
+    limits: Limits,
+) -> Result<i64, String> {
+    let mut prog_io = SimpleInputOutput::new(&[mode]);
+    execute_limited(program, &mut prog_io, limits)
+        .map_err(|err| err.to_string())?;
+    prog_io
+        .get_output()
+        .last()
+        .cloned()
+        .ok_or_else(|| "program produced no output".to_string())
 }
@@ -1,5 +1,6 @@
 use clap::{crate_description, App, Arg};
 use day09::{part1, part2};
+use intcode::limits::Limits;
 use std::fs::read_to_string;
 use std::num::ParseIntError;
 use std::process::exit;
@@ -12,25 +13,25 @@ fn main() {
                 .required(true)
                 .index(1),
         )
+        .args(&Limits::args())
         .get_matches();
 
     println!(crate_description!());
     let program = read_input(args.value_of("INPUT").unwrap());
-    if let Some(result) = part1(&program) {
-        println!("Part 1: {}", result);
-    } else {
-        println!("Part 1: Program failed");
+    let limits = Limits::from_matches(&args);
+    match part1(&program, limits) {
+        Ok(result) => println!("Part 1: {}", result),
+        Err(err) => println!("Part 1: Program failed: {}", err),
     }
-    if let Some(result) = part2(&program) {
-        println!("Part 2: {}", result);
-    } else {
-        println!("Part 2: Program failed");
+    match part2(&program, limits) {
+        Ok(result) => println!("Part 2: {}", result),
+        Err(err) => println!("Part 2: Program failed: {}", err),
     }
 }
 
 fn read_input(filename: &str) -> Vec<i64> {
     let input = read_to_string(filename).unwrap_or_else(|err| {
-        println!("Failed to read file '{}': {}", filename, err.to_string());
+        println!("Failed to read file '{}': {}", filename, err);
         exit(3);
     });
     input
@@ -38,7 +39,7 @@ fn read_input(filename: &str) -> Vec<i64> {
         .map(|s| s.trim().parse())
         .collect::<Result<_, _>>()
         .unwrap_or_else(|err: ParseIntError| {
-            println!("Failed to parse input: {}", err.to_string());
+            println!("Failed to parse input: {}", err);
             exit(3);
         })
 }
@@ -1,4 +1,5 @@
-use intcode::{execute, InputOutput};
+use intcode::limits::Limits;
+use intcode::{execute_limited, InputOutput};
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use Color::*;
@@ -196,15 +197,19 @@ impl InputOutput for PaintRobot {
     }
 }
 
-pub fn part1(program: &[i64]) -> Option<usize> {
+pub fn part1(program: &[i64], limits: Limits) -> Result<usize, String> {
     let mut robot = PaintRobot::default();
-    execute(program, &mut robot)?;
-    Some(robot.count_painted_panels())
+    execute_limited(program, &mut robot, limits)
+        .map_err(|err| err.to_string())?;
+    Ok(robot.count_painted_panels())
 }
 
-pub fn part2(program: &[i64]) -> Option<String> {
+pub fn part2(program: &[i64], limits: Limits) -> Result<String, String> {
     let mut robot = PaintRobot::default();
     robot.paint_current_panel(White);
-    execute(program, &mut robot)?;
-    robot.display_panels()
+    execute_limited(program, &mut robot, limits)
+        .map_err(|err| err.to_string())?;
+    robot
+        .display_panels()
+        .ok_or_else(|| "no panels were painted".to_string())
 }
@@ -1,4 +1,5 @@
-use intcode::{execute, InputOutput};
+use intcode::limits::Limits;
+use intcode::{execute_limited, InputOutput, IntcodeError};
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::thread::sleep;
@@ -156,13 +157,18 @@ impl InputOutput for Game {
     }
 }
 
-pub fn part1(program: &[i64]) -> Option<(usize)> {
+pub fn part1(program: &[i64], limits: Limits) -> Result<usize, IntcodeError> {
     let mut game = Game::default();
-    execute(program, &mut game)?;
-    Some(game.count_block_tiles())
+    execute_limited(program, &mut game, limits)?;
+    Ok(game.count_block_tiles())
 }
 
-pub fn part2(program: &[i64], display: bool, frametime: u64) -> Option<i64> {
+pub fn part2(
+    program: &[i64],
+    display: bool,
+    frametime: u64,
+    limits: Limits,
+) -> Result<i64, IntcodeError> {
     let hacked_prog = [2]
         .iter()
         .chain(program[1..].iter())
@@ -171,7 +177,7 @@ pub fn part2(program: &[i64], display: bool, frametime: u64) -> Option<i64> {
     let mut game = Game::default();
     game.set_display(display);
     game.set_frametime(frametime);
-    execute(&hacked_prog, &mut game)?;
+    execute_limited(&hacked_prog, &mut game, limits)?;
     game.display();
-    Some(game.score)
+    Ok(game.score)
 }
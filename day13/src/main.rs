@@ -1,5 +1,6 @@
 use clap::{crate_description, value_t_or_exit, App, Arg};
 use day13::{part1, part2};
+use intcode::limits::Limits;
 use std::fs::read_to_string;
 use std::num::ParseIntError;
 use std::process::exit;
@@ -26,27 +27,27 @@ fn main() {
                 .takes_value(true)
                 .default_value("15"),
         )
+        .args(&Limits::args())
         .get_matches();
 
     println!(crate_description!());
     let program = read_input(args.value_of("INPUT").unwrap());
+    let limits = Limits::from_matches(&args);
     let display = args.is_present("DISPLAY");
     let frametime = value_t_or_exit!(args.value_of("FRAME_TIME"), u64);
-    if let Some(count) = part1(&program) {
-        println!("Part 1: {}", count);
-    } else {
-        println!("Part 1: Program failed");
+    match part1(&program, limits) {
+        Ok(count) => println!("Part 1: {}", count),
+        Err(err) => println!("Part 1: Program failed: {}", err),
     }
-    if let Some(score) = part2(&program, display, frametime) {
-        println!("Part 2: {}", score);
-    } else {
-        println!("Part 2: Program failed");
+    match part2(&program, display, frametime, limits) {
+        Ok(score) => println!("Part 2: {}", score),
+        Err(err) => println!("Part 2: Program failed: {}", err),
     }
 }
 
 fn read_input(filename: &str) -> Vec<i64> {
     let input = read_to_string(filename).unwrap_or_else(|err| {
-        println!("Failed to read file '{}': {}", filename, err.to_string());
+        println!("Failed to read file '{}': {}", filename, err);
         exit(3);
     });
     input
@@ -54,7 +55,7 @@ fn read_input(filename: &str) -> Vec<i64> {
         .map(|s| s.trim().parse())
         .collect::<Result<_, _>>()
         .unwrap_or_else(|err: ParseIntError| {
-            println!("Failed to parse input: {}", err.to_string());
+            println!("Failed to parse input: {}", err);
             exit(3);
         })
 }
@@ -1,4 +1,7 @@
-use intcode::{execute, InputOutput};
+use intcode::limits::Limits;
+use intcode::{execute_limited, InputOutput, IntcodeError};
+use intcode2::memory::CowMemory;
+use intcode2::{IntcodeComputer, SimpleInputOutput};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::convert::TryFrom;
 use std::thread::sleep;
@@ -68,7 +71,7 @@ impl Position {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Area {
     Wall,
     Empty,
@@ -106,6 +109,7 @@ pub struct MappingDroid {
     position: Position,
     frametime: u64,
     display: bool,
+    limits: Limits,
 }
 
 impl MappingDroid {
@@ -114,9 +118,54 @@ impl MappingDroid {
         self.frametime = frametime;
     }
 
-    pub fn scan(&mut self, program: &[i64]) -> Option<()> {
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn scan(&mut self, program: &[i64]) -> Result<(), IntcodeError> {
         self.area.entry(self.origin).or_insert(Area::Empty);
-        execute(program, self)
+        let limits = self.limits;
+        execute_limited(program, self, limits)
+    }
+
+    /// Maps the area with a breadth-first search, forking the droid's
+    /// computer for every step into an unexplored position instead of
+    /// walking back and forth with a single one
+    pub fn scan_by_forking(&mut self, program: &[i64]) -> Result<(), String> {
+        self.area.entry(self.origin).or_insert(Area::Empty);
+        let mut computer =
+            IntcodeComputer::<CowMemory>::with_memory(program, true);
+        computer.set_limits(self.limits);
+        let mut droid_io = SimpleInputOutput::default();
+        computer.run(&mut droid_io).map_err(|err| err.to_string())?;
+
+        let mut pending = VecDeque::new();
+        pending.push_back((self.origin, computer));
+        while let Some((position, computer)) = pending.pop_front() {
+            for &direction in &[North, South, West, East] {
+                let next_position = position.go(direction);
+                if self.area.contains_key(&next_position) {
+                    continue;
+                }
+                let mut fork = computer.clone();
+                let mut droid_io =
+                    SimpleInputOutput::new(&[i64::from(direction)]);
+                if fork.run(&mut droid_io).map_err(|err| err.to_string())? {
+                    return Err("droid program halted".to_string());
+                }
+                let area = match droid_io.get_output().as_slice() {
+                    [value] => Area::try_from(*value)?,
+                    _ => return Err("invalid droid output".to_string()),
+                };
+                self.area.insert(next_position, area);
+                self.position = next_position;
+                self.display();
+                if area != Area::Wall {
+                    pending.push_back((next_position, fork));
+                }
+            }
+        }
+        Ok(())
     }
 
     fn get_oxygen_system_position(&self) -> Option<Position> {
@@ -240,3 +289,30 @@ impl InputOutput for MappingDroid {
         Some(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+
+    #[test]
+    fn forking_scan() {
+        let filename = concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt");
+        let program = read_to_string(filename)
+            .unwrap()
+            .trim()
+            .split(',')
+            .map(|value| value.parse().unwrap())
+            .collect::<Vec<i64>>();
+        let mut walking = MappingDroid::default();
+        walking.scan(&program).unwrap();
+        let mut forking = MappingDroid::default();
+        forking.scan_by_forking(&program).unwrap();
+
+        assert_eq!(forking.area, walking.area);
+        for droid in &[walking, forking] {
+            assert_eq!(droid.distance_to_oxygen_system(), Some(262));
+            assert_eq!(droid.time_to_fill_area_with_oxygen(), Some(314));
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use clap::{crate_description, value_t_or_exit, App, Arg};
 use day15::MappingDroid;
+use intcode::limits::Limits;
 use std::fs::read_to_string;
 use std::num::ParseIntError;
 use std::process::exit;
@@ -26,17 +27,31 @@ fn main() {
                 .takes_value(true)
                 .default_value("15"),
         )
+        .arg(
+            Arg::with_name("FORK")
+                .help("Map the area by forking the droid's computer")
+                .short("f")
+                .long("fork"),
+        )
+        .args(&Limits::args())
         .get_matches();
 
     println!(crate_description!());
     let program = read_input(args.value_of("INPUT").unwrap());
+    let limits = Limits::from_matches(&args);
     let display = args.is_present("DISPLAY");
     let frametime = value_t_or_exit!(args.value_of("FRAME_TIME"), u64);
 
     let mut droid = MappingDroid::default();
     droid.set_display(display, frametime);
-    if droid.scan(&program).is_none() {
-        println!("Failed failed to scan area");
+    droid.set_limits(limits);
+    let result = if args.is_present("FORK") {
+        droid.scan_by_forking(&program)
+    } else {
+        droid.scan(&program).map_err(|err| err.to_string())
+    };
+    if let Err(err) = result {
+        println!("Failed to scan area: {}", err);
         exit(4);
     }
 
@@ -54,7 +69,7 @@ fn main() {
 
 fn read_input(filename: &str) -> Vec<i64> {
     let input = read_to_string(filename).unwrap_or_else(|err| {
-        println!("Failed to read file '{}': {}", filename, err.to_string());
+        println!("Failed to read file '{}': {}", filename, err);
         exit(2);
     });
     input
@@ -62,7 +77,7 @@ fn read_input(filename: &str) -> Vec<i64> {
         .map(|s| s.trim().parse())
         .collect::<Result<_, _>>()
         .unwrap_or_else(|err: ParseIntError| {
-            println!("Failed to parse input: {}", err.to_string());
+            println!("Failed to parse input: {}", err);
             exit(3);
         })
 }
@@ -1,10 +1,11 @@
-use intcode::{execute, InputOutput};
+use intcode::ascii::AsciiInputOutput;
+use intcode::limits::Limits;
+use intcode::{execute_limited, IntcodeError};
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::io::stdout;
 use Direction::*;
 
-const NEW_LINE: char = '\n';
-
 #[derive(Clone)]
 pub enum Movement {
     TurnLeft,
@@ -27,13 +28,6 @@ struct Position {
 }
 
 impl Position {
-    fn next_line(&self) -> Self {
-        Self {
-            pos_x: 0,
-            pos_y: self.pos_y + 1,
-        }
-    }
-
     fn go(&self, direction: Direction) -> Self {
         match direction {
             North => Self {
@@ -83,9 +77,9 @@ impl TryFrom<char> for Area {
 
 #[derive(Default)]
 pub struct ScaffoldScanner {
-    position: Position,
     area: HashMap<Position, Area>,
     video_feed: bool,
+    limits: Limits,
 }
 
 impl ScaffoldScanner {
@@ -93,9 +87,32 @@ impl ScaffoldScanner {
         self.video_feed = video_feed;
     }
 
-    pub fn scan(&mut self, program: &[i64]) -> Option<i64> {
-        execute(program, self)?;
-        Some(self.sum_alignment_params())
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn scan(&mut self, program: &[i64]) -> Result<i64, IntcodeError> {
+        let mut camera = AsciiInputOutput::new();
+        if self.video_feed {
+            camera.set_mirror(stdout());
+        }
+        execute_limited(program, &mut camera, self.limits)?;
+        self.map_area(camera.lines());
+        Ok(self.sum_alignment_params())
+    }
+
+    fn map_area(&mut self, lines: &[String]) {
+        for (pos_y, line) in lines.iter().enumerate() {
+            for (pos_x, ch) in line.chars().enumerate() {
+                let position = Position {
+                    pos_x: pos_x as i64,
+                    pos_y: pos_y as i64,
+                };
+                if let Ok(area) = Area::try_from(ch) {
+                    self.area.insert(position, area);
+                }
+            }
+        }
     }
 
     fn sum_alignment_params(&self) -> i64 {
@@ -128,32 +145,10 @@ impl ScaffoldScanner {
     }
 }
 
-impl InputOutput for ScaffoldScanner {
-    fn provide_input(&mut self) -> Option<i64> {
-        None
-    }
-
-    fn take_output(&mut self, value: i64) -> Option<()> {
-        let character = char::from(u8::try_from(value).ok()?);
-        if self.video_feed {
-            print!("{}", character);
-        }
-        if character == NEW_LINE {
-            self.position = self.position.next_line();
-        } else {
-            let area = Area::try_from(character).ok()?;
-            self.area.insert(self.position, area);
-            self.position = self.position.go(East);
-        }
-        None
-    }
-}
-
 #[derive(Default)]
 pub struct ScaffoldWalker {
-    movements: Vec<char>,
-    dust: Option<i64>,
     video_feed: bool,
+    limits: Limits,
 }
 
 impl ScaffoldWalker {
@@ -161,52 +156,39 @@ impl ScaffoldWalker {
         self.video_feed = video_feed;
     }
 
-    pub fn walk(&mut self, program: &[i64], path: &[Movement]) -> Option<i64> {
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn walk(
+        &mut self,
+        program: &[i64],
+        path: &[Movement],
+    ) -> Result<i64, String> {
         let altered_prog = [2]
             .iter()
             .chain(program[1..].iter())
             .copied()
             .collect::<Vec<_>>();
-        self.process_path(path);
-        execute(&altered_prog, self)?;
-        self.dust
-    }
-
-    fn process_path(&mut self, _path: &[Movement]) {
-        // TODO: convert and compress path
-        let compressed = "A,B,A,C,A,B,C,A,B,C\n\
-                          R,12,R,4,R,10,R,12\n\
-                          R,6,L,8,R,10\n\
-                          L,8,R,4,R,4,R,6\n";
-
-        // Movements are stored in reverse order
-        self.movements.push(NEW_LINE);
-        if self.video_feed {
-            self.movements.push('y');
-        } else {
-            self.movements.push('n');
-        }
-
-        self.movements.extend(compressed.chars().rev());
-    }
-}
-
-impl InputOutput for ScaffoldWalker {
-    fn provide_input(&mut self) -> Option<i64> {
-        let character = self.movements.pop()?;
+        let mut robot = AsciiInputOutput::new();
         if self.video_feed {
-            print!("{}", character);
+            robot.set_mirror(stdout());
         }
-        Some(character as i64)
+        robot.push_str(&self.process_path(path));
+        robot.push_line(if self.video_feed { "y" } else { "n" });
+        execute_limited(&altered_prog, &mut robot, self.limits)
+            .map_err(|err| err.to_string())?;
+        robot
+            .last_value()
+            .ok_or_else(|| "program produced no dust report".to_string())
     }
 
-    fn take_output(&mut self, value: i64) -> Option<()> {
-        if self.video_feed {
-            if let Ok(ascii_code) = u8::try_from(value) {
-                print!("{}", char::from(ascii_code));
-            }
-        }
-        self.dust = Some(value);
-        Some(())
+    fn process_path(&self, _path: &[Movement]) -> String {
+        // TODO: convert and compress path
+        "A,B,A,C,A,B,C,A,B,C\n\
+         R,12,R,4,R,10,R,12\n\
+         R,6,L,8,R,10\n\
+         L,8,R,4,R,4,R,6\n"
+            .to_string()
     }
 }
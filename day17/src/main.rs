@@ -1,5 +1,6 @@
 use clap::{crate_description, App, Arg};
 use day17::{ScaffoldScanner, ScaffoldWalker};
+use intcode::limits::Limits;
 use std::fs::read_to_string;
 use std::num::ParseIntError;
 use std::process::exit;
@@ -18,34 +19,38 @@ fn main() {
                 .short("v")
                 .long("video-feed"),
         )
+        .args(&Limits::args())
         .get_matches();
 
     println!(crate_description!());
     let program = read_input(args.value_of("INPUT").unwrap());
+    let limits = Limits::from_matches(&args);
     let video_feed = args.is_present("VIDEO_FEED");
 
     let mut scanner = ScaffoldScanner::default();
     scanner.set_video_feed(video_feed);
-    if let Some(alignment_params) = scanner.scan(&program) {
-        println!("Part 1: {}", alignment_params);
-    } else {
-        println!("Failed failed to scan area");
-        exit(4);
+    scanner.set_limits(limits);
+    match scanner.scan(&program) {
+        Ok(alignment_params) => println!("Part 1: {}", alignment_params),
+        Err(err) => {
+            println!("Failed to scan area: {}", err);
+            exit(4);
+        }
     }
 
     let path = scanner.build_scaffold_path();
     let mut walker = ScaffoldWalker::default();
     walker.set_video_feed(video_feed);
-    if let Some(dust) = walker.walk(&program, &path) {
-        println!("Part 2: {}", dust);
-    } else {
-        println!("Part 2: Failed to walk the scaffold");
+    walker.set_limits(limits);
+    match walker.walk(&program, &path) {
+        Ok(dust) => println!("Part 2: {}", dust),
+        Err(err) => println!("Part 2: Failed to walk the scaffold: {}", err),
     }
 }
 
 fn read_input(filename: &str) -> Vec<i64> {
     let input = read_to_string(filename).unwrap_or_else(|err| {
-        println!("Failed to read file '{}': {}", filename, err.to_string());
+        println!("Failed to read file '{}': {}", filename, err);
         exit(2);
     });
     input
@@ -53,7 +58,7 @@ fn read_input(filename: &str) -> Vec<i64> {
         .map(|s| s.trim().parse())
         .collect::<Result<_, _>>()
         .unwrap_or_else(|err: ParseIntError| {
-            println!("Failed to parse input: {}", err.to_string());
+            println!("Failed to parse input: {}", err);
             exit(3);
         })
 }
@@ -0,0 +1,21 @@
+use intcode2::transpile::transpile;
+use std::env;
+use std::fs::{read_to_string, write};
+use std::path::Path;
+
+/// Transpiles the drone program in `input.txt` to Rust, for `--native`
+fn main() {
+    println!("cargo:rerun-if-changed=input.txt");
+    let input = read_to_string("input.txt").expect("failed to read input");
+    let program = input
+        .split(',')
+        .map(|s| s.trim().parse())
+        .collect::<Result<Vec<i64>, _>>()
+        .expect("failed to parse input");
+    let out_dir = env::var("OUT_DIR").unwrap();
+    write(
+        Path::new(&out_dir).join("drone.rs"),
+        transpile(&program, "run"),
+    )
+    .expect("failed to write drone program");
+}
@@ -1,9 +1,19 @@
-use intcode::{execute, SimpleInputOutput};
+use intcode::limits::Limits;
+use intcode::profile::Profiler;
+use intcode::{execute_limited, execute_traced_limited, SimpleInputOutput};
 use std::convert::TryFrom;
 
+/// The drone program in `input.txt`, transpiled to Rust at build time
+mod drone {
+    include!(concat!(env!("OUT_DIR"), "/drone.rs"));
+}
+
 pub struct BeamScanner<'a> {
     program: &'a [i64],
     program_io: SimpleInputOutput,
+    limits: Limits,
+    profiler: Option<Profiler>,
+    native: bool,
 }
 
 impl<'a> BeamScanner<'a> {
@@ -11,10 +21,43 @@ impl<'a> BeamScanner<'a> {
         Self {
             program,
             program_io: SimpleInputOutput::default(),
+            limits: Limits::default(),
+            profiler: None,
+            native: false,
+        }
+    }
+
+    /// Runs the drone program compiled in from `input.txt` instead of
+    /// interpreting it, which ignores the limits and profiling; fails if
+    /// that's not the program being scanned with
+    pub fn enable_native(&mut self) -> Result<(), String> {
+        if self.program != &drone::PROGRAM[..] {
+            return Err("the drone program compiled in is different".into());
         }
+        self.native = true;
+        Ok(())
+    }
+
+    /// Limits for each run of the drone program
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Profiles every run of the drone program from now on
+    pub fn enable_profiling(&mut self) {
+        self.profiler.get_or_insert_with(Profiler::default);
+    }
+
+    /// All runs of the drone program since profiling was enabled
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
     }
 
-    pub fn scan_and_count(&mut self, dim_x: i64, dim_y: i64) -> Option<i64> {
+    pub fn scan_and_count(
+        &mut self,
+        dim_x: i64,
+        dim_y: i64,
+    ) -> Result<i64, String> {
         let mut count = 0;
         let mut row_start = 0;
         let mut row_end = 1;
@@ -44,10 +87,10 @@ impl<'a> BeamScanner<'a> {
 
             count += row_end - row_start;
         }
-        Some(count)
+        Ok(count)
     }
 
-    pub fn find_fit(&mut self, dim_x: i64, dim_y: i64) -> Option<i64> {
+    pub fn find_fit(&mut self, dim_x: i64, dim_y: i64) -> Result<i64, String> {
         let mut row_ends = Vec::new();
         let mut row_start = 0;
         let mut row_end = 1;
@@ -73,7 +116,7 @@ impl<'a> BeamScanner<'a> {
             if let Ok(top_row) = usize::try_from(pos_y - dim_y + 1) {
                 if let Some(top_end) = row_ends.get(top_row) {
                     if *top_end >= row_start + dim_x {
-                        return Some(row_start * 10_000 + (pos_y - dim_y + 1));
+                        return Ok(row_start * 10_000 + (pos_y - dim_y + 1));
                     }
                 }
             }
@@ -87,16 +130,47 @@ impl<'a> BeamScanner<'a> {
             }
             row_ends.push(row_end);
         }
-        None
+        Err("no fit found".to_string())
+    }
+
+    fn is_pulled(&mut self, pos_x: i64, pos_y: i64) -> Result<bool, String> {
+        let output = if self.native {
+            let mut native_io =
+                intcode2::SimpleInputOutput::new(&[pos_x, pos_y]);
+            drone::run(&mut native_io).map_err(|err| err.to_string())?;
+            native_io.get_output().pop()
+        } else {
+            self.interpret(pos_x, pos_y)?
+        };
+        match output {
+            Some(0) => Ok(false),
+            Some(1) => Ok(true),
+            Some(value) => Err(format!("invalid drone output {}", value)),
+            None => Err("drone produced no output".to_string()),
+        }
     }
 
-    fn is_pulled(&mut self, pos_x: i64, pos_y: i64) -> Option<bool> {
+    fn interpret(
+        &mut self,
+        pos_x: i64,
+        pos_y: i64,
+    ) -> Result<Option<i64>, String> {
         self.program_io.add_input(&[pos_x, pos_y]);
-        execute(self.program, &mut self.program_io)?;
-        match self.program_io.get_output().pop()? {
-            0 => Some(false),
-            1 => Some(true),
-            _ => None,
+        match &mut self.profiler {
+            Some(profiler) => {
+                profiler.start_run();
+                execute_traced_limited(
+                    self.program,
+                    &mut self.program_io,
+                    profiler,
+                    self.limits,
+                )
+            }
+            None => {
+                execute_limited(self.program, &mut self.program_io, self.limits)
+            }
         }
+        .map_err(|err| err.to_string())?;
+        Ok(self.program_io.get_output().pop())
     }
 }
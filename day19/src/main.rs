@@ -1,5 +1,6 @@
 use clap::{crate_description, App, Arg};
 use day19::BeamScanner;
+use intcode::limits::Limits;
 use std::fs::read_to_string;
 use std::num::ParseIntError;
 use std::process::exit;
@@ -12,30 +13,62 @@ fn main() {
                 .required(true)
                 .index(1),
         )
+        .arg(
+            Arg::with_name("PROFILE")
+                .help("Profile the drone program")
+                .short("p")
+                .long("profile"),
+        )
+        .args(&Limits::args())
+        .arg(
+            Arg::with_name("NATIVE")
+                .help("Run the drone program compiled in from input.txt")
+                .short("n")
+                .long("native")
+                .conflicts_with_all(&["PROFILE", "MAX_STEPS", "DETECT_LOOPS"]),
+        )
         .get_matches();
 
     println!(crate_description!());
     let program = read_input(args.value_of("INPUT").unwrap());
+    let limits = Limits::from_matches(&args);
     let mut scanner = BeamScanner::new(&program);
+    scanner.set_limits(limits);
+    if args.is_present("PROFILE") {
+        scanner.enable_profiling();
+    }
+    if args.is_present("NATIVE") {
+        if let Err(err) = scanner.enable_native() {
+            println!("Can't run natively: {}", err);
+            exit(5);
+        }
+    }
+
+    match scanner.scan_and_count(50, 50) {
+        Ok(result) => println!("Part 1: {}", result),
+        Err(err) => {
+            println!("Failed to scan area: {}", err);
+            exit(4);
+        }
+    }
 
-    if let Some(result) = scanner.scan_and_count(50, 50) {
-        println!("Part 1: {}", result);
-    } else {
-        println!("Failed failed to scan area");
-        exit(4);
+    match scanner.find_fit(100, 100) {
+        Ok(result) => println!("Part 2: {}", result),
+        Err(err) => {
+            println!("Failed to scan area: {}", err);
+            exit(4);
+        }
     }
 
-    if let Some(result) = scanner.find_fit(100, 100) {
-        println!("Part 2: {}", result);
-    } else {
-        println!("Failed failed to scan area");
-        exit(4);
+    if let Some(profiler) = scanner.profiler() {
+        println!("\n{}", profiler.hotspot_report(20));
+        print!("{}", profiler.block_report());
     }
 }
 
 fn read_input(filename: &str) -> Vec<i64> {
     let input = read_to_string(filename).unwrap_or_else(|err| {
-        println!("Failed to read file '{}': {}", filename, err.to_string());
+        println!("Failed to read file '{}': {}", filename, err);
         exit(2);
     });
     input
@@ -43,7 +76,7 @@ fn read_input(filename: &str) -> Vec<i64> {
         .map(|s| s.trim().parse())
         .collect::<Result<_, _>>()
         .unwrap_or_else(|err: ParseIntError| {
-            println!("Failed to parse input: {}", err.to_string());
+            println!("Failed to parse input: {}", err);
             exit(3);
         })
 }
@@ -1,51 +1,31 @@
-use intcode2::{InputOutput, IntcodeComputer};
-use std::convert::TryFrom;
+use intcode2::ascii::AsciiInputOutput;
+use intcode2::limits::Limits;
+use intcode2::{IntcodeComputer, IntcodeError};
+use std::io::stdout;
 
-struct DroidInputOutput {
+fn run_script(
+    program: &[i64],
+    script: &str,
     video_feed: bool,
-    script: Vec<char>,
-    damage: i64,
-}
-
-impl DroidInputOutput {
-    fn new(springscript: &str, video_feed: bool) -> Self {
-        Self {
-            video_feed,
-            script: springscript.chars().rev().collect(),
-            damage: 0,
-        }
-    }
-}
-
-impl InputOutput for DroidInputOutput {
-    fn provide_input(&mut self) -> Option<i64> {
-        let ch = self.script.pop()?;
-        if self.video_feed {
-            print!("{}", ch);
-        }
-        Some(ch as i64)
+    limits: Limits,
+) -> Result<i64, IntcodeError> {
+    let mut droid_io = AsciiInputOutput::new();
+    if video_feed {
+        droid_io.set_mirror(stdout());
     }
-
-    fn take_output(&mut self, value: i64) -> Option<()> {
-        if let Ok(ascii) = u8::try_from(value) {
-            if self.video_feed {
-                print!("{}", char::from(ascii));
-            }
-        } else {
-            self.damage = value;
-        }
-        Some(())
-    }
-}
-
-fn run_script(program: &[i64], script: &str, video_feed: bool) -> Option<i64> {
-    let mut droid_io = DroidInputOutput::new(script, video_feed);
+    droid_io.push_str(script);
     let mut droid = IntcodeComputer::new(program, true);
+    droid.set_limits(limits);
     droid.run(&mut droid_io)?;
-    Some(droid_io.damage)
+    // A droid that falls into space reports no damage
+    Ok(droid_io.last_value().unwrap_or(0))
 }
 
-pub fn part1(program: &[i64], video_feed: bool) -> Option<i64> {
+pub fn part1(
+    program: &[i64],
+    video_feed: bool,
+    limits: Limits,
+) -> Result<i64, IntcodeError> {
     // (NOT A) OR ((NOT B OR NOT C) AND D)
     let springscript = "NOT A J\n\
                         NOT B T\n\
@@ -55,10 +35,14 @@ pub fn part1(program: &[i64], video_feed: bool) -> Option<i64> {
                         AND D T\n\
                         OR T J\n\
                         WALK\n";
-    run_script(program, springscript, video_feed)
+    run_script(program, springscript, video_feed, limits)
 }
 
-pub fn part2(program: &[i64], video_feed: bool) -> Option<i64> {
+pub fn part2(
+    program: &[i64],
+    video_feed: bool,
+    limits: Limits,
+) -> Result<i64, IntcodeError> {
     // ((NOT B OR NOT C) AND D AND (E OR H)) OR (NOT A)
     //
     // Which is equivalent to:
@@ -76,5 +60,5 @@ pub fn part2(program: &[i64], video_feed: bool) -> Option<i64> {
                         NOT A T\n\
                         OR T J\n\
                         RUN\n";
-    run_script(program, springscript, video_feed)
+    run_script(program, springscript, video_feed, limits)
 }
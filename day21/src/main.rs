@@ -1,5 +1,6 @@
 use clap::{crate_description, App, Arg};
 use day21::{part1, part2};
+use intcode2::limits::Limits;
 use std::fs::read_to_string;
 use std::num::ParseIntError;
 use std::process::exit;
@@ -18,30 +19,34 @@ fn main() {
                 .short("v")
                 .long("video-feed"),
         )
+        .args(&Limits::args())
         .get_matches();
 
     println!(crate_description!());
     let program = read_input(args.value_of("INPUT").unwrap());
+    let limits = Limits::from_matches(&args);
     let video_feed = args.is_present("VIDEO_FEED");
 
-    if let Some(damage) = part1(&program, video_feed) {
-        println!("Part 1: {}", damage);
-    } else {
-        println!("Springdroid program failed");
-        exit(4);
+    match part1(&program, video_feed, limits) {
+        Ok(damage) => println!("Part 1: {}", damage),
+        Err(err) => {
+            println!("Springdroid program failed: {}", err);
+            exit(4);
+        }
     }
 
-    if let Some(damage) = part2(&program, video_feed) {
-        println!("Part 2: {}", damage);
-    } else {
-        println!("Springdroid program failed");
-        exit(4);
+    match part2(&program, video_feed, limits) {
+        Ok(damage) => println!("Part 2: {}", damage),
+        Err(err) => {
+            println!("Springdroid program failed: {}", err);
+            exit(4);
+        }
     }
 }
 
 fn read_input(filename: &str) -> Vec<i64> {
     let input = read_to_string(filename).unwrap_or_else(|err| {
-        println!("Failed to read file '{}': {}", filename, err.to_string());
+        println!("Failed to read file '{}': {}", filename, err);
         exit(2);
     });
     input
@@ -49,7 +54,7 @@ fn read_input(filename: &str) -> Vec<i64> {
         .map(|s| s.trim().parse())
         .collect::<Result<_, _>>()
         .unwrap_or_else(|err: ParseIntError| {
-            println!("Failed to parse input: {}", err.to_string());
+            println!("Failed to parse input: {}", err);
             exit(3);
         })
 }
@@ -1,8 +1,18 @@
-use intcode2::{InputOutput, IntcodeComputer};
+use intcode2::channel::spawn_with;
+use intcode2::limits::Limits;
+use intcode2::{InputOutput, IntcodeComputer, IntcodeError};
 use std::collections::VecDeque;
 use std::convert::TryFrom;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{
+    channel, Receiver, RecvTimeoutError, Sender, TryRecvError,
+};
+use std::sync::Arc;
+use std::thread::{yield_now, JoinHandle};
+use std::time::Duration;
 
 const NAT_ADDR: i64 = 255;
+const NETWORK_SIZE: usize = 50;
 
 type Address = i64;
 type Packet = (i64, i64);
@@ -20,8 +30,9 @@ struct NicInputOutput {
 }
 
 impl Computer {
-    fn new(address: Address, nic_program: &[i64]) -> Self {
-        let nic = IntcodeComputer::new(nic_program, true);
+    fn new(address: Address, nic_program: &[i64], limits: Limits) -> Self {
+        let mut nic = IntcodeComputer::new(nic_program, true);
+        nic.set_limits(limits);
         let nic_io = NicInputOutput::new(address);
         Self { nic, nic_io }
     }
@@ -32,7 +43,10 @@ impl Computer {
         self.nic_io.incoming.push_back(value_y);
     }
 
-    fn send(&mut self, message_queue: &mut MessageQueue) -> Option<()> {
+    fn send(
+        &mut self,
+        message_queue: &mut MessageQueue,
+    ) -> Result<(), IntcodeError> {
         self.nic_io.incoming.push_back(-1);
         self.nic.run(&mut self.nic_io)?;
         for messsage in self.nic_io.outgoing.as_slice().chunks(3) {
@@ -41,7 +55,7 @@ impl Computer {
             message_queue.push_back((dest_address, packet));
         }
         self.nic_io.outgoing.clear();
-        Some(())
+        Ok(())
     }
 }
 
@@ -63,14 +77,19 @@ impl InputOutput for NicInputOutput {
     }
 }
 
-pub fn run_network(nic_program: &[i64]) -> Option<(i64, i64)> {
+pub fn run_network(
+    nic_program: &[i64],
+    limits: Limits,
+) -> Result<(i64, i64), String> {
     let mut message_queue = MessageQueue::new();
     let mut computers = Vec::new();
 
-    for address in 0..50 {
+    for address in 0..NETWORK_SIZE as i64 {
         // Start each computer with a unique address
-        let mut computer = Computer::new(address, nic_program);
-        computer.send(&mut message_queue)?;
+        let mut computer = Computer::new(address, nic_program, limits);
+        computer
+            .send(&mut message_queue)
+            .map_err(|err| err.to_string())?;
         computers.push(computer);
     }
 
@@ -87,24 +106,232 @@ pub fn run_network(nic_program: &[i64]) -> Option<(i64, i64)> {
                 }
                 last_nat_packet = Some(packet);
             } else {
-                let computer_address = usize::try_from(dest_address).ok()?;
+                let computer_address =
+                    usize::try_from(dest_address).map_err(|_| {
+                        format!("invalid destination address {}", dest_address)
+                    })?;
                 if let Some(computer) = computers.get_mut(computer_address) {
                     computer.receive(packet);
-                    computer.send(&mut message_queue)?;
+                    computer
+                        .send(&mut message_queue)
+                        .map_err(|err| err.to_string())?;
                 }
             }
         }
         // All computers are now idle: first check
         // we have a NAT packet to resume activity
-        last_nat_packet?;
+        last_nat_packet.ok_or_else(|| {
+            "network is idle without a NAT packet".to_string()
+        })?;
         if last_resume_packet == last_nat_packet {
             // Found first repeated resume package
             let (_, part1) = first_nat_packet.unwrap();
             let (_, part2) = last_resume_packet.unwrap();
-            return Some((part1, part2));
+            return Ok((part1, part2));
         }
         // Send last NAT packet to address 0 to resume activity
         message_queue.push_back((0, last_nat_packet.unwrap()));
         last_resume_packet = last_nat_packet;
     }
 }
+
+/// What the router needs to know to tell when the network is idle
+struct Activity {
+    // Computers that kept finding no packets since they last did any I/O
+    idle: Vec<AtomicBool>,
+    // Packets sent but not yet read in full
+    pending: AtomicUsize,
+}
+
+impl Activity {
+    fn new(size: usize) -> Self {
+        Self {
+            idle: (0..size).map(|_| AtomicBool::new(false)).collect(),
+            pending: AtomicUsize::new(0),
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        self.pending.load(Ordering::SeqCst) == 0
+            && self.idle.iter().all(|idle| idle.load(Ordering::SeqCst))
+    }
+}
+
+/// A network card on its own thread: packets arrive from the router on a
+/// channel and the packets sent go back to it
+struct ThreadedNic {
+    address: usize,
+    booted: bool,
+    incoming: Receiver<i64>,
+    read_half: bool,
+    empty_reads: u32,
+    outgoing: Vec<i64>,
+    router: Sender<Message>,
+    activity: Arc<Activity>,
+}
+
+impl ThreadedNic {
+    fn busy(&mut self) {
+        self.empty_reads = 0;
+        self.activity.idle[self.address].store(false, Ordering::SeqCst);
+    }
+}
+
+impl InputOutput for ThreadedNic {
+    fn provide_input(&mut self) -> Option<i64> {
+        if !self.booted {
+            self.booted = true;
+            return Some(self.address as i64);
+        }
+        match self.incoming.try_recv() {
+            Ok(value) => {
+                self.busy();
+                self.read_half = !self.read_half;
+                if !self.read_half {
+                    self.activity.pending.fetch_sub(1, Ordering::SeqCst);
+                }
+                Some(value)
+            }
+            // The router shut the network down
+            Err(TryRecvError::Disconnected) => None,
+            Err(TryRecvError::Empty) => {
+                // Finding no packets twice in a row means the computer went
+                // through a whole round without sending anything
+                self.empty_reads += 1;
+                if self.empty_reads >= 2 {
+                    self.activity.idle[self.address]
+                        .store(true, Ordering::SeqCst);
+                }
+                yield_now();
+                Some(-1)
+            }
+        }
+    }
+
+    fn take_output(&mut self, value: i64) -> Option<()> {
+        self.busy();
+        self.outgoing.push(value);
+        if let [dest_address, value_x, value_y] = self.outgoing[..] {
+            self.outgoing.clear();
+            self.activity.pending.fetch_add(1, Ordering::SeqCst);
+            self.router.send((dest_address, (value_x, value_y))).ok()?;
+        }
+        Some(())
+    }
+}
+
+/// Same as `run_network`, but with every computer running on its own thread
+/// and the router on this one; the network counts as idle once no packets
+/// are in flight and every computer keeps finding no packets to read
+pub fn run_network_threaded(
+    nic_program: &[i64],
+    limits: Limits,
+) -> Result<(i64, i64), String> {
+    let activity = Arc::new(Activity::new(NETWORK_SIZE));
+    let (router, messages) = channel();
+    let mut computers = Vec::new();
+    let mut handles = Vec::new();
+
+    for address in 0..NETWORK_SIZE {
+        let (sender, incoming) = channel();
+        let mut nic = IntcodeComputer::new(nic_program, true);
+        nic.set_limits(limits);
+        let nic_io = ThreadedNic {
+            address,
+            booted: false,
+            incoming,
+            read_half: false,
+            empty_reads: 0,
+            outgoing: Vec::new(),
+            router: router.clone(),
+            activity: Arc::clone(&activity),
+        };
+        handles.push(spawn_with(nic, nic_io));
+        computers.push(sender);
+    }
+    drop(router);
+
+    let result = route(&messages, &computers, &handles, &activity);
+
+    // Computers stop once they find their channel closed
+    drop(computers);
+    let mut failure = None;
+    for handle in handles {
+        let error = match handle.join() {
+            Ok(Ok(_)) => continue,
+            Ok(Err(err)) => err.to_string(),
+            Err(_) => "network computer panicked".to_string(),
+        };
+        failure.get_or_insert(error);
+    }
+    match failure {
+        Some(err) if result.is_err() => Err(err),
+        _ => result,
+    }
+}
+
+fn route(
+    messages: &Receiver<Message>,
+    computers: &[Sender<i64>],
+    handles: &[JoinHandle<Result<bool, IntcodeError>>],
+    activity: &Activity,
+) -> Result<(i64, i64), String> {
+    let send = |address: usize, (value_x, value_y): Packet| {
+        computers[address]
+            .send(value_x)
+            .and_then(|_| computers[address].send(value_y))
+            .map_err(|_| format!("computer {} stopped", address))
+    };
+
+    let mut first_nat_packet = None;
+    let mut last_nat_packet = None;
+    let mut last_resume_packet = None;
+
+    loop {
+        match messages.recv_timeout(Duration::from_millis(1)) {
+            Ok((dest_address, packet)) => {
+                let computer_address =
+                    usize::try_from(dest_address).map_err(|_| {
+                        format!("invalid destination address {}", dest_address)
+                    })?;
+                if computer_address < NETWORK_SIZE {
+                    send(computer_address, packet)?;
+                    continue;
+                }
+                // Packets to unknown addresses are dropped like in
+                // `run_network`, so they're no longer in flight
+                activity.pending.fetch_sub(1, Ordering::SeqCst);
+                if dest_address == NAT_ADDR {
+                    first_nat_packet.get_or_insert(packet);
+                    last_nat_packet = Some(packet);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if let Some(address) =
+                    handles.iter().position(|handle| handle.is_finished())
+                {
+                    return Err(format!("computer {} stopped", address));
+                }
+                if !activity.is_idle() {
+                    continue;
+                }
+                let packet = last_nat_packet.ok_or_else(|| {
+                    "network is idle without a NAT packet".to_string()
+                })?;
+                if last_resume_packet == Some(packet) {
+                    // Found first repeated resume package
+                    let (_, part1) = first_nat_packet.unwrap();
+                    let (_, part2) = packet;
+                    return Ok((part1, part2));
+                }
+                // Send last NAT packet to address 0 to resume activity
+                activity.pending.fetch_add(1, Ordering::SeqCst);
+                send(0, packet)?;
+                last_resume_packet = Some(packet);
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err("all computers stopped".to_string());
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use clap::{crate_description, App, Arg};
-use day23::run_network;
+use day23::{run_network, run_network_threaded};
+use intcode2::limits::Limits;
 use std::fs::read_to_string;
 use std::num::ParseIntError;
 use std::process::exit;
@@ -12,22 +13,38 @@ fn main() {
                 .required(true)
                 .index(1),
         )
+        .args(&Limits::args())
+        .arg(
+            Arg::with_name("THREADED")
+                .help("Run each computer on its own thread")
+                .short("t")
+                .long("threaded"),
+        )
         .get_matches();
 
     println!(crate_description!());
     let program = read_input(args.value_of("INPUT").unwrap());
-    if let Some((part1, part2)) = run_network(&program) {
-        println!("Part 1: {}", part1);
-        println!("Part 2: {}", part2);
+    let limits = Limits::from_matches(&args);
+    let result = if args.is_present("THREADED") {
+        run_network_threaded(&program, limits)
     } else {
-        println!("Network software failed");
-        exit(4);
+        run_network(&program, limits)
+    };
+    match result {
+        Ok((part1, part2)) => {
+            println!("Part 1: {}", part1);
+            println!("Part 2: {}", part2);
+        }
+        Err(err) => {
+            println!("Network software failed: {}", err);
+            exit(4);
+        }
     }
 }
 
 fn read_input(filename: &str) -> Vec<i64> {
     let input = read_to_string(filename).unwrap_or_else(|err| {
-        println!("Failed to read file '{}': {}", filename, err.to_string());
+        println!("Failed to read file '{}': {}", filename, err);
         exit(2);
     });
     input
@@ -35,7 +52,7 @@ fn read_input(filename: &str) -> Vec<i64> {
         .map(|s| s.trim().parse())
         .collect::<Result<_, _>>()
         .unwrap_or_else(|err: ParseIntError| {
-            println!("Failed to parse input: {}", err.to_string());
+            println!("Failed to parse input: {}", err);
             exit(3);
         })
 }
@@ -0,0 +1,546 @@
+use intcode2::memory::PagedMemory;
+use intcode2::trace::{TraceEntry, Tracer};
+use intcode2::IntcodeComputer;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::str::FromStr;
+
+/// Instructions each engine may execute per case, with loop detection on
+pub const MAX_STEPS: u64 = 1_000;
+
+/// Programs don't grow past this many values
+const MAX_LEN: usize = 64;
+
+const OPCODES: [i64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+const EDGE_VALUES: [i64; 10] = [
+    0,
+    1,
+    -1,
+    2,
+    99,
+    1 << 21,
+    1 << 40,
+    i64::MAX,
+    i64::MIN,
+    i64::MIN + 1,
+];
+
+/// Xorshift generator, good enough to pick mutations and reproducible from
+/// its seed
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..bound`
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound.max(1) as u64) as usize
+    }
+
+    fn chance(&mut self, one_in: usize) -> bool {
+        self.below(one_in) == 0
+    }
+
+    /// An instruction with random parameter modes, now and then invalid ones
+    fn instr(&mut self) -> i64 {
+        let opcode = OPCODES[self.below(OPCODES.len())];
+        let modes = (0..3).fold(0, |modes, _| {
+            let mode = if self.chance(20) { 3 } else { self.below(3) };
+            modes * 10 + mode as i64
+        });
+        modes * 100 + opcode
+    }
+
+    /// A parameter, mostly a position near the program
+    fn value(&mut self, len: usize) -> i64 {
+        match self.below(4) {
+            0 => EDGE_VALUES[self.below(EDGE_VALUES.len())],
+            1 => self.next_u64() as i64,
+            _ => self.below(len + 8) as i64 - 2,
+        }
+    }
+}
+
+/// A program and the inputs it is given
+#[derive(Clone, Debug, Default, Hash, PartialEq)]
+pub struct Case {
+    pub program: Vec<i64>,
+    pub inputs: Vec<i64>,
+}
+
+impl Case {
+    pub fn random(rng: &mut Rng) -> Self {
+        let mut case = Self::default();
+        for _ in 0..=rng.below(12) {
+            insert_instr(&mut case.program, rng);
+        }
+        case.inputs = (0..rng.below(4)).map(|_| rng.value(8)).collect();
+        case
+    }
+
+    /// A copy with a few random changes
+    pub fn mutate(&self, rng: &mut Rng) -> Self {
+        let mut case = self.clone();
+        for _ in 0..=rng.below(3) {
+            let len = case.program.len();
+            match rng.below(6) {
+                0 if len > 0 => case.program[rng.below(len)] = rng.value(len),
+                1 if len > 0 => case.program[rng.below(len)] = rng.instr(),
+                2 if len > 0 => {
+                    let pos = rng.below(len);
+                    let delta = rng.below(5) as i64 - 2;
+                    case.program[pos] = case.program[pos].wrapping_add(delta);
+                }
+                3 if len > 1 => {
+                    let start = rng.below(len);
+                    let end = (start + 1 + rng.below(4)).min(len);
+                    case.program.drain(start..end);
+                }
+                4 if !case.inputs.is_empty() && rng.chance(2) => {
+                    let pos = rng.below(case.inputs.len());
+                    case.inputs[pos] = rng.value(len);
+                }
+                4 => case.inputs.push(rng.value(len)),
+                _ if len < MAX_LEN => insert_instr(&mut case.program, rng),
+                _ => {}
+            }
+        }
+        case
+    }
+
+    fn id(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+fn insert_instr(program: &mut Vec<i64>, rng: &mut Rng) {
+    let pos = rng.below(program.len() + 1);
+    let instr = rng.instr();
+    let params = match instr % 100 {
+        1 | 2 | 7 | 8 => 3,
+        5 | 6 => 2,
+        3 | 4 | 9 => 1,
+        _ => 0,
+    };
+    let len = program.len() + params + 1;
+    let mut words = vec![instr];
+    words.extend((0..params).map(|_| rng.value(len)));
+    program.splice(pos..pos, words);
+}
+
+fn join(values: &[i64]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The program on the first line and the inputs on the second, as comma
+/// separated values
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", join(&self.program))?;
+        writeln!(f, "{}", join(&self.inputs))
+    }
+}
+
+/// Reads what `Display` writes, skipping lines that start with `#`
+impl FromStr for Case {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().filter(|line| !line.starts_with('#'));
+        let mut values = || -> Result<Vec<i64>, String> {
+            lines
+                .next()
+                .unwrap_or("")
+                .split(',')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| value.parse().map_err(|err| format!("{}", err)))
+                .collect()
+        };
+        Ok(Self {
+            program: values()?,
+            inputs: values()?,
+        })
+    }
+}
+
+/// How a run ended and what it output
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome {
+    pub result: Result<(), String>,
+    pub outputs: Vec<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Failure {
+    /// An engine panicked, with its message
+    Panic(&'static str, String),
+    /// The engines disagree; `intcode` first, then `intcode2`
+    Mismatch(Outcome, Outcome),
+}
+
+impl Failure {
+    /// Failures of the same kind are assumed to have the same cause
+    pub fn kind(&self) -> String {
+        match self {
+            Failure::Panic(engine, message) => {
+                format!("{} panicked: {}", engine, message)
+            }
+            Failure::Mismatch(_, _) => "engines disagree".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Panic(_, _) => write!(f, "{}", self.kind()),
+            Failure::Mismatch(outcome1, outcome2) => write!(
+                f,
+                "engines disagree: intcode {:?}, intcode2 {:?}",
+                outcome1, outcome2
+            ),
+        }
+    }
+}
+
+/// Each instruction with its parameter modes, the opcode executed before it
+/// and whether it was jumped back to, as a rough measure of what a case
+/// exercised
+#[derive(Default)]
+pub struct Coverage {
+    edges: HashSet<u64>,
+    prev_instr: (usize, i64),
+}
+
+impl Tracer for Coverage {
+    fn record(&mut self, entry: TraceEntry) {
+        let (prev_ptr, prev_opcode) = self.prev_instr;
+        let backward = entry.instr_ptr <= prev_ptr;
+        let mut hasher = DefaultHasher::new();
+        (prev_opcode, entry.instr, backward).hash(&mut hasher);
+        self.edges.insert(hasher.finish());
+        self.prev_instr = (entry.instr_ptr, entry.instr % 100);
+    }
+}
+
+impl Coverage {
+    /// Counts how a run ended as coverage too, ignoring where it was and
+    /// any numbers in the message
+    fn add_outcome(&mut self, outcome: &str) {
+        let mut hasher = DefaultHasher::new();
+        for word in outcome
+            .split_whitespace()
+            .take_while(|&word| word != "at")
+            .filter(|word| word.parse::<i64>().is_err())
+        {
+            word.hash(&mut hasher);
+        }
+        self.edges.insert(hasher.finish());
+    }
+}
+
+fn catch_panic<T>(
+    engine: &'static str,
+    run: impl FnOnce() -> T,
+) -> Result<T, Failure> {
+    catch_unwind(AssertUnwindSafe(run)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Failure::Panic(engine, message)
+    })
+}
+
+fn run_intcode(case: &Case) -> Outcome {
+    let limits = intcode::limits::Limits {
+        max_steps: Some(MAX_STEPS),
+        detect_loops: true,
+    };
+    let mut io = intcode::SimpleInputOutput::new(&case.inputs);
+    let result = intcode::execute_limited(&case.program, &mut io, limits);
+    Outcome {
+        result: result.map_err(|err| err.to_string()),
+        outputs: io.get_output(),
+    }
+}
+
+/// Runs the case on both engines with 128-bit words, where sums and products
+/// can reach addresses past what an `i64` holds; only panics matter here
+fn run_wide(case: &Case) {
+    let program = case.program.iter().map(|&value| i128::from(value));
+    let program = program.collect::<Vec<_>>();
+    let inputs = case.inputs.iter().map(|&value| i128::from(value));
+    let inputs = inputs.collect::<Vec<_>>();
+    let limits = intcode::limits::Limits {
+        max_steps: Some(MAX_STEPS),
+        detect_loops: true,
+    };
+    let mut io = intcode::SimpleInputOutput::new(&inputs);
+    let _ = intcode::execute_limited(&program, &mut io, limits);
+    let mut computer =
+        IntcodeComputer::<PagedMemory<i128>>::with_memory(&program, false);
+    computer.set_limits(limits);
+    let _ = computer.run(&mut intcode2::SimpleInputOutput::new(&inputs));
+}
+
+fn run_intcode2(case: &Case, coverage: &mut Coverage) -> Outcome {
+    let mut computer = IntcodeComputer::new(&case.program, false);
+    computer.set_limits(intcode2::limits::Limits {
+        max_steps: Some(MAX_STEPS),
+        detect_loops: true,
+    });
+    let mut io = intcode2::SimpleInputOutput::new(&case.inputs);
+    let result = computer.run_traced(&mut io, coverage);
+    Outcome {
+        result: result.map(|_| ()).map_err(|err| err.to_string()),
+        outputs: io.get_output(),
+    }
+}
+
+/// Runs the case on both engines, which must neither panic nor disagree,
+/// adding what it covered to `coverage`
+pub fn check_covered(
+    case: &Case,
+    coverage: &mut Coverage,
+) -> Result<(), Failure> {
+    let result = catch_panic("wide words", || run_wide(case))
+        .and_then(|()| catch_panic("intcode", || run_intcode(case)))
+        .and_then(|outcome1| {
+            let outcome2 =
+                catch_panic("intcode2", || run_intcode2(case, coverage))?;
+            if let Err(err) = &outcome2.result {
+                coverage.add_outcome(err);
+            }
+            if outcome1 == outcome2 {
+                Ok(())
+            } else {
+                Err(Failure::Mismatch(outcome1, outcome2))
+            }
+        });
+    if let Err(failure) = &result {
+        coverage.add_outcome(&failure.kind());
+    }
+    result
+}
+
+pub fn check(case: &Case) -> Result<(), Failure> {
+    check_covered(case, &mut Coverage::default())
+}
+
+/// Shrinks a failing case for as long as it keeps failing the same way
+pub fn minimize(case: &Case, failure: &Failure) -> Case {
+    let kind = failure.kind();
+    shrink(case, |case| {
+        check(case).err().map(|failure| failure.kind()) == Some(kind.clone())
+    })
+}
+
+/// Drops runs of values, then moves the remaining values towards zero, over
+/// and over until nothing changes
+fn shrink(case: &Case, mut fails: impl FnMut(&Case) -> bool) -> Case {
+    let mut best = case.clone();
+    loop {
+        let before = best.clone();
+        for &is_program in &[true, false] {
+            best = drop_values(best, is_program, &mut fails);
+            best = simplify_values(best, is_program, &mut fails);
+        }
+        if best == before {
+            return best;
+        }
+    }
+}
+
+fn values(case: &mut Case, is_program: bool) -> &mut Vec<i64> {
+    if is_program {
+        &mut case.program
+    } else {
+        &mut case.inputs
+    }
+}
+
+fn drop_values(
+    mut case: Case,
+    is_program: bool,
+    fails: &mut impl FnMut(&Case) -> bool,
+) -> Case {
+    let mut len = values(&mut case, is_program).len();
+    while len > 0 {
+        let mut start = 0;
+        while start + len <= values(&mut case, is_program).len() {
+            let mut shorter = case.clone();
+            values(&mut shorter, is_program).drain(start..start + len);
+            if fails(&shorter) {
+                case = shorter;
+            } else {
+                start += len;
+            }
+        }
+        len /= 2;
+    }
+    case
+}
+
+fn simplify_values(
+    mut case: Case,
+    is_program: bool,
+    fails: &mut impl FnMut(&Case) -> bool,
+) -> Case {
+    for pos in 0..values(&mut case, is_program).len() {
+        let value = values(&mut case, is_program)[pos];
+        let with_value = |magnitude: u64| {
+            let mut simpler = case.clone();
+            let magnitude = magnitude as i64;
+            values(&mut simpler, is_program)[pos] =
+                if value < 0 { -magnitude } else { magnitude };
+            simpler
+        };
+        // Binary search for the smallest magnitude that still fails, as if
+        // all larger ones did
+        let (mut passing, mut failing) = (0, value.unsigned_abs());
+        if fails(&with_value(0)) {
+            failing = 0;
+        }
+        while failing > passing + 1 {
+            let middle = passing + (failing - passing) / 2;
+            if fails(&with_value(middle)) {
+                failing = middle;
+            } else {
+                passing = middle;
+            }
+        }
+        if failing < value.unsigned_abs() {
+            case = with_value(failing);
+        }
+    }
+    case
+}
+
+/// Keeps the cases that reached new coverage and derives new cases from them
+pub struct Fuzzer {
+    rng: Rng,
+    corpus: Vec<Case>,
+    coverage: Coverage,
+    runs: u64,
+}
+
+impl Fuzzer {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            corpus: Vec::new(),
+            coverage: Coverage::default(),
+            runs: 0,
+        }
+    }
+
+    /// Checks one new case, returning it if it failed
+    pub fn run_once(&mut self) -> Option<(Case, Failure)> {
+        let case = if self.corpus.is_empty() || self.rng.chance(10) {
+            Case::random(&mut self.rng)
+        } else {
+            let parent = self.rng.below(self.corpus.len());
+            self.corpus[parent].mutate(&mut self.rng)
+        };
+        self.runs += 1;
+        let covered = self.coverage.edges.len();
+        let result = check_covered(&case, &mut self.coverage);
+        if self.coverage.edges.len() > covered {
+            self.corpus.push(case.clone());
+        }
+        result.err().map(|failure| (case, failure))
+    }
+
+    pub fn runs(&self) -> u64 {
+        self.runs
+    }
+
+    pub fn corpus_len(&self) -> usize {
+        self.corpus.len()
+    }
+
+    pub fn coverage(&self) -> usize {
+        self.coverage.edges.len()
+    }
+}
+
+/// File name for a minimized failing case
+pub fn regression_name(case: &Case) -> String {
+    format!("crash-{:016x}.txt", case.id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{read_dir, read_to_string};
+    use std::path::Path;
+
+    #[test]
+    fn regressions() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("regressions");
+        for entry in read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let case: Case = read_to_string(&path).unwrap().parse().unwrap();
+            assert_eq!(check(&case), Ok(()), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn fuzz() {
+        let mut fuzzer = Fuzzer::new(2019);
+        for _ in 0..2_000 {
+            if let Some((case, failure)) = fuzzer.run_once() {
+                let case = minimize(&case, &failure);
+                panic!("{}\n{}", failure, case);
+            }
+        }
+        assert!(fuzzer.corpus_len() > 10);
+    }
+
+    #[test]
+    fn round_trip() {
+        let case = Case {
+            program: vec![3, 0, 4, 0, 99],
+            inputs: vec![],
+        };
+        assert_eq!(case.to_string(), "3,0,4,0,99\n\n");
+        let text = "# a comment\n1,-2, 3\n4\n";
+        let case = text.parse::<Case>().unwrap();
+        assert_eq!((case.program, case.inputs), (vec![1, -2, 3], vec![4]));
+    }
+
+    #[test]
+    fn shrinks() {
+        let case = Case {
+            program: vec![1101, 5, 6, 7, 3, 9, 4, 9, 99, 0],
+            inputs: vec![123, 4],
+        };
+        let case = shrink(&case, |case| {
+            case.program.contains(&99) && case.inputs.iter().any(|&v| v > 10)
+        });
+        assert_eq!((case.program, case.inputs), (vec![99], vec![11]));
+    }
+}
@@ -0,0 +1,83 @@
+use clap::{crate_description, value_t_or_exit, App, Arg};
+use fuzz::{check, minimize, regression_name, Fuzzer};
+use std::collections::HashSet;
+use std::fs::{create_dir_all, write};
+use std::panic::set_hook;
+use std::path::Path;
+use std::process::exit;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    let args = App::new(crate_description!())
+        .after_help(
+            "Overflow checks are only on in debug builds, so run it without \
+             --release to catch arithmetic overflow.",
+        )
+        .arg(
+            Arg::with_name("RUNS")
+                .help("Number of cases to run")
+                .short("n")
+                .long("runs")
+                .default_value("10000"),
+        )
+        .arg(
+            Arg::with_name("SEED")
+                .help("Seed for the random generator, by default the time")
+                .short("s")
+                .long("seed")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .help("Directory to save minimized failing cases in")
+                .short("o")
+                .long("output")
+                .default_value(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/regressions"
+                )),
+        )
+        .get_matches();
+
+    let runs = value_t_or_exit!(args, "RUNS", u64);
+    let seed = if args.is_present("SEED") {
+        value_t_or_exit!(args, "SEED", u64)
+    } else {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |time| time.as_nanos() as u64)
+    };
+    let output = Path::new(args.value_of("OUTPUT").unwrap());
+
+    // Panics are caught and reported as failures
+    set_hook(Box::new(|_| {}));
+    println!("Seed: {}", seed);
+    let mut fuzzer = Fuzzer::new(seed);
+    let mut seen = HashSet::new();
+    while fuzzer.runs() < runs {
+        let (case, failure) = match fuzzer.run_once() {
+            Some(failed) => failed,
+            None => continue,
+        };
+        if !seen.insert(failure.kind()) {
+            continue;
+        }
+        let case = minimize(&case, &failure);
+        let failure = check(&case).unwrap_err();
+        println!("\n{}\n{}", failure, case);
+        let path = output.join(regression_name(&case));
+        let contents = format!("# {}\n{}", failure.kind(), case);
+        if let Err(err) = create_dir_all(output).and(write(&path, contents)) {
+            println!("Failed to write '{}': {}", path.display(), err);
+            exit(2);
+        }
+        println!("Saved as {}", path.display());
+    }
+    println!(
+        "\n{} runs, {} cases in corpus, {} edges covered, {} failures",
+        fuzzer.runs(),
+        fuzzer.corpus_len(),
+        fuzzer.coverage(),
+        seen.len()
+    );
+}
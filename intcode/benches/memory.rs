@@ -0,0 +1,42 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use intcode::memory::{HashMemory, Memory, PagedMemory};
+use intcode::{execute_with_memory, SimpleInputOutput};
+use std::fs::read_to_string;
+
+const QUINE: [i64; 16] = [
+    109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+];
+
+fn boost_program() -> Vec<i64> {
+    let filename = concat!(env!("CARGO_MANIFEST_DIR"), "/../day09/input.txt");
+    read_to_string(filename)
+        .unwrap()
+        .trim()
+        .split(',')
+        .map(|value| value.parse().unwrap())
+        .collect()
+}
+
+fn run<M: Memory<Word = i64>>(program: &[i64], input: &[i64]) -> Vec<i64> {
+    let mut test_io = SimpleInputOutput::new(input);
+    execute_with_memory::<M>(program, &mut test_io).unwrap();
+    test_io.get_output()
+}
+
+fn bench_backends(c: &mut Criterion) {
+    let boost = boost_program();
+    let programs = [("quine", &QUINE[..], 0), ("boost", &boost[..], 2)];
+    for (name, program, input) in programs.iter() {
+        let mut group = c.benchmark_group(format!("execute {}", name));
+        group.bench_function("hash", |b| {
+            b.iter(|| run::<HashMemory>(program, &[*input]))
+        });
+        group.bench_function("paged", |b| {
+            b.iter(|| run::<PagedMemory>(program, &[*input]))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_backends);
+criterion_main!(benches);
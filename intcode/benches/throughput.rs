@@ -0,0 +1,51 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use intcode::trace::{TraceEntry, Tracer};
+use intcode::{execute, execute_traced, execute_uncached, SimpleInputOutput};
+use std::fs::read_to_string;
+
+/// Counts executed instructions
+struct Counter(u64);
+
+impl Tracer for Counter {
+    fn record(&mut self, _entry: TraceEntry) {
+        self.0 += 1;
+    }
+}
+
+fn boost_program() -> Vec<i64> {
+    let filename = concat!(env!("CARGO_MANIFEST_DIR"), "/../day09/input.txt");
+    read_to_string(filename)
+        .unwrap()
+        .trim()
+        .split(',')
+        .map(|value| value.parse().unwrap())
+        .collect()
+}
+
+fn bench_boost(c: &mut Criterion) {
+    let program = boost_program();
+    let mut counter = Counter(0);
+    execute_traced(&program, &mut SimpleInputOutput::new(&[2]), &mut counter)
+        .unwrap();
+
+    let mut group = c.benchmark_group("execute boost");
+    group.throughput(Throughput::Elements(counter.0));
+    group.bench_function("part2", |b| {
+        b.iter(|| {
+            let mut test_io = SimpleInputOutput::new(&[2]);
+            execute(&program, &mut test_io).unwrap();
+            test_io.get_output()
+        })
+    });
+    group.bench_function("part2 uncached", |b| {
+        b.iter(|| {
+            let mut test_io = SimpleInputOutput::new(&[2]);
+            execute_uncached(&program, &mut test_io).unwrap();
+            test_io.get_output()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_boost);
+criterion_main!(benches);
@@ -0,0 +1,150 @@
+use crate::InputOutput;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::mem::take;
+
+const NEW_LINE: i64 = b'\n' as i64;
+
+/// Talks to a program in ASCII: queues lines of input, collects the lines it
+/// outputs and sets aside the values outside the ASCII range, which programs
+/// use to report their results
+#[derive(Default)]
+pub struct AsciiInputOutput {
+    input: VecDeque<i64>,
+    lines: Vec<String>,
+    line: String,
+    values: Vec<i64>,
+    mirror: Option<Box<dyn Write + Send>>,
+}
+
+impl AsciiInputOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copies the output, and the input as the program reads it, to `writer`
+    pub fn set_mirror<W: Write + Send + 'static>(&mut self, writer: W) {
+        self.mirror = Some(Box::new(writer));
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        self.input.extend(text.chars().map(|ch| ch as i64));
+    }
+
+    /// Queues the line, ending it with a new line
+    pub fn push_line(&mut self, line: &str) {
+        self.push_str(line);
+        self.input.push_back(NEW_LINE);
+    }
+
+    /// Number of input characters not read yet
+    pub fn pending_input(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Complete lines output so far, without their new line
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn take_lines(&mut self) -> Vec<String> {
+        take(&mut self.lines)
+    }
+
+    /// Output since the last new line, such as a prompt
+    pub fn partial_line(&self) -> &str {
+        &self.line
+    }
+
+    /// Output values outside the ASCII range
+    pub fn values(&self) -> &[i64] {
+        &self.values
+    }
+
+    pub fn last_value(&self) -> Option<i64> {
+        self.values.last().copied()
+    }
+
+    fn echo(&mut self, ch: char) {
+        if let Some(mirror) = &mut self.mirror {
+            // The mirror is only for show, so failing to write is no reason
+            // to stop the program
+            let _ = write!(mirror, "{}", ch).and_then(|_| mirror.flush());
+        }
+    }
+}
+
+fn to_ascii(value: i64) -> Option<char> {
+    Some(value)
+        .filter(|value| (0..128).contains(value))
+        .map(|value| char::from(value as u8))
+}
+
+impl InputOutput for AsciiInputOutput {
+    fn provide_input(&mut self) -> Option<i64> {
+        let value = self.input.pop_front()?;
+        if let Some(ch) = to_ascii(value) {
+            self.echo(ch);
+        }
+        Some(value)
+    }
+
+    fn take_output(&mut self, value: i64) -> Option<()> {
+        match to_ascii(value) {
+            Some('\n') => {
+                self.echo('\n');
+                self.lines.push(take(&mut self.line));
+            }
+            Some(ch) => {
+                self.echo(ch);
+                self.line.push(ch);
+            }
+            None => self.values.push(value),
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execute;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    // Outputs "Hi", reads two characters, outputs their sum and then "!"
+    const GREETER: [i64; 19] = [
+        104, 72, 104, 105, 104, 10, 3, 100, 3, 101, 1, 100, 101, 102, 4, 102,
+        104, 33, 99,
+    ];
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn lines_and_values() {
+        let mirror = SharedBuffer::default();
+        let mut io = AsciiInputOutput::new();
+        io.set_mirror(mirror.clone());
+        io.push_line("ab");
+        assert_eq!(execute(&GREETER, &mut io), Ok(()));
+        assert_eq!(io.lines(), ["Hi"]);
+        assert_eq!(io.partial_line(), "!");
+        assert_eq!(io.values(), [195]);
+        assert_eq!(io.pending_input(), 1);
+        assert_eq!(*mirror.0.lock().unwrap(), b"Hi\nab!");
+
+        assert_eq!(io.take_lines(), ["Hi"]);
+        assert!(io.lines().is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+use crate::memory::Memory;
+use crate::{decode_instr, InstrState};
+
+/// Instructions are only cached at addresses below this
+const CACHE_LIMIT: usize = 1 << 20;
+
+/// Instructions with larger values have modes with more than one digit
+const MAX_CACHED_INSTR: i64 = 100_000;
+
+/// Opcode and modes of decoded instructions keyed by address, packed in a
+/// byte each; the entry for an address must be invalidated whenever memory at
+/// that address is written
+#[derive(Clone, Debug)]
+pub struct InstrCache {
+    decoded: Vec<Option<[u8; 4]>>,
+    limit: usize,
+}
+
+impl Default for InstrCache {
+    fn default() -> Self {
+        Self {
+            decoded: Vec::new(),
+            limit: CACHE_LIMIT,
+        }
+    }
+}
+
+impl InstrCache {
+    /// A cache that decodes every instruction it fetches
+    pub fn disabled() -> Self {
+        Self {
+            decoded: Vec::new(),
+            limit: 0,
+        }
+    }
+
+    /// The instruction at `instr_ptr`, decoded from memory unless it's cached
+    pub fn fetch<M: Memory>(
+        &mut self,
+        memory: &M,
+        instr_ptr: usize,
+        base: i64,
+    ) -> InstrState {
+        if let Some(Some([opcode, mode1, mode2, mode3])) =
+            self.decoded.get(instr_ptr)
+        {
+            let modes =
+                [i64::from(*mode1), i64::from(*mode2), i64::from(*mode3)];
+            let opcode = i64::from(*opcode);
+            return InstrState {
+                instr_ptr,
+                instr: opcode
+                    + modes[0] * 100
+                    + modes[1] * 1_000
+                    + modes[2] * 10_000,
+                opcode,
+                modes,
+                base,
+            };
+        }
+        let state = decode_instr(memory, instr_ptr, base);
+        if instr_ptr < self.limit
+            && (0..MAX_CACHED_INSTR).contains(&state.instr)
+        {
+            if instr_ptr >= self.decoded.len() {
+                self.decoded.resize(instr_ptr + 1, None);
+            }
+            let [mode1, mode2, mode3] = state.modes;
+            self.decoded[instr_ptr] = Some([
+                state.opcode as u8,
+                mode1 as u8,
+                mode2 as u8,
+                mode3 as u8,
+            ]);
+        }
+        state
+    }
+
+    pub fn invalidate(&mut self, addr: usize) {
+        if let Some(entry) = self.decoded.get_mut(addr) {
+            *entry = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::PagedMemory;
+
+    #[test]
+    fn invalidation() {
+        let mut memory = PagedMemory::load(&[1002, 4, 3, 4, 33]);
+        let mut cache = InstrCache::default();
+        assert_eq!(cache.fetch(&memory, 0, 0).opcode, 2);
+        assert_eq!(cache.fetch(&memory, 0, 7).base, 7);
+
+        memory.set(0, 1101);
+        assert_eq!(cache.fetch(&memory, 0, 0).opcode, 2);
+        cache.invalidate(0);
+        let state = cache.fetch(&memory, 0, 0);
+        assert_eq!((state.opcode, state.modes), (1, [1, 1, 0]));
+    }
+}
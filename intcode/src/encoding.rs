@@ -0,0 +1,51 @@
+use std::io::{self, Read};
+
+/// Writes `value` 7 bits per byte, least significant bits first, with the
+/// top bit set on all bytes but the last
+pub fn write_unsigned(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+pub fn write_signed(buffer: &mut Vec<u8>, value: i64) {
+    // Zigzag encoding keeps small negative numbers short
+    write_unsigned(buffer, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+pub fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads one byte, or `None` at the end of the input
+pub fn read_byte<R: Read>(reader: &mut R) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+pub fn read_unsigned<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = read_byte(reader)?
+            .ok_or_else(|| invalid_data("unexpected end of data"))?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("invalid encoded number"))
+}
+
+pub fn read_signed<R: Read>(reader: &mut R) -> io::Result<i64> {
+    let value = read_unsigned(reader)?;
+    Ok(((value >> 1) as i64) ^ -((value & 1) as i64))
+}
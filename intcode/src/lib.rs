@@ -1,104 +1,375 @@
-use std::collections::HashMap;
-use std::convert::TryFrom;
-use std::mem::replace;
+use std::error::Error;
+use std::fmt;
+use std::mem::take;
 
-const ADD_OP: i64 = 1;
-const MULT_OP: i64 = 2;
-const IN_OP: i64 = 3;
-const OUT_OP: i64 = 4;
-const JIT_OP: i64 = 5;
-const JIF_OP: i64 = 6;
-const LT_OP: i64 = 7;
-const EQ_OP: i64 = 8;
-const BASE_OP: i64 = 9;
-const HALT_OP: i64 = 99;
+pub mod ascii;
+pub mod cache;
+pub mod encoding;
+pub mod limits;
+pub mod memory;
+pub mod profile;
+pub mod trace;
+pub mod word;
 
-const POSITION_MODE: i64 = 0;
-const IMMEDIATE_MODE: i64 = 1;
-const RELATIVE_MODE: i64 = 2;
+use cache::InstrCache;
+use limits::{Limits, LoopDetector};
+use memory::{Memory, PagedMemory};
+use trace::{Operands, Tracer};
+use word::{clamp, Word};
 
-type Memory = HashMap<usize, i64>;
+pub const ADD_OP: i64 = 1;
+pub const MULT_OP: i64 = 2;
+pub const IN_OP: i64 = 3;
+pub const OUT_OP: i64 = 4;
+pub const JIT_OP: i64 = 5;
+pub const JIF_OP: i64 = 6;
+pub const LT_OP: i64 = 7;
+pub const EQ_OP: i64 = 8;
+pub const BASE_OP: i64 = 9;
+pub const HALT_OP: i64 = 99;
 
-pub trait InputOutput {
-    fn provide_input(&mut self) -> Option<i64>;
-    fn take_output(&mut self, value: i64) -> Option<()>;
+pub const POSITION_MODE: i64 = 0;
+pub const IMMEDIATE_MODE: i64 = 1;
+pub const RELATIVE_MODE: i64 = 2;
+
+/// Opcode, mnemonic, number of parameters and whether the last one is written
+pub const INSTRUCTIONS: [(i64, &str, usize, bool); 10] = [
+    (ADD_OP, "add", 3, true),
+    (MULT_OP, "mul", 3, true),
+    (IN_OP, "in", 1, true),
+    (OUT_OP, "out", 1, false),
+    (JIT_OP, "jt", 2, false),
+    (JIF_OP, "jf", 2, false),
+    (LT_OP, "lt", 3, true),
+    (EQ_OP, "eq", 3, true),
+    (BASE_OP, "rbo", 1, false),
+    (HALT_OP, "hlt", 0, false),
+];
+
+pub fn instr_info(opcode: i64) -> Option<(&'static str, usize, bool)> {
+    INSTRUCTIONS
+        .iter()
+        .find(|(op, _, _, _)| *op == opcode)
+        .map(|&(_, mnemonic, params, writes)| (mnemonic, params, writes))
+}
+
+pub trait InputOutput<W = i64> {
+    fn provide_input(&mut self) -> Option<W>;
+    fn take_output(&mut self, value: W) -> Option<()>;
+
+    /// Whether the last `None` only means the I/O isn't ready yet, so the
+    /// instruction blocks even if the computer doesn't block on I/O
+    fn is_pending(&self) -> bool {
+        false
+    }
+}
+
+/// The instruction being executed along with the relative base at that point
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InstrState {
+    pub instr_ptr: usize,
+    pub instr: i64,
+    pub opcode: i64,
+    pub modes: [i64; 3],
+    pub base: i64,
+}
+
+impl InstrState {
+    pub fn new(instr_ptr: usize, instr: i64, base: i64) -> Self {
+        let (opcode, mode1, mode2, mode3) = parse_instr(instr);
+        Self {
+            instr_ptr,
+            instr,
+            opcode,
+            modes: [mode1, mode2, mode3],
+            base,
+        }
+    }
+}
+
+impl fmt::Display for InstrState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "address {} (instruction {}, opcode {}, modes {}/{}/{}, \
+             relative base {})",
+            self.instr_ptr,
+            self.instr,
+            self.opcode,
+            self.modes[0],
+            self.modes[1],
+            self.modes[2],
+            self.base
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntcodeError {
+    InvalidOpcode(InstrState),
+    InvalidMode(InstrState, i64),
+    NegativeAddress(InstrState, i64),
+    ImmediateWrite(InstrState),
+    NoInput(InstrState),
+    OutputRejected(InstrState),
+    BudgetExhausted(InstrState, u64),
+    InfiniteLoop(InstrState),
+    Overflow(InstrState),
+    PastEndOfMemory(InstrState),
+}
+
+impl IntcodeError {
+    pub fn state(&self) -> &InstrState {
+        match self {
+            IntcodeError::InvalidOpcode(state)
+            | IntcodeError::InvalidMode(state, _)
+            | IntcodeError::NegativeAddress(state, _)
+            | IntcodeError::ImmediateWrite(state)
+            | IntcodeError::NoInput(state)
+            | IntcodeError::OutputRejected(state)
+            | IntcodeError::BudgetExhausted(state, _)
+            | IntcodeError::InfiniteLoop(state)
+            | IntcodeError::Overflow(state)
+            | IntcodeError::PastEndOfMemory(state) => state,
+        }
+    }
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::InvalidOpcode(state) => {
+                write!(f, "invalid opcode {}", state.opcode)?
+            }
+            IntcodeError::InvalidMode(_, mode) => {
+                write!(f, "invalid parameter mode {}", mode)?
+            }
+            IntcodeError::NegativeAddress(_, addr) => {
+                write!(f, "negative address {}", addr)?
+            }
+            IntcodeError::ImmediateWrite(_) => {
+                write!(f, "write parameter in immediate mode")?
+            }
+            IntcodeError::NoInput(_) => write!(f, "no input available")?,
+            IntcodeError::OutputRejected(_) => {
+                write!(f, "output was not taken")?
+            }
+            IntcodeError::BudgetExhausted(_, max_steps) => {
+                write!(f, "instruction budget of {} exhausted", max_steps)?
+            }
+            IntcodeError::InfiniteLoop(_) => {
+                write!(f, "infinite loop detected")?
+            }
+            IntcodeError::Overflow(_) => write!(f, "value out of range")?,
+            IntcodeError::PastEndOfMemory(_) => {
+                write!(f, "instruction runs past the end of memory")?
+            }
+        }
+        write!(f, " at {}", self.state())
+    }
 }
 
-pub fn execute(program: &[i64], io: &mut dyn InputOutput) -> Option<()> {
+impl Error for IntcodeError {}
+
+/// Runs the program; arithmetic that overflows fails with
+/// `IntcodeError::Overflow`
+pub fn execute(
+    program: &[i64],
+    io: &mut dyn InputOutput,
+) -> Result<(), IntcodeError> {
+    execute_words(program, io)
+}
+
+/// Like `execute`, on words of type `W`
+pub fn execute_words<W: Word>(
+    program: &[W],
+    io: &mut dyn InputOutput<W>,
+) -> Result<(), IntcodeError> {
+    run::<PagedMemory<W>>(
+        program,
+        io,
+        None,
+        Limits::default(),
+        InstrCache::default(),
+    )
+}
+
+/// Like `execute`, decoding every instruction as it's fetched; the baseline
+/// for the instruction cache
+pub fn execute_uncached(
+    program: &[i64],
+    io: &mut dyn InputOutput,
+) -> Result<(), IntcodeError> {
+    run::<PagedMemory>(
+        program,
+        io,
+        None,
+        Limits::default(),
+        InstrCache::disabled(),
+    )
+}
+
+/// Like `execute`, keeping the program memory in the given backend
+pub fn execute_with_memory<M: Memory>(
+    program: &[M::Word],
+    io: &mut dyn InputOutput<M::Word>,
+) -> Result<(), IntcodeError> {
+    run::<M>(program, io, None, Limits::default(), InstrCache::default())
+}
+
+/// Like `execute`, recording every executed instruction in `tracer`
+pub fn execute_traced(
+    program: &[i64],
+    io: &mut dyn InputOutput,
+    tracer: &mut dyn Tracer,
+) -> Result<(), IntcodeError> {
+    run::<PagedMemory>(
+        program,
+        io,
+        Some(tracer),
+        Limits::default(),
+        InstrCache::default(),
+    )
+}
+
+/// Like `execute`, failing when the program runs past the given limits
+pub fn execute_limited<W: Word>(
+    program: &[W],
+    io: &mut dyn InputOutput<W>,
+    limits: Limits,
+) -> Result<(), IntcodeError> {
+    run::<PagedMemory<W>>(program, io, None, limits, InstrCache::default())
+}
+
+/// Like `execute_traced`, failing when the program runs past the given limits
+pub fn execute_traced_limited(
+    program: &[i64],
+    io: &mut dyn InputOutput,
+    tracer: &mut dyn Tracer,
+    limits: Limits,
+) -> Result<(), IntcodeError> {
+    run::<PagedMemory>(program, io, Some(tracer), limits, InstrCache::default())
+}
+
+fn run<M: Memory>(
+    program: &[M::Word],
+    io: &mut dyn InputOutput<M::Word>,
+    mut tracer: Option<&mut dyn Tracer<M::Word>>,
+    limits: Limits,
+    mut cache: InstrCache,
+) -> Result<(), IntcodeError> {
     let mut base = 0;
     let mut instr_ptr = 0;
-    let mut memory = program.iter().cloned().enumerate().collect::<Memory>();
+    let mut memory = M::load(program);
+    let mut steps = 0;
+    let mut loop_detector = LoopDetector::default();
 
     loop {
-        match memory.get(&instr_ptr).map(|&i| parse_instr(i)) {
-            Some((HALT_OP, _, _, _)) => break,
-            Some((ADD_OP, mode1, mode2, mode3)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, base, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, base, mode2)?;
-                let addr = get_addr(&memory, instr_ptr + 3, base, mode3)?;
-                set_value(&mut memory, addr, val1 + val2);
-                instr_ptr += 4;
-            }
-            Some((MULT_OP, mode1, mode2, mode3)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, base, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, base, mode2)?;
-                let addr = get_addr(&memory, instr_ptr + 3, base, mode3)?;
-                set_value(&mut memory, addr, val1 * val2);
-                instr_ptr += 4;
-            }
-            Some((IN_OP, mode, _, _)) => {
-                let addr = get_addr(&memory, instr_ptr + 1, base, mode)?;
-                set_value(&mut memory, addr, io.provide_input()?);
-                instr_ptr += 2;
-            }
-            Some((OUT_OP, mode, _, _)) => {
-                let val = get_value(&memory, instr_ptr + 1, base, mode)?;
-                io.take_output(val);
-                instr_ptr += 2;
+        let state = cache.fetch(&memory, instr_ptr, base);
+        if let Some(max_steps) = limits.max_steps {
+            if steps >= max_steps {
+                return Err(IntcodeError::BudgetExhausted(state, max_steps));
             }
-            Some((JIT_OP, mode1, mode2, _)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, base, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, base, mode2)?;
-                if val1 != 0 {
-                    instr_ptr = usize::try_from(val2).ok()?;
+        }
+        let operands = tracer.as_ref().map(|_| {
+            let (_, params, writes) =
+                instr_info(state.opcode).unwrap_or(("", 0, false));
+            Operands::capture(&memory, &state, (params, writes))
+        });
+        match state.opcode {
+            HALT_OP => {}
+            ADD_OP | MULT_OP => {
+                let val1 = get_value(&memory, &state, 1)?;
+                let val2 = get_value(&memory, &state, 2)?;
+                let addr = get_addr(&memory, &state, 3)?;
+                let result = if state.opcode == ADD_OP {
+                    val1.checked_add(&val2)
                 } else {
-                    instr_ptr += 3;
-                }
+                    val1.checked_mul(&val2)
+                };
+                let value = result.ok_or(IntcodeError::Overflow(state))?;
+                set_value(
+                    &mut memory,
+                    &mut cache,
+                    &mut loop_detector,
+                    addr,
+                    value,
+                );
+                instr_ptr = instr_offset(&state, 4)?;
+            }
+            IN_OP => {
+                let addr = get_addr(&memory, &state, 1)?;
+                let value =
+                    io.provide_input().ok_or(IntcodeError::NoInput(state))?;
+                set_value(
+                    &mut memory,
+                    &mut cache,
+                    &mut loop_detector,
+                    addr,
+                    value,
+                );
+                instr_ptr = instr_offset(&state, 2)?;
             }
-            Some((JIF_OP, mode1, mode2, _)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, base, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, base, mode2)?;
-                if val1 == 0 {
-                    instr_ptr = usize::try_from(val2).ok()?;
+            OUT_OP => {
+                let val = get_value(&memory, &state, 1)?;
+                io.take_output(val);
+                instr_ptr = instr_offset(&state, 2)?;
+            }
+            JIT_OP | JIF_OP => {
+                let val1 = get_value(&memory, &state, 1)?;
+                let val2 = get_value(&memory, &state, 2)?;
+                if (val1 != M::Word::zero()) == (state.opcode == JIT_OP) {
+                    instr_ptr = to_addr(&state, &val2)?;
                 } else {
-                    instr_ptr += 3;
+                    instr_ptr = instr_offset(&state, 3)?;
                 }
             }
-            Some((LT_OP, mode1, mode2, mode3)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, base, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, base, mode2)?;
-                let addr = get_addr(&memory, instr_ptr + 3, base, mode3)?;
-                set_value(&mut memory, addr, i64::from(val1 < val2));
-                instr_ptr += 4;
-            }
-            Some((EQ_OP, mode1, mode2, mode3)) => {
-                let val1 = get_value(&memory, instr_ptr + 1, base, mode1)?;
-                let val2 = get_value(&memory, instr_ptr + 2, base, mode2)?;
-                let addr = get_addr(&memory, instr_ptr + 3, base, mode3)?;
-                set_value(&mut memory, addr, i64::from(val1 == val2));
-                instr_ptr += 4;
+            LT_OP | EQ_OP => {
+                let val1 = get_value(&memory, &state, 1)?;
+                let val2 = get_value(&memory, &state, 2)?;
+                let addr = get_addr(&memory, &state, 3)?;
+                let holds = if state.opcode == LT_OP {
+                    val1 < val2
+                } else {
+                    val1 == val2
+                };
+                set_value(
+                    &mut memory,
+                    &mut cache,
+                    &mut loop_detector,
+                    addr,
+                    M::Word::from_bool(holds),
+                );
+                instr_ptr = instr_offset(&state, 4)?;
             }
-            Some((BASE_OP, mode, _, _)) => {
-                base += get_value(&memory, instr_ptr + 1, base, mode)?;
-                instr_ptr += 2;
+            BASE_OP => {
+                let offset = get_value(&memory, &state, 1)?;
+                base = move_base(&state, &offset)?;
+                instr_ptr = instr_offset(&state, 2)?;
             }
-            _ => {
-                // Invalid instruction
-                return None;
+            _ => return Err(IntcodeError::InvalidOpcode(state)),
+        }
+        if let (Some(tracer), Some(operands)) = (tracer.as_mut(), operands) {
+            tracer.record(operands.into_entry(&memory, &state));
+        }
+        steps += 1;
+        if limits.detect_loops {
+            let backward_jump = (state.opcode == JIT_OP
+                || state.opcode == JIF_OP)
+                && instr_ptr <= state.instr_ptr;
+            if state.opcode == IN_OP || state.opcode == OUT_OP {
+                loop_detector.reset();
+            } else if backward_jump
+                && loop_detector.revisits(&memory, instr_ptr, base)
+            {
+                return Err(IntcodeError::InfiniteLoop(state));
             }
         }
+        if state.opcode == HALT_OP {
+            break;
+        }
     }
-    Some(())
+    Ok(())
 }
 
 fn parse_instr(instr: i64) -> (i64, i64, i64, i64) {
@@ -112,60 +383,161 @@ fn parse_instr(instr: i64) -> (i64, i64, i64, i64) {
     (opcode, mode1, mode2, mode3)
 }
 
-fn set_value(mem: &mut Memory, addr: usize, value: i64) {
-    mem.entry(addr).and_modify(|v| *v = value).or_insert(value);
+/// Decodes the instruction at `instr_ptr`; values that don't fit an `i64`
+/// can't be valid instructions, so they decode to an invalid opcode
+pub fn decode_instr<M: Memory>(
+    memory: &M,
+    instr_ptr: usize,
+    base: i64,
+) -> InstrState {
+    let instr = memory.get(instr_ptr);
+    match instr.to_i64() {
+        Some(instr) => InstrState::new(instr_ptr, instr, base),
+        None => {
+            let instr = clamp(&instr);
+            InstrState {
+                instr_ptr,
+                instr,
+                opcode: instr,
+                modes: [0; 3],
+                base,
+            }
+        }
+    }
+}
+
+fn set_value<M: Memory>(
+    mem: &mut M,
+    cache: &mut InstrCache,
+    loop_detector: &mut LoopDetector<M::Word>,
+    addr: usize,
+    value: M::Word,
+) {
+    loop_detector.record_write(mem, addr, &value);
+    mem.set(addr, value);
+    cache.invalidate(addr);
+}
+
+pub fn to_addr<W: Word>(
+    state: &InstrState,
+    value: &W,
+) -> Result<usize, IntcodeError> {
+    match value.to_usize() {
+        Some(addr) => Ok(addr),
+        None if *value < W::zero() => {
+            Err(IntcodeError::NegativeAddress(*state, clamp(value)))
+        }
+        None => Err(IntcodeError::Overflow(*state)),
+    }
+}
+
+/// The relative base after adding `offset` to it, which must fit in a word
+pub fn move_base<W: Word>(
+    state: &InstrState,
+    offset: &W,
+) -> Result<i64, IntcodeError> {
+    offset
+        .to_i64()
+        .and_then(|offset| state.base.checked_add(offset))
+        .filter(|&base| W::from_i64(base).is_some())
+        .ok_or(IntcodeError::Overflow(*state))
+}
+
+/// The address a relative mode parameter refers to
+fn relative_addr<W: Word>(
+    state: &InstrState,
+    offset: &W,
+) -> Result<usize, IntcodeError> {
+    to_addr(state, &move_base(state, offset)?)
 }
 
-fn get_value(mem: &Memory, addr: usize, base: i64, mode: i64) -> Option<i64> {
-    let pos = match mode {
-        IMMEDIATE_MODE => addr,
-        POSITION_MODE => usize::try_from(*mem.get(&addr)?).ok()?,
-        RELATIVE_MODE => usize::try_from(base + *mem.get(&addr)?).ok()?,
-        _ => return None,
-    };
-    // At this point, the memory position is an usize and hence valid;
-    // if it's not yet set, return a Some(0)
-    mem.get(&pos).cloned().or(Some(0))
+/// The address `offset` positions after the start of the instruction
+pub fn instr_offset(
+    state: &InstrState,
+    offset: usize,
+) -> Result<usize, IntcodeError> {
+    state
+        .instr_ptr
+        .checked_add(offset)
+        .ok_or(IntcodeError::PastEndOfMemory(*state))
 }
 
-fn get_addr(mem: &Memory, addr: usize, base: i64, mode: i64) -> Option<usize> {
-    let value = match mode {
-        POSITION_MODE => *mem.get(&addr)?,
-        RELATIVE_MODE => base + *mem.get(&addr)?,
-        _ => return None,
-    };
-    usize::try_from(value).ok()
+/// The value of a parameter of the instruction
+pub fn get_value<M: Memory>(
+    mem: &M,
+    state: &InstrState,
+    param: usize,
+) -> Result<M::Word, IntcodeError> {
+    Ok(mem.get(get_pos(mem, state, param)?))
 }
 
-#[derive(Default)]
-pub struct SimpleInputOutput {
-    input: Vec<i64>,
-    output: Vec<i64>,
+/// The memory position a parameter's value is read from
+pub fn get_pos<M: Memory>(
+    mem: &M,
+    state: &InstrState,
+    param: usize,
+) -> Result<usize, IntcodeError> {
+    let addr = instr_offset(state, param)?;
+    match state.modes[param - 1] {
+        IMMEDIATE_MODE => Ok(addr),
+        POSITION_MODE => to_addr(state, &mem.get(addr)),
+        RELATIVE_MODE => relative_addr(state, &mem.get(addr)),
+        mode => Err(IntcodeError::InvalidMode(*state, mode)),
+    }
+}
+
+/// The memory position a parameter writes to
+pub fn get_addr<M: Memory>(
+    mem: &M,
+    state: &InstrState,
+    param: usize,
+) -> Result<usize, IntcodeError> {
+    let addr = instr_offset(state, param)?;
+    match state.modes[param - 1] {
+        POSITION_MODE => to_addr(state, &mem.get(addr)),
+        RELATIVE_MODE => relative_addr(state, &mem.get(addr)),
+        IMMEDIATE_MODE => Err(IntcodeError::ImmediateWrite(*state)),
+        mode => Err(IntcodeError::InvalidMode(*state, mode)),
+    }
+}
+
+pub struct SimpleInputOutput<W = i64> {
+    input: Vec<W>,
+    output: Vec<W>,
+}
+
+impl<W> Default for SimpleInputOutput<W> {
+    fn default() -> Self {
+        Self {
+            input: Vec::new(),
+            output: Vec::new(),
+        }
+    }
 }
 
-impl SimpleInputOutput {
-    pub fn new(input_slice: &[i64]) -> Self {
+impl<W: Clone> SimpleInputOutput<W> {
+    pub fn new(input_slice: &[W]) -> Self {
         Self {
-            input: input_slice.iter().rev().copied().collect(),
+            input: input_slice.iter().rev().cloned().collect(),
             output: Vec::new(),
         }
     }
 
-    pub fn add_input(&mut self, input_slice: &[i64]) {
-        self.input.extend(input_slice.iter().rev());
+    pub fn add_input(&mut self, input_slice: &[W]) {
+        self.input.extend(input_slice.iter().rev().cloned());
     }
 
-    pub fn get_output(&mut self) -> Vec<i64> {
-        replace(&mut self.output, Vec::new())
+    pub fn get_output(&mut self) -> Vec<W> {
+        take(&mut self.output)
     }
 }
 
-impl InputOutput for SimpleInputOutput {
-    fn provide_input(&mut self) -> Option<i64> {
+impl<W> InputOutput<W> for SimpleInputOutput<W> {
+    fn provide_input(&mut self) -> Option<W> {
         self.input.pop()
     }
 
-    fn take_output(&mut self, value: i64) -> Option<()> {
+    fn take_output(&mut self, value: W) -> Option<()> {
         self.output.push(value);
         Some(())
     }
@@ -174,10 +546,11 @@ impl InputOutput for SimpleInputOutput {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use memory::HashMemory;
+    use word::{to_words, BigInt};
 
-    #[test]
-    fn samples() {
-        let tests = vec![
+    fn sample_programs() -> Vec<(Vec<i64>, Vec<i64>)> {
+        vec![
             (
                 vec![
                     109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101,
@@ -193,14 +566,202 @@ mod tests {
                 vec![1219070632396864],
             ),
             (vec![104, 1125899906842624, 99], vec![1125899906842624]),
+        ]
+    }
+
+    #[test]
+    fn samples() {
+        for (program, output) in sample_programs() {
+            let mut test_io = SimpleInputOutput::default();
+            assert_eq!(execute(&program, &mut test_io), Ok(()));
+            assert_eq!(test_io.get_output(), output);
+
+            assert_eq!(
+                execute_with_memory::<HashMemory>(&program, &mut test_io),
+                Ok(())
+            );
+            assert_eq!(test_io.get_output(), output);
+        }
+    }
+
+    /// Runs the samples on words of type `W`, expecting the ones that don't
+    /// fit to overflow
+    fn samples_on<W: Word>() {
+        for (program, output) in sample_programs() {
+            let program = match to_words::<W>(&program) {
+                Some(program) => program,
+                // Can't even be loaded
+                None => continue,
+            };
+            let mut test_io = SimpleInputOutput::default();
+            let result = execute_words(&program, &mut test_io);
+            let mut hash_io = SimpleInputOutput::default();
+            let hash_result =
+                execute_with_memory::<HashMemory<W>>(&program, &mut hash_io);
+            assert_eq!(hash_result, result);
+            match to_words::<W>(&output) {
+                Some(output) => {
+                    assert_eq!(result, Ok(()));
+                    assert_eq!(test_io.get_output(), output);
+                    assert_eq!(hash_io.get_output(), output);
+                }
+                None => {
+                    assert!(matches!(result, Err(IntcodeError::Overflow(_))))
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn samples_on_every_word() {
+        samples_on::<i32>();
+        samples_on::<i64>();
+        samples_on::<i128>();
+        samples_on::<BigInt>();
+    }
+
+    #[test]
+    fn self_modifying() {
+        // Turns its first instruction from `out 7` into `out [7]` and runs it
+        // again
+        let program = [
+            104, 7, 1101, 0, 4, 0, 1001, 20, 1, 20, 1008, 20, 2, 21, 1006, 21,
+            0, 99,
+        ];
+        let mut test_io = SimpleInputOutput::default();
+        assert_eq!(execute(&program, &mut test_io), Ok(()));
+        assert_eq!(test_io.get_output(), vec![7, 20]);
+    }
+
+    #[test]
+    fn limits() {
+        let run = |program: &[i64], limits| {
+            execute_limited(program, &mut SimpleInputOutput::default(), limits)
+        };
+        let budget = Limits {
+            max_steps: Some(100),
+            detect_loops: false,
+        };
+        let detector = Limits {
+            max_steps: None,
+            detect_loops: true,
+        };
+        let state = |instr_ptr, instr| InstrState::new(instr_ptr, instr, 0);
+
+        // Jumps to itself
+        let program = [1105, 1, 0];
+        assert_eq!(
+            run(&program, budget),
+            Err(IntcodeError::BudgetExhausted(state(0, 1105), 100))
+        );
+        assert_eq!(
+            run(&program, detector),
+            Err(IntcodeError::InfiniteLoop(state(0, 1105)))
+        );
+
+        // Counts forever, so no state repeats
+        let program = [1001, 7, 1, 7, 1105, 1, 0, 0];
+        assert_eq!(
+            run(&program, budget),
+            Err(IntcodeError::BudgetExhausted(state(0, 1001), 100))
+        );
+
+        // Counts up to 15 and halts
+        let program = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101,
+            0, 99,
+        ];
+        assert_eq!(run(&program, detector), Ok(()));
+        let limits = Limits {
+            max_steps: Some(81),
+            detect_loops: true,
+        };
+        assert_eq!(run(&program, limits), Ok(()));
+        let limits = Limits {
+            max_steps: Some(80),
+            ..limits
+        };
+        assert!(run(&program, limits).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let state =
+            |instr_ptr, instr, base| InstrState::new(instr_ptr, instr, base);
+        let tests = vec![
+            (vec![42], IntcodeError::InvalidOpcode(state(0, 42, 0))),
+            (
+                vec![1101, 1, 2, 5, 12, 0],
+                IntcodeError::InvalidOpcode(state(4, 12, 0)),
+            ),
+            (
+                vec![301, 0, 0, 0, 99],
+                IntcodeError::InvalidMode(state(0, 301, 0), 3),
+            ),
+            (
+                vec![1, -1, 0, 0, 99],
+                IntcodeError::NegativeAddress(state(0, 1, 0), -1),
+            ),
+            (
+                vec![109, -5, 204, 0, 99],
+                IntcodeError::NegativeAddress(state(2, 204, -5), -5),
+            ),
+            (
+                vec![1105, 1, -3],
+                IntcodeError::NegativeAddress(state(0, 1105, 0), -3),
+            ),
+            (
+                vec![11101, 1, 1, 0, 99],
+                IntcodeError::ImmediateWrite(state(0, 11101, 0)),
+            ),
+            (vec![3, 0, 99], IntcodeError::NoInput(state(0, 3, 0))),
         ];
-        for (program, output) in tests {
+        for (program, error) in tests {
             let mut test_io = SimpleInputOutput::default();
-            assert_eq!(execute(&program, &mut test_io), Some(()));
-            assert_eq!(test_io.output, output);
+            assert_eq!(execute(&program, &mut test_io), Err(error));
         }
     }
 
+    #[test]
+    fn traced() {
+        use trace::{IoEvent, TraceEntry};
+
+        let program = [3, 9, 1001, 9, 5, 10, 4, 10, 99, 0, 0];
+        let mut test_io = SimpleInputOutput::new(&[-7]);
+        let mut trace = Vec::new();
+        assert_eq!(execute_traced(&program, &mut test_io, &mut trace), Ok(()));
+        assert_eq!(test_io.output, vec![-2]);
+        let entry =
+            |instr_ptr, instr, params: &[i64], reads: &[i64]| TraceEntry {
+                instr_ptr,
+                instr,
+                base: 0,
+                params: params.to_vec(),
+                reads: reads.to_vec(),
+                write: None,
+                io: None,
+            };
+        assert_eq!(
+            trace,
+            vec![
+                TraceEntry {
+                    write: Some((9, -7)),
+                    io: Some(IoEvent::Input(-7)),
+                    ..entry(0, 3, &[9], &[])
+                },
+                TraceEntry {
+                    write: Some((10, -2)),
+                    ..entry(2, 1001, &[9, 5, 10], &[-7, 5])
+                },
+                TraceEntry {
+                    io: Some(IoEvent::Output(-2)),
+                    ..entry(6, 4, &[10], &[-2])
+                },
+                entry(8, 99, &[], &[]),
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_instr() {
         assert_eq!(parse_instr(0), (0, 0, 0, 0));
@@ -0,0 +1,200 @@
+use crate::memory::Memory;
+use crate::word::Word;
+use clap::{value_t_or_exit, Arg, ArgMatches};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
+
+/// Safeguards against programs that never stop
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Limits {
+    /// Number of instructions a computer may execute before failing
+    pub max_steps: Option<u64>,
+    /// Fail when a backward jump leads to a machine state already seen since
+    /// the last I/O, which can only repeat forever
+    pub detect_loops: bool,
+}
+
+impl Limits {
+    /// Command line options for the limits, read by `from_matches`
+    pub fn args<'a, 'b>() -> [Arg<'a, 'b>; 2] {
+        [
+            Arg::with_name("MAX_STEPS")
+                .help("Stop Intcode programs after this many instructions")
+                .long("max-steps")
+                .takes_value(true),
+            Arg::with_name("DETECT_LOOPS")
+                .help("Stop Intcode programs stuck in an infinite loop")
+                .long("detect-loops"),
+        ]
+    }
+
+    /// The limits set by the options from `args`; exits with an error
+    /// message if the step count isn't a number
+    pub fn from_matches(args: &ArgMatches) -> Self {
+        Self {
+            max_steps: if args.is_present("MAX_STEPS") {
+                Some(value_t_or_exit!(args, "MAX_STEPS", u64))
+            } else {
+                None
+            },
+            detect_loops: args.is_present("DETECT_LOOPS"),
+        }
+    }
+}
+
+/// States remembered before they are all forgotten; loops that go through
+/// more distinct states than this are left to the instruction budget
+const MAX_STATES: usize = 1 << 16;
+
+/// States whose memory is kept to tell a repeat from a hash collision
+const MAX_SNAPSHOTS: usize = 64;
+
+type StateKey = (usize, i64, u64);
+
+/// Remembers machine states at backward jumps; states are fingerprinted by
+/// instruction pointer, relative base and a hash of the memory contents that
+/// is updated on every write
+#[derive(Clone, Debug)]
+pub struct LoopDetector<W = i64> {
+    memory_hash: Option<u64>,
+    seen: HashSet<StateKey>,
+    snapshots: HashMap<StateKey, Vec<(usize, W)>>,
+}
+
+impl<W> Default for LoopDetector<W> {
+    fn default() -> Self {
+        Self {
+            memory_hash: None,
+            seen: HashSet::new(),
+            snapshots: HashMap::new(),
+        }
+    }
+}
+
+impl<W: Word> LoopDetector<W> {
+    /// Keeps the memory hash up to date; must be called before `value` is
+    /// written to `addr`
+    pub fn record_write<M: Memory<Word = W>>(
+        &mut self,
+        memory: &M,
+        addr: usize,
+        value: &W,
+    ) {
+        if let Some(hash) = &mut self.memory_hash {
+            *hash ^= mix(addr, &memory.get(addr)) ^ mix(addr, value);
+        }
+    }
+
+    /// Records the state and returns whether the machine was already in it
+    pub fn revisits<M: Memory<Word = W>>(
+        &mut self,
+        memory: &M,
+        instr_ptr: usize,
+        base: i64,
+    ) -> bool {
+        // Combining cells with XOR makes the hash independent of their order
+        let memory_hash = *self.memory_hash.get_or_insert_with(|| {
+            memory
+                .cells()
+                .into_iter()
+                .fold(0, |hash, (addr, value)| hash ^ mix(addr, &value))
+        });
+        let key = (instr_ptr, base, memory_hash);
+        if self.seen.len() >= MAX_STATES {
+            self.reset();
+        }
+        if self.seen.insert(key) {
+            return false;
+        }
+
+        // A program stuck in a loop comes back to the same state again, so a
+        // hit only counts if memory matches the snapshot of the last one
+        let mut cells = memory.cells();
+        cells.sort_unstable_by_key(|&(addr, _)| addr);
+        if self.snapshots.get(&key) == Some(&cells) {
+            return true;
+        }
+        if self.snapshots.len() < MAX_SNAPSHOTS
+            || self.snapshots.contains_key(&key)
+        {
+            self.snapshots.insert(key, cells);
+        }
+        false
+    }
+
+    /// Forgets all states, as I/O may change what the program does next
+    pub fn reset(&mut self) {
+        self.seen.clear();
+        self.snapshots.clear();
+    }
+}
+
+fn mix<W: Word>(addr: usize, value: &W) -> u64 {
+    // Zero cells are left out, like in `Memory::cells`
+    if *value == W::zero() {
+        return 0;
+    }
+    let bits = value.to_i64().map_or_else(
+        || {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        },
+        |value| value as u64,
+    );
+    // SplitMix64 finalizer
+    let mut hash = (addr as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ bits;
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::PagedMemory;
+    use clap::App;
+
+    #[test]
+    fn command_line() {
+        let limits = |args: &[&str]| {
+            let app = App::new("test").args(&Limits::args());
+            Limits::from_matches(&app.get_matches_from(args))
+        };
+        assert_eq!(limits(&["test"]), Limits::default());
+        assert_eq!(
+            limits(&["test", "--max-steps", "5", "--detect-loops"]),
+            Limits {
+                max_steps: Some(5),
+                detect_loops: true,
+            }
+        );
+    }
+
+    #[test]
+    fn incremental_hash() {
+        let mut memory = PagedMemory::load(&[1, 2, 3]);
+        let mut detector = LoopDetector::default();
+        assert!(!detector.revisits(&memory, 0, 0));
+        for &(addr, value) in &[(1, 0), (5000, 7), (0, 4), (5000, 0)] {
+            detector.record_write(&memory, addr, &value);
+            memory.set(addr, value);
+        }
+        let mut fresh = LoopDetector::default();
+        assert!(!fresh.revisits(&memory, 0, 0));
+        assert_eq!(detector.memory_hash, fresh.memory_hash);
+    }
+
+    #[test]
+    fn hash_collision() {
+        let memory = PagedMemory::load(&[1, 2, 3]);
+        let other = PagedMemory::load(&[3, 2, 1]);
+        let mut detector = LoopDetector::default();
+        assert!(!detector.revisits(&memory, 0, 0));
+        assert!(!detector.revisits(&memory, 0, 0));
+        // Pretend the other memory hashes the same
+        assert!(!detector.revisits(&other, 0, 0));
+        assert!(detector.revisits(&other, 0, 0));
+    }
+}
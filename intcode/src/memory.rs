@@ -0,0 +1,232 @@
+use crate::word::Word;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Positions are allocated in pages of this many values
+const PAGE_SIZE: usize = 1024;
+
+/// Positions from here on are kept in a sparse map instead of the vector
+pub const DENSE_LIMIT: usize = 1 << 20;
+
+/// Storage for the memory of an Intcode program; positions that were never
+/// set hold 0
+pub trait Memory {
+    type Word: Word;
+
+    fn load(program: &[Self::Word]) -> Self;
+    fn get(&self, addr: usize) -> Self::Word;
+    fn set(&mut self, addr: usize, value: Self::Word);
+    /// Positions holding a value other than 0, in no particular order
+    fn cells(&self) -> Vec<(usize, Self::Word)>;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct HashMemory<W = i64> {
+    values: HashMap<usize, W>,
+}
+
+impl<W: Word> Memory for HashMemory<W> {
+    type Word = W;
+
+    fn load(program: &[W]) -> Self {
+        Self {
+            values: program.iter().cloned().enumerate().collect(),
+        }
+    }
+
+    fn get(&self, addr: usize) -> W {
+        self.values.get(&addr).cloned().unwrap_or_else(W::zero)
+    }
+
+    fn set(&mut self, addr: usize, value: W) {
+        self.values.insert(addr, value);
+    }
+
+    fn cells(&self) -> Vec<(usize, W)> {
+        self.values
+            .iter()
+            .filter(|(_, value)| **value != W::zero())
+            .map(|(&addr, value)| (addr, value.clone()))
+            .collect()
+    }
+}
+
+/// Keeps the program image and anything written near it in a vector that
+/// grows a page at a time, and very large addresses in a map
+#[derive(Clone, Debug, Default)]
+pub struct PagedMemory<W = i64> {
+    dense: Vec<W>,
+    sparse: HashMap<usize, W>,
+}
+
+impl<W: Word> Memory for PagedMemory<W> {
+    type Word = W;
+
+    fn load(program: &[W]) -> Self {
+        let mut memory = Self {
+            dense: Vec::new(),
+            sparse: HashMap::new(),
+        };
+        let len = program.len().min(DENSE_LIMIT);
+        memory.grow(len);
+        memory.dense[..len].clone_from_slice(&program[..len]);
+        for (addr, value) in program.iter().enumerate().skip(len) {
+            memory.sparse.insert(addr, value.clone());
+        }
+        memory
+    }
+
+    fn get(&self, addr: usize) -> W {
+        match self.dense.get(addr) {
+            Some(value) => value.clone(),
+            None => self.sparse.get(&addr).cloned().unwrap_or_else(W::zero),
+        }
+    }
+
+    fn set(&mut self, addr: usize, value: W) {
+        if addr >= self.dense.len() {
+            if addr >= DENSE_LIMIT {
+                self.sparse.insert(addr, value);
+                return;
+            }
+            self.grow(addr + 1);
+        }
+        self.dense[addr] = value;
+    }
+
+    fn cells(&self) -> Vec<(usize, W)> {
+        self.dense
+            .iter()
+            .cloned()
+            .enumerate()
+            .chain(
+                self.sparse
+                    .iter()
+                    .map(|(&addr, value)| (addr, value.clone())),
+            )
+            .filter(|(_, value)| *value != W::zero())
+            .collect()
+    }
+}
+
+impl<W: Word> PagedMemory<W> {
+    /// Makes room for at least `len` positions in the vector
+    fn grow(&mut self, len: usize) {
+        let pages = len.div_ceil(PAGE_SIZE);
+        self.dense
+            .resize((pages * PAGE_SIZE).min(DENSE_LIMIT), W::zero());
+    }
+}
+
+/// Like `PagedMemory`, but pages are shared between clones and only copied
+/// when one of them writes to it, which makes forking a machine cheap at the
+/// cost of slower writes
+#[derive(Clone, Debug, Default)]
+pub struct CowMemory<W = i64> {
+    pages: Vec<Arc<Vec<W>>>,
+    sparse: HashMap<usize, W>,
+}
+
+impl<W: Word> Memory for CowMemory<W> {
+    type Word = W;
+
+    fn load(program: &[W]) -> Self {
+        let len = program.len().min(DENSE_LIMIT);
+        let pages = program[..len]
+            .chunks(PAGE_SIZE)
+            .map(|chunk| {
+                let mut page = chunk.to_vec();
+                page.resize(PAGE_SIZE, W::zero());
+                Arc::new(page)
+            })
+            .collect();
+        let sparse = program.iter().cloned().enumerate().skip(len).collect();
+        Self { pages, sparse }
+    }
+
+    fn get(&self, addr: usize) -> W {
+        match self.pages.get(addr / PAGE_SIZE) {
+            Some(page) => page[addr % PAGE_SIZE].clone(),
+            None => self.sparse.get(&addr).cloned().unwrap_or_else(W::zero),
+        }
+    }
+
+    fn set(&mut self, addr: usize, value: W) {
+        if addr >= DENSE_LIMIT {
+            self.sparse.insert(addr, value);
+            return;
+        }
+        let page = addr / PAGE_SIZE;
+        if page >= self.pages.len() {
+            self.pages
+                .resize_with(page + 1, || Arc::new(vec![W::zero(); PAGE_SIZE]));
+        }
+        Arc::make_mut(&mut self.pages[page])[addr % PAGE_SIZE] = value;
+    }
+
+    fn cells(&self) -> Vec<(usize, W)> {
+        self.pages
+            .iter()
+            .flat_map(|page| page.iter().cloned())
+            .enumerate()
+            .chain(
+                self.sparse
+                    .iter()
+                    .map(|(&addr, value)| (addr, value.clone())),
+            )
+            .filter(|(_, value)| *value != W::zero())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise<M: Memory<Word = i64>>() -> Vec<(usize, i64)> {
+        let mut memory = M::load(&[1, 0, 3]);
+        assert_eq!(memory.get(2), 3);
+        assert_eq!(memory.get(5000), 0);
+        memory.set(5000, -4);
+        memory.set(1 << 40, 7);
+        memory.set(0, 0);
+        assert_eq!(memory.get(5000), -4);
+        assert_eq!(memory.get(1 << 40), 7);
+        assert_eq!(memory.get(4999), 0);
+        let mut cells = memory.cells();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn backends_agree() {
+        let cells = vec![(2, 3), (5000, -4), (1 << 40, 7)];
+        assert_eq!(exercise::<HashMemory>(), cells);
+        assert_eq!(exercise::<PagedMemory>(), cells);
+        assert_eq!(exercise::<CowMemory>(), cells);
+    }
+
+    #[test]
+    fn paged_growth() {
+        let mut memory = PagedMemory::load(&[99]);
+        assert_eq!(memory.dense.len(), PAGE_SIZE);
+        memory.set(PAGE_SIZE, 1);
+        assert_eq!(memory.dense.len(), 2 * PAGE_SIZE);
+        memory.set(DENSE_LIMIT, 1);
+        assert_eq!(memory.dense.len(), 2 * PAGE_SIZE);
+        assert_eq!(memory.sparse.len(), 1);
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let mut memory = CowMemory::load(&[1, 2, 3]);
+        memory.set(PAGE_SIZE, 4);
+        let mut copy = memory.clone();
+        assert!(Arc::ptr_eq(&memory.pages[0], &copy.pages[0]));
+
+        copy.set(1, 20);
+        assert!(!Arc::ptr_eq(&memory.pages[0], &copy.pages[0]));
+        assert!(Arc::ptr_eq(&memory.pages[1], &copy.pages[1]));
+        assert_eq!((memory.get(1), copy.get(1)), (2, 20));
+    }
+}
@@ -0,0 +1,324 @@
+use crate::trace::{TraceEntry, Tracer};
+use crate::word::Word;
+use crate::{instr_info, HALT_OP, IMMEDIATE_MODE, JIF_OP, JIT_OP};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+/// Width of the bars in the block report
+const BAR_WIDTH: u64 = 40;
+
+/// What happened at one instruction address
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AddrProfile {
+    pub opcode: i64,
+    pub size: usize,
+    pub count: u64,
+    pub taken: u64,
+    pub not_taken: u64,
+    pub reads: u64,
+    pub writes: u64,
+}
+
+/// A straight run of executed instructions that is only entered at the top
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockProfile {
+    pub start: usize,
+    pub end: usize,
+    pub entries: u64,
+    pub instrs: u64,
+}
+
+/// Counts executions per address and per opcode from the trace of a run;
+/// a profiler can be fed several runs to add them up
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    addrs: BTreeMap<usize, AddrProfile>,
+    opcodes: BTreeMap<i64, u64>,
+    leaders: HashSet<usize>,
+    next_instr: Option<usize>,
+}
+
+impl<W: Word> Tracer<W> for Profiler {
+    fn record(&mut self, entry: TraceEntry<W>) {
+        let opcode = entry.instr % 100;
+        let size = entry.params.len() + 1;
+        if self.next_instr != Some(entry.instr_ptr) {
+            self.leaders.insert(entry.instr_ptr);
+        }
+
+        let profile = self.addrs.entry(entry.instr_ptr).or_default();
+        profile.opcode = opcode;
+        profile.size = size;
+        profile.count += 1;
+        let read_params = entry.params.len() - entry.write.iter().count();
+        profile.reads += (0..read_params)
+            .filter(|param| mode(entry.instr, *param) != IMMEDIATE_MODE)
+            .count() as u64;
+        profile.writes += entry.write.iter().count() as u64;
+        *self.opcodes.entry(opcode).or_default() += 1;
+
+        // Whatever runs after a branch starts a new block
+        self.next_instr = match opcode {
+            JIT_OP | JIF_OP => {
+                let condition =
+                    entry.reads.first().cloned().unwrap_or_else(W::zero);
+                if (opcode == JIT_OP) == (condition != W::zero()) {
+                    profile.taken += 1;
+                } else {
+                    profile.not_taken += 1;
+                }
+                None
+            }
+            HALT_OP => None,
+            _ => entry.instr_ptr.checked_add(size),
+        };
+    }
+}
+
+fn mode(instr: i64, param: usize) -> i64 {
+    instr / [100, 1000, 10_000][param] % 10
+}
+
+fn mnemonic(opcode: i64) -> String {
+    instr_info(opcode)
+        .map(|(mnemonic, _, _)| mnemonic.to_string())
+        .unwrap_or_else(|| opcode.to_string())
+}
+
+fn share(count: u64, total: u64) -> f64 {
+    100.0 * count as f64 / total.max(1) as f64
+}
+
+impl Profiler {
+    /// Starts a new run; the first instruction it executes begins a block
+    pub fn start_run(&mut self) {
+        self.next_instr = None;
+    }
+
+    pub fn addr_profile(&self, addr: usize) -> Option<&AddrProfile> {
+        self.addrs.get(&addr)
+    }
+
+    /// Number of instructions executed per opcode
+    pub fn opcode_counts(&self) -> &BTreeMap<i64, u64> {
+        &self.opcodes
+    }
+
+    pub fn total(&self) -> u64 {
+        self.opcodes.values().sum()
+    }
+
+    /// Addresses sorted by how often they were executed, most often first
+    pub fn hotspots(&self) -> Vec<(usize, AddrProfile)> {
+        let mut hotspots = self
+            .addrs
+            .iter()
+            .map(|(&addr, &profile)| (addr, profile))
+            .collect::<Vec<_>>();
+        hotspots.sort_by_key(|&(addr, profile)| (!profile.count, addr));
+        hotspots
+    }
+
+    /// Executed instructions grouped into basic blocks, in address order; a
+    /// block ends at a branch or halt, or where another block is entered
+    pub fn blocks(&self) -> Vec<BlockProfile> {
+        let mut blocks: Vec<BlockProfile> = Vec::new();
+        let mut open = false;
+        for (&addr, profile) in &self.addrs {
+            match blocks.last_mut() {
+                Some(block)
+                    if open
+                        && block.end == addr
+                        && !self.leaders.contains(&addr) =>
+                {
+                    block.end += profile.size;
+                    block.instrs += profile.count;
+                }
+                _ => blocks.push(BlockProfile {
+                    start: addr,
+                    end: addr + profile.size,
+                    entries: profile.count,
+                    instrs: profile.count,
+                }),
+            }
+            open = ![JIT_OP, JIF_OP, HALT_OP].contains(&profile.opcode);
+        }
+        blocks
+    }
+
+    /// Totals, counts per opcode and the `limit` most executed addresses
+    pub fn hotspot_report(&self, limit: usize) -> String {
+        let total = self.total();
+        let sums = self.addrs.values().fold(
+            AddrProfile::default(),
+            |mut sums, profile| {
+                sums.taken += profile.taken;
+                sums.not_taken += profile.not_taken;
+                sums.reads += profile.reads;
+                sums.writes += profile.writes;
+                sums
+            },
+        );
+        let mut report = String::new();
+        writeln!(
+            report,
+            "{} instructions, {} reads, {} writes, \
+             {} branches taken, {} not taken",
+            total, sums.reads, sums.writes, sums.taken, sums.not_taken
+        )
+        .unwrap();
+
+        let mut opcodes = self.opcodes.iter().collect::<Vec<_>>();
+        opcodes.sort_by_key(|&(&opcode, &count)| (!count, opcode));
+        writeln!(report, "\nopcode        count   share").unwrap();
+        for (&opcode, &count) in opcodes {
+            writeln!(
+                report,
+                "{:<6} {:>12} {:>6.1}%",
+                mnemonic(opcode),
+                count,
+                share(count, total)
+            )
+            .unwrap();
+        }
+
+        writeln!(
+            report,
+            "\naddr  opcode        count   share    taken  not taken   \
+             reads  writes"
+        )
+        .unwrap();
+        for (addr, profile) in self.hotspots().into_iter().take(limit) {
+            writeln!(
+                report,
+                "{:04}  {:<6} {:>12} {:>6.1}% {:>8} {:>10} {:>7} {:>7}",
+                addr,
+                mnemonic(profile.opcode),
+                profile.count,
+                share(profile.count, total),
+                profile.taken,
+                profile.not_taken,
+                profile.reads,
+                profile.writes
+            )
+            .unwrap();
+        }
+        report
+    }
+
+    /// One line per basic block, most expensive first, with a bar as wide as
+    /// its share of the executed instructions
+    pub fn block_report(&self) -> String {
+        let total = self.total();
+        let mut blocks = self.blocks();
+        blocks.sort_by_key(|block| (!block.instrs, block.start));
+        let mut report = String::new();
+        for block in blocks {
+            let width = (block.instrs * BAR_WIDTH + total / 2) / total.max(1);
+            writeln!(
+                report,
+                "{:04}-{:04} {:>12} {:>6.1}% {:>10} entries  {}",
+                block.start,
+                block.end - 1,
+                block.instrs,
+                share(block.instrs, total),
+                block.entries,
+                "#".repeat(width as usize)
+            )
+            .unwrap();
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{execute_traced, SimpleInputOutput};
+
+    // Counts up to 15 and halts
+    const COUNTER: [i64; 16] = [
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0,
+        99,
+    ];
+
+    fn profile(program: &[i64], runs: usize) -> Profiler {
+        let mut profiler = Profiler::default();
+        for _ in 0..runs {
+            profiler.start_run();
+            let mut test_io = SimpleInputOutput::default();
+            assert_eq!(
+                execute_traced(program, &mut test_io, &mut profiler),
+                Ok(())
+            );
+        }
+        profiler
+    }
+
+    #[test]
+    fn counts() {
+        let profiler = profile(&COUNTER, 2);
+        assert_eq!(profiler.total(), 162);
+        assert_eq!(profiler.opcode_counts()[&1], 32);
+        assert_eq!(
+            profiler.addr_profile(12),
+            Some(&AddrProfile {
+                opcode: 6,
+                size: 3,
+                count: 32,
+                taken: 30,
+                not_taken: 2,
+                reads: 32,
+                writes: 0,
+            })
+        );
+        let add = profiler.addr_profile(4).unwrap();
+        assert_eq!((add.reads, add.writes), (32, 32));
+        let hotspots = profiler.hotspots();
+        assert_eq!(hotspots.len(), 6);
+        assert_eq!(hotspots[0].0, 0);
+        assert_eq!(hotspots[5].0, 15);
+    }
+
+    #[test]
+    fn blocks() {
+        // Skips the first output unless the input is 0, so both outputs start
+        // a block
+        let program = [3, 20, 1005, 20, 7, 104, 1, 104, 2, 99];
+        let mut profiler = Profiler::default();
+        for input in &[0, 5] {
+            profiler.start_run();
+            let mut test_io = SimpleInputOutput::new(&[*input]);
+            execute_traced(&program, &mut test_io, &mut profiler).unwrap();
+        }
+        let blocks = profiler
+            .blocks()
+            .iter()
+            .map(|block| (block.start, block.end, block.entries, block.instrs))
+            .collect::<Vec<_>>();
+        assert_eq!(blocks, vec![(0, 5, 2, 4), (5, 7, 1, 1), (7, 10, 2, 4)]);
+
+        let report = profiler.block_report();
+        assert!(report.starts_with("0000-0004            4   44.4% "));
+        assert_eq!(report.lines().count(), 3);
+    }
+
+    #[test]
+    fn reports() {
+        let profiler = profile(&COUNTER, 1);
+        let report = profiler.hotspot_report(2);
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            "81 instructions, 64 reads, 32 writes, 15 branches taken, \
+             1 not taken"
+        );
+        assert_eq!(lines[2], "opcode        count   share");
+        assert_eq!(lines[3], "add              16   19.8%");
+        assert_eq!(lines.len(), 13);
+        assert_eq!(
+            lines[12].split_whitespace().collect::<Vec<_>>(),
+            ["0002", "out", "16", "19.8%", "0", "0", "16", "0"]
+        );
+    }
+}
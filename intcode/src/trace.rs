@@ -0,0 +1,302 @@
+use crate::encoding::{
+    invalid_data, read_byte, read_signed, read_unsigned, write_signed,
+    write_unsigned,
+};
+use crate::memory::Memory;
+use crate::word::Word;
+use crate::{get_addr, get_value, InstrState, IN_OP, OUT_OP};
+use std::fmt;
+use std::io::{self, Read, Write};
+
+const TRACE_MAGIC: &[u8; 4] = b"ICTR";
+const TRACE_VERSION: u8 = 1;
+
+const HAS_WRITE: u8 = 1;
+const HAS_INPUT: u8 = 2;
+const HAS_OUTPUT: u8 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IoEvent<W = i64> {
+    Input(W),
+    Output(W),
+}
+
+/// One executed instruction: where it was, its raw parameters, the values it
+/// read, the memory position it wrote and the I/O it performed
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEntry<W = i64> {
+    pub instr_ptr: usize,
+    pub instr: i64,
+    pub base: i64,
+    pub params: Vec<W>,
+    pub reads: Vec<W>,
+    pub write: Option<(usize, W)>,
+    pub io: Option<IoEvent<W>>,
+}
+
+impl<W: fmt::Display> fmt::Display for TraceEntry<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |values: &[W]| {
+            values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        write!(
+            f,
+            "{:04} {} [{}] base={} reads=[{}]",
+            self.instr_ptr,
+            self.instr,
+            join(&self.params),
+            self.base,
+            join(&self.reads)
+        )?;
+        if let Some((addr, value)) = &self.write {
+            write!(f, " [{}]<-{}", addr, value)?;
+        }
+        match &self.io {
+            Some(IoEvent::Input(value)) => write!(f, " in={}", value),
+            Some(IoEvent::Output(value)) => write!(f, " out={}", value),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parameters of an instruction and the values it reads, captured before it
+/// executes
+pub struct Operands<W = i64> {
+    params: Vec<W>,
+    reads: Vec<W>,
+    write_addr: Option<usize>,
+}
+
+impl<W: Word> Operands<W> {
+    /// Captures the operands of an instruction with the given number of
+    /// parameters, the last of which is written if `writes` is set
+    pub fn capture<M: Memory<Word = W>>(
+        memory: &M,
+        state: &InstrState,
+        (count, writes): (usize, bool),
+    ) -> Self {
+        let params = (1..=count)
+            .filter_map(|param| state.instr_ptr.checked_add(param))
+            .map(|addr| memory.get(addr))
+            .collect();
+        let read_count = if writes { count - 1 } else { count };
+        // Invalid operands make the instruction fail, so it never gets traced
+        let reads = (1..=read_count)
+            .filter_map(|param| get_value(memory, state, param).ok())
+            .collect();
+        let write_addr = if writes {
+            get_addr(memory, state, count).ok()
+        } else {
+            None
+        };
+        Self {
+            params,
+            reads,
+            write_addr,
+        }
+    }
+
+    /// Completes the entry once the instruction has executed
+    pub fn into_entry<M: Memory<Word = W>>(
+        self,
+        memory: &M,
+        state: &InstrState,
+    ) -> TraceEntry<W> {
+        let write = self.write_addr.map(|addr| (addr, memory.get(addr)));
+        let io = match state.opcode {
+            IN_OP => write.clone().map(|(_, value)| IoEvent::Input(value)),
+            OUT_OP => self.reads.first().cloned().map(IoEvent::Output),
+            _ => None,
+        };
+        TraceEntry {
+            instr_ptr: state.instr_ptr,
+            instr: state.instr,
+            base: state.base,
+            params: self.params,
+            reads: self.reads,
+            write,
+            io,
+        }
+    }
+}
+
+pub trait Tracer<W = i64> {
+    fn record(&mut self, entry: TraceEntry<W>);
+}
+
+impl<W> Tracer<W> for Vec<TraceEntry<W>> {
+    fn record(&mut self, entry: TraceEntry<W>) {
+        self.push(entry);
+    }
+}
+
+/// Writes trace entries in the compact binary trace format: a header with
+/// the format version followed by variable length encoded entries
+pub struct TraceWriter<W: Write> {
+    writer: W,
+    result: io::Result<()>,
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(TRACE_MAGIC)?;
+        writer.write_all(&[TRACE_VERSION])?;
+        Ok(Self {
+            writer,
+            result: Ok(()),
+        })
+    }
+
+    /// Flushes the trace, reporting the first error found while writing it
+    pub fn finish(mut self) -> io::Result<W> {
+        self.result?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_entry(&mut self, entry: &TraceEntry) -> io::Result<()> {
+        let mut buffer = Vec::new();
+        write_unsigned(&mut buffer, entry.instr_ptr as u64);
+        write_signed(&mut buffer, entry.instr);
+        write_signed(&mut buffer, entry.base);
+        for values in &[&entry.params, &entry.reads] {
+            write_unsigned(&mut buffer, values.len() as u64);
+            for &value in values.iter() {
+                write_signed(&mut buffer, value);
+            }
+        }
+        let mut flags = 0;
+        if entry.write.is_some() {
+            flags |= HAS_WRITE;
+        }
+        match entry.io {
+            Some(IoEvent::Input(_)) => flags |= HAS_INPUT,
+            Some(IoEvent::Output(_)) => flags |= HAS_OUTPUT,
+            None => {}
+        }
+        buffer.push(flags);
+        if let Some((addr, value)) = entry.write {
+            write_unsigned(&mut buffer, addr as u64);
+            write_signed(&mut buffer, value);
+        }
+        if let Some(IoEvent::Input(value)) | Some(IoEvent::Output(value)) =
+            entry.io
+        {
+            write_signed(&mut buffer, value);
+        }
+        self.writer.write_all(&buffer)
+    }
+}
+
+impl<W: Write> Tracer for TraceWriter<W> {
+    fn record(&mut self, entry: TraceEntry) {
+        if self.result.is_ok() {
+            self.result = self.write_entry(&entry);
+        }
+    }
+}
+
+fn read_values<R: Read>(reader: &mut R) -> io::Result<Vec<i64>> {
+    let count = read_unsigned(reader)?;
+    (0..count).map(|_| read_signed(reader)).collect()
+}
+
+pub fn read_trace<R: Read>(mut reader: R) -> io::Result<Vec<TraceEntry>> {
+    let mut header = [0; 5];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != TRACE_MAGIC {
+        return Err(invalid_data("not an Intcode trace"));
+    }
+    if header[4] != TRACE_VERSION {
+        return Err(invalid_data("unsupported trace version"));
+    }
+
+    let mut entries = Vec::new();
+    while let Some(first) = read_byte(&mut reader)? {
+        let first = [first];
+        let mut reader = (&first[..]).chain(&mut reader);
+        let instr_ptr = read_unsigned(&mut reader)? as usize;
+        let instr = read_signed(&mut reader)?;
+        let base = read_signed(&mut reader)?;
+        let params = read_values(&mut reader)?;
+        let reads = read_values(&mut reader)?;
+        let flags = read_byte(&mut reader)?
+            .ok_or_else(|| invalid_data("truncated trace entry"))?;
+        let write = if flags & HAS_WRITE != 0 {
+            let addr = read_unsigned(&mut reader)? as usize;
+            Some((addr, read_signed(&mut reader)?))
+        } else {
+            None
+        };
+        let io = if flags & HAS_INPUT != 0 {
+            Some(IoEvent::Input(read_signed(&mut reader)?))
+        } else if flags & HAS_OUTPUT != 0 {
+            Some(IoEvent::Output(read_signed(&mut reader)?))
+        } else {
+            None
+        };
+        entries.push(TraceEntry {
+            instr_ptr,
+            instr,
+            base,
+            params,
+            reads,
+            write,
+            io,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{execute_traced, SimpleInputOutput};
+
+    const PROGRAM: [i64; 13] = [3, 11, 1001, 11, 5, 12, 4, 12, 99, 0, 0, 0, 0];
+
+    fn record(program: &[i64], input: &[i64]) -> Vec<TraceEntry> {
+        let mut test_io = SimpleInputOutput::new(input);
+        let mut trace = Vec::new();
+        assert_eq!(execute_traced(program, &mut test_io, &mut trace), Ok(()));
+        trace
+    }
+
+    #[test]
+    fn record_entries() {
+        let trace = record(&PROGRAM, &[-7]);
+        let entries = trace
+            .iter()
+            .map(|entry| entry.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                "0000 3 [11] base=0 reads=[] [11]<--7 in=-7",
+                "0002 1001 [11,5,12] base=0 reads=[-7,5] [12]<--2",
+                "0006 4 [12] base=0 reads=[-2] out=-2",
+                "0008 99 [] base=0 reads=[]",
+            ]
+        );
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let trace = record(&PROGRAM, &[1 << 40]);
+        let mut writer = TraceWriter::new(Vec::new()).unwrap();
+        for entry in &trace {
+            writer.record(entry.clone());
+        }
+        let bytes = writer.finish().unwrap();
+        assert_eq!(read_trace(bytes.as_slice()).unwrap(), trace);
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = TRACE_VERSION + 1;
+        assert!(read_trace(bad_version.as_slice()).is_err());
+        assert!(read_trace(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::Hash;
+
+pub use num_bigint::BigInt;
+
+/// A value held in an Intcode memory position, with arithmetic that fails
+/// instead of wrapping around when the result doesn't fit
+pub trait Word:
+    Clone + Eq + Hash + PartialOrd + fmt::Debug + fmt::Display
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn from_bool(value: bool) -> Self {
+        if value {
+            Self::one()
+        } else {
+            Self::zero()
+        }
+    }
+    /// `None` if the value doesn't fit this word
+    fn from_i64(value: i64) -> Option<Self>;
+    fn to_i64(&self) -> Option<i64>;
+    fn to_usize(&self) -> Option<usize>;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+}
+
+macro_rules! primitive_word {
+    ($($word:ty),*) => {$(
+        impl Word for $word {
+            fn zero() -> Self {
+                0
+            }
+
+            fn one() -> Self {
+                1
+            }
+
+            fn from_i64(value: i64) -> Option<Self> {
+                Self::try_from(value).ok()
+            }
+
+            fn to_i64(&self) -> Option<i64> {
+                i64::try_from(*self).ok()
+            }
+
+            fn to_usize(&self) -> Option<usize> {
+                usize::try_from(*self).ok()
+            }
+
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$word>::checked_add(*self, *other)
+            }
+
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$word>::checked_mul(*self, *other)
+            }
+        }
+    )*};
+}
+
+primitive_word!(i32, i64, i128);
+
+/// Arbitrary precision, so arithmetic never overflows
+impl Word for BigInt {
+    fn zero() -> Self {
+        BigInt::default()
+    }
+
+    fn one() -> Self {
+        BigInt::from(1)
+    }
+
+    fn from_i64(value: i64) -> Option<Self> {
+        Some(BigInt::from(value))
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        i64::try_from(self).ok()
+    }
+
+    fn to_usize(&self) -> Option<usize> {
+        usize::try_from(self).ok()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+}
+
+/// Converts a program or I/O values to another word type; `None` if any of
+/// them doesn't fit
+pub fn to_words<W: Word>(values: &[i64]) -> Option<Vec<W>> {
+    values.iter().map(|&value| W::from_i64(value)).collect()
+}
+
+/// Errors report values as `i64`, so larger ones are clamped to its range
+pub(crate) fn clamp<W: Word>(value: &W) -> i64 {
+    value.to_i64().unwrap_or(if *value < W::zero() {
+        i64::MIN
+    } else {
+        i64::MAX
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{execute_words, InstrState, IntcodeError, SimpleInputOutput};
+
+    fn run<W: Word>(program: &[i64]) -> Result<Vec<W>, IntcodeError> {
+        let program = to_words::<W>(program).unwrap();
+        let mut test_io = SimpleInputOutput::default();
+        execute_words(&program, &mut test_io)?;
+        Ok(test_io.get_output())
+    }
+
+    #[test]
+    fn overflow() {
+        let big = i64::MAX / 2 + 1;
+        let program = [1102, big, 2, 7, 4, 7, 99, 0];
+        assert_eq!(
+            run::<i64>(&program),
+            Err(IntcodeError::Overflow(InstrState::new(0, 1102, 0)))
+        );
+        assert_eq!(run::<i128>(&program), Ok(vec![i128::from(big) * 2]));
+        assert_eq!(run::<BigInt>(&program), Ok(vec![BigInt::from(big) * 2]));
+
+        // Squares 3 eight times, past what fits in 128 bits
+        let program = [
+            1101, 0, 8, 100, 2, 21, 21, 21, 101, -1, 100, 100, 1005, 100, 4, 4,
+            21, 99, 0, 0, 0, 3,
+        ];
+        assert!(matches!(
+            run::<i128>(&program),
+            Err(IntcodeError::Overflow(_))
+        ));
+        assert_eq!(run::<BigInt>(&program), Ok(vec![BigInt::from(3).pow(256)]));
+    }
+
+    #[test]
+    fn errors() {
+        let state =
+            |instr_ptr, instr, base| InstrState::new(instr_ptr, instr, base);
+        assert_eq!(
+            run::<i32>(&[109, -5, 204, 0, 99]),
+            Err(IntcodeError::NegativeAddress(state(2, 204, -5), -5))
+        );
+        assert_eq!(
+            run::<i32>(&[109, i64::from(i32::MAX), 109, 1, 99]),
+            Err(IntcodeError::Overflow(state(2, 109, i64::from(i32::MAX))))
+        );
+        assert_eq!(
+            run::<i128>(&[1101, i64::MAX, i64::MAX, 0, 1105, 1, 0]),
+            Err(IntcodeError::InvalidOpcode(InstrState {
+                instr_ptr: 0,
+                instr: i64::MAX,
+                opcode: i64::MAX,
+                modes: [0; 3],
+                base: 0,
+            }))
+        );
+
+        // Writes an instruction to the very end of memory and jumps to it
+        let end = usize::MAX as i128 - 1;
+        let program = [1101, 1100, 1, end, 1105, 1, end, 99];
+        let mut test_io = SimpleInputOutput::default();
+        assert_eq!(
+            execute_words(&program, &mut test_io),
+            Err(IntcodeError::PastEndOfMemory(state(
+                usize::MAX - 1,
+                1101,
+                0
+            )))
+        );
+    }
+}
@@ -0,0 +1,861 @@
+use crate::{IMMEDIATE_MODE, POSITION_MODE, RELATIVE_MODE};
+use intcode::INSTRUCTIONS;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+const COMMENT: char = ';';
+const MAX_MACRO_DEPTH: usize = 32;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl AsmError {
+    fn new(line: usize, column: usize, message: String) -> Self {
+        Self {
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for AsmError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(i64),
+    Str(String),
+    Punct(char),
+}
+
+#[derive(Clone, Debug)]
+struct Lexeme {
+    token: Token,
+    column: usize,
+    end: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Location {
+    line: usize,
+    column: usize,
+    // Macro expansion the location came from, if any
+    expansion: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Atom {
+    Number(i64),
+    Label(String, Location),
+    Here,
+}
+
+#[derive(Debug)]
+struct Expr {
+    terms: Vec<(bool, Atom)>,
+    location: Location,
+}
+
+#[derive(Debug)]
+enum DataItem {
+    Value(Expr),
+    Str(String),
+}
+
+#[derive(Debug)]
+enum Statement {
+    Label(String, Location),
+    Instr(i64, Vec<(i64, Expr)>),
+    Data(Vec<DataItem>),
+}
+
+struct Macro {
+    params: Vec<String>,
+    body: Vec<(usize, String)>,
+}
+
+/// Assembles Intcode assembly into a program.
+///
+/// Each line holds an optional `label:` followed by an instruction or a
+/// directive; `;` starts a comment. Instructions use the disassembler's
+/// mnemonics with comma separated operands: `5` is an immediate operand,
+/// `[5]` a position operand and `rel[5]` a relative operand. Operands are
+/// sums and differences of numbers, character literals (`'a'`), labels and
+/// `$`, the address of the current instruction. `.data` lists values and
+/// string literals, stored one character per position. Macros are defined
+/// between `.macro name param, ...` and `.endm`, and refer to their
+/// parameters as `%param`; `%@` expands to a number unique to each use.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+    let mut assembler = Assembler::default();
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(num, text)| (num + 1, text.to_string()))
+        .collect::<Vec<_>>();
+    assembler.process(&lines, None)?;
+    assembler.encode()
+}
+
+#[derive(Default)]
+struct Assembler {
+    macros: HashMap<String, Macro>,
+    statements: Vec<Statement>,
+    // Error note and nesting depth of each macro expansion
+    expansions: Vec<(String, usize)>,
+}
+
+impl Assembler {
+    fn process(
+        &mut self,
+        lines: &[(usize, String)],
+        expansion: Option<usize>,
+    ) -> Result<(), AsmError> {
+        let note = match expansion {
+            Some(index) => self.expansions[index].0.clone(),
+            None => String::new(),
+        };
+        let tokenize_line = |line: usize, text: &str| {
+            tokenize(line, text).map_err(|err| {
+                AsmError::new(err.line, err.column, err.message + &note)
+            })
+        };
+        let mut lines = lines.iter();
+        while let Some((line, text)) = lines.next() {
+            let lexemes = tokenize_line(*line, text)?;
+            let mut parser = Parser::new(*line, &lexemes, expansion, &note);
+            if parser.peek_ident() == Some(".macro") {
+                let (name, params) = parser.macro_header()?;
+                let mut body = Vec::new();
+                loop {
+                    let (body_line, body_text) =
+                        lines.next().ok_or_else(|| {
+                            parser.error_at(
+                                1,
+                                &format!("macro '{}' has no .endm", name),
+                            )
+                        })?;
+                    let body_lexemes = tokenize_line(*body_line, body_text)?;
+                    let mut body_parser = Parser::new(
+                        *body_line,
+                        &body_lexemes,
+                        expansion,
+                        &note,
+                    );
+                    if body_parser.peek_ident() == Some(".endm") {
+                        body_parser.next();
+                        body_parser.end()?;
+                        break;
+                    }
+                    body.push((*body_line, body_text.clone()));
+                }
+                self.macros.insert(name, Macro { params, body });
+                continue;
+            }
+
+            while let Some((label, location)) = parser.label() {
+                self.statements.push(Statement::Label(label, location));
+            }
+            let location = parser.location();
+            match parser.peek_ident() {
+                None => parser.end()?,
+                Some(".data") => {
+                    parser.next();
+                    let items = parser.data()?;
+                    self.statements.push(Statement::Data(items));
+                }
+                Some(".endm") => {
+                    return Err(parser.error(".endm outside a macro"));
+                }
+                Some(name) if self.macros.contains_key(name) => {
+                    let name = name.to_string();
+                    parser.next();
+                    let args = parser.macro_args(text)?;
+                    self.expand(&name, &args, location)?;
+                }
+                Some(name) => {
+                    let (opcode, params, writes) =
+                        match instr_info_by_name(name) {
+                            Some(info) => info,
+                            None => {
+                                return Err(parser.error(&format!(
+                                    "unknown mnemonic '{}'",
+                                    name
+                                )))
+                            }
+                        };
+                    parser.next();
+                    let operands = parser.operands()?;
+                    if operands.len() != params {
+                        return Err(self.error_at(
+                            location,
+                            format!(
+                                "'{}' takes {} operands, found {}",
+                                name,
+                                params,
+                                operands.len()
+                            ),
+                        ));
+                    }
+                    if let Some((IMMEDIATE_MODE, expr)) = operands.last() {
+                        if writes {
+                            return Err(self.error_at(
+                                expr.location,
+                                "cannot write to an immediate operand"
+                                    .to_string(),
+                            ));
+                        }
+                    }
+                    self.statements.push(Statement::Instr(opcode, operands));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expand(
+        &mut self,
+        name: &str,
+        args: &[String],
+        location: Location,
+    ) -> Result<(), AsmError> {
+        let (parent_note, depth) = match location.expansion {
+            Some(index) => self.expansions[index].clone(),
+            None => (String::new(), 0),
+        };
+        if depth >= MAX_MACRO_DEPTH {
+            return Err(self.error_at(
+                location,
+                format!("macro '{}' is nested too deeply", name),
+            ));
+        }
+        let mac = &self.macros[name];
+        if args.len() != mac.params.len() {
+            return Err(self.error_at(
+                location,
+                format!(
+                    "macro '{}' takes {} arguments, found {}",
+                    name,
+                    mac.params.len(),
+                    args.len()
+                ),
+            ));
+        }
+        // Longer parameter names first, so %ab isn't replaced as %a
+        let mut params = mac.params.iter().zip(args).collect::<Vec<_>>();
+        params.sort_by_key(|(param, _)| Reverse(param.len()));
+        let unique = (self.expansions.len() + 1).to_string();
+        let body = mac
+            .body
+            .iter()
+            .map(|(line, text)| {
+                let text = params.iter().fold(
+                    text.replace("%@", &unique),
+                    |text, (param, arg)| {
+                        text.replace(&format!("%{}", param), arg)
+                    },
+                );
+                (*line, text)
+            })
+            .collect::<Vec<_>>();
+        let note = format!(
+            " (in macro '{}' used at line {}){}",
+            name, location.line, parent_note
+        );
+        self.expansions.push((note, depth + 1));
+        self.process(&body, Some(self.expansions.len() - 1))
+    }
+
+    /// Builds an error for a location, noting the macro it was expanded from
+    fn error_at(&self, location: Location, message: String) -> AsmError {
+        let note = match location.expansion {
+            Some(index) => self.expansions[index].0.as_str(),
+            None => "",
+        };
+        AsmError::new(location.line, location.column, message + note)
+    }
+
+    fn encode(&self) -> Result<Vec<i64>, AsmError> {
+        let mut labels = HashMap::new();
+        let mut addr = 0;
+        for statement in &self.statements {
+            match statement {
+                Statement::Label(name, location) => {
+                    if labels.insert(name.as_str(), addr).is_some() {
+                        return Err(self.error_at(
+                            *location,
+                            format!("label '{}' is defined twice", name),
+                        ));
+                    }
+                }
+                Statement::Instr(_, operands) => addr += operands.len() + 1,
+                Statement::Data(items) => {
+                    addr += items
+                        .iter()
+                        .map(|item| match item {
+                            DataItem::Value(_) => 1,
+                            DataItem::Str(string) => string.chars().count(),
+                        })
+                        .sum::<usize>()
+                }
+            }
+        }
+
+        let mut program = Vec::new();
+        for statement in &self.statements {
+            let here = program.len() as i64;
+            match statement {
+                Statement::Label(_, _) => {}
+                Statement::Instr(opcode, operands) => {
+                    let modes = operands
+                        .iter()
+                        .enumerate()
+                        .map(|(param, (mode, _))| {
+                            mode * 10_i64.pow(param as u32)
+                        })
+                        .sum::<i64>();
+                    program.push(opcode + 100 * modes);
+                    for (_, expr) in operands {
+                        program.push(self.eval(expr, &labels, here)?);
+                    }
+                }
+                Statement::Data(items) => {
+                    for item in items {
+                        match item {
+                            DataItem::Value(expr) => {
+                                program.push(self.eval(expr, &labels, here)?)
+                            }
+                            DataItem::Str(string) => program
+                                .extend(string.chars().map(|ch| ch as i64)),
+                        }
+                    }
+                }
+            }
+        }
+        Ok(program)
+    }
+
+    fn eval(
+        &self,
+        expr: &Expr,
+        labels: &HashMap<&str, usize>,
+        here: i64,
+    ) -> Result<i64, AsmError> {
+        let mut total = 0_i64;
+        for (negative, atom) in &expr.terms {
+            let value = match atom {
+                Atom::Number(value) => *value,
+                Atom::Here => here,
+                Atom::Label(name, location) => {
+                    match labels.get(name.as_str()) {
+                        Some(&addr) => addr as i64,
+                        None => {
+                            return Err(self.error_at(
+                                *location,
+                                format!("undefined label '{}'", name),
+                            ))
+                        }
+                    }
+                }
+            };
+            let result = if *negative {
+                total.checked_sub(value)
+            } else {
+                total.checked_add(value)
+            };
+            total = result.ok_or_else(|| {
+                self.error_at(expr.location, "value out of range".to_string())
+            })?;
+        }
+        Ok(total)
+    }
+}
+
+fn instr_info_by_name(name: &str) -> Option<(i64, usize, bool)> {
+    INSTRUCTIONS
+        .iter()
+        .find(|(_, mnemonic, _, _)| *mnemonic == name)
+        .map(|&(opcode, _, params, writes)| (opcode, params, writes))
+}
+
+fn tokenize(line: usize, text: &str) -> Result<Vec<Lexeme>, AsmError> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut lexemes = Vec::new();
+    let mut pos = 0;
+    while pos < chars.len() {
+        let start = pos;
+        let ch = chars[pos];
+        let token = if ch == COMMENT {
+            break;
+        } else if ch.is_whitespace() {
+            pos += 1;
+            continue;
+        } else if ch.is_ascii_digit() {
+            while pos < chars.len() && chars[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            let digits = chars[start..pos].iter().collect::<String>();
+            let value = digits.parse().map_err(|_| {
+                AsmError::new(
+                    line,
+                    start + 1,
+                    format!("number {} is out of range", digits),
+                )
+            })?;
+            Token::Number(value)
+        } else if ch.is_alphabetic() || "_.%@".contains(ch) {
+            while pos < chars.len()
+                && (chars[pos].is_alphanumeric() || "_.%@".contains(chars[pos]))
+            {
+                pos += 1;
+            }
+            Token::Ident(chars[start..pos].iter().collect())
+        } else if ch == '"' || ch == '\'' {
+            let mut string = String::new();
+            pos += 1;
+            loop {
+                let next = match chars.get(pos) {
+                    Some(&next) if next == ch => break,
+                    Some('\\') => {
+                        pos += 1;
+                        match chars.get(pos) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('0') => '\0',
+                            Some(&other @ '\\') | Some(&other @ '"')
+                            | Some(&other @ '\'') => other,
+                            _ => {
+                                return Err(AsmError::new(
+                                    line,
+                                    pos + 1,
+                                    "invalid escape sequence".to_string(),
+                                ))
+                            }
+                        }
+                    }
+                    Some(&next) => next,
+                    None => {
+                        return Err(AsmError::new(
+                            line,
+                            start + 1,
+                            "unterminated literal".to_string(),
+                        ))
+                    }
+                };
+                string.push(next);
+                pos += 1;
+            }
+            pos += 1;
+            if ch == '"' {
+                Token::Str(string)
+            } else {
+                let mut string_chars = string.chars();
+                match (string_chars.next(), string_chars.next()) {
+                    (Some(single), None) => Token::Number(single as i64),
+                    _ => {
+                        return Err(AsmError::new(
+                            line,
+                            start + 1,
+                            "character literal must hold one character"
+                                .to_string(),
+                        ))
+                    }
+                }
+            }
+        } else if ",:[]+-$".contains(ch) {
+            pos += 1;
+            Token::Punct(ch)
+        } else {
+            return Err(AsmError::new(
+                line,
+                start + 1,
+                format!("unexpected character '{}'", ch),
+            ));
+        };
+        lexemes.push(Lexeme {
+            token,
+            column: start + 1,
+            end: pos,
+        });
+    }
+    Ok(lexemes)
+}
+
+struct Parser<'a> {
+    line: usize,
+    lexemes: &'a [Lexeme],
+    pos: usize,
+    expansion: Option<usize>,
+    note: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(
+        line: usize,
+        lexemes: &'a [Lexeme],
+        expansion: Option<usize>,
+        note: &'a str,
+    ) -> Self {
+        Self {
+            line,
+            lexemes,
+            pos: 0,
+            expansion,
+            note,
+        }
+    }
+
+    fn location(&self) -> Location {
+        let column = match self.lexemes.get(self.pos) {
+            Some(lexeme) => lexeme.column,
+            None => self.lexemes.last().map(|lex| lex.end + 1).unwrap_or(1),
+        };
+        Location {
+            line: self.line,
+            column,
+            expansion: self.expansion,
+        }
+    }
+
+    fn error_at(&self, column: usize, message: &str) -> AsmError {
+        AsmError::new(self.line, column, message.to_string() + self.note)
+    }
+
+    fn error(&self, message: &str) -> AsmError {
+        self.error_at(self.location().column, message)
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.lexemes.get(self.pos).map(|lexeme| &lexeme.token)
+    }
+
+    fn peek_ident(&self) -> Option<&'a str> {
+        match self.peek() {
+            Some(Token::Ident(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn accept(&mut self, punct: char) -> bool {
+        if self.peek() == Some(&Token::Punct(punct)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), AsmError> {
+        if self.accept(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", punct)))
+        }
+    }
+
+    fn end(&self) -> Result<(), AsmError> {
+        if self.pos < self.lexemes.len() {
+            Err(self.error("unexpected input"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, AsmError> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                Ok(name.clone())
+            }
+            _ => Err(self.error("expected a name")),
+        }
+    }
+
+    fn label(&mut self) -> Option<(String, Location)> {
+        let location = self.location();
+        match (self.peek(), self.lexemes.get(self.pos + 1)) {
+            (Some(Token::Ident(name)), Some(lexeme))
+                if lexeme.token == Token::Punct(':') =>
+            {
+                self.pos += 2;
+                Some((name.clone(), location))
+            }
+            _ => None,
+        }
+    }
+
+    fn macro_header(&mut self) -> Result<(String, Vec<String>), AsmError> {
+        self.next();
+        let name = self.ident()?;
+        let mut params = Vec::new();
+        if self.pos < self.lexemes.len() {
+            params.push(self.ident()?);
+            while self.accept(',') {
+                params.push(self.ident()?);
+            }
+        }
+        self.end()?;
+        Ok((name, params))
+    }
+
+    /// Splits the rest of the line at top level commas, as raw source text
+    fn macro_args(&mut self, text: &str) -> Result<Vec<String>, AsmError> {
+        let chars = text.chars().collect::<Vec<_>>();
+        let mut args = Vec::new();
+        let mut depth = 0;
+        let mut start = None;
+        let mut end = 0;
+        while let Some(lexeme) = self.lexemes.get(self.pos) {
+            match lexeme.token {
+                Token::Punct(',') if depth == 0 => {
+                    let from = start.take().ok_or_else(|| {
+                        self.error("expected a macro argument")
+                    })?;
+                    args.push(chars[from..end].iter().collect());
+                }
+                _ => {
+                    match lexeme.token {
+                        Token::Punct('[') => depth += 1,
+                        Token::Punct(']') => depth -= 1,
+                        _ => {}
+                    }
+                    start = start.or(Some(lexeme.column - 1));
+                    end = lexeme.end;
+                }
+            }
+            self.pos += 1;
+        }
+        match start {
+            Some(from) => args.push(chars[from..end].iter().collect()),
+            None if !args.is_empty() => {
+                return Err(self.error("expected a macro argument"))
+            }
+            None => {}
+        }
+        Ok(args)
+    }
+
+    fn expr(&mut self) -> Result<Expr, AsmError> {
+        let location = self.location();
+        let mut terms = Vec::new();
+        let mut negative = self.accept('-');
+        if !negative {
+            self.accept('+');
+        }
+        loop {
+            let atom_location = self.location();
+            let atom = match self.next() {
+                Some(Token::Number(value)) => Atom::Number(*value),
+                Some(Token::Punct('$')) => Atom::Here,
+                Some(Token::Ident(name)) if name != "rel" => {
+                    Atom::Label(name.clone(), atom_location)
+                }
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("expected a value"));
+                }
+            };
+            terms.push((negative, atom));
+            if self.accept('+') {
+                negative = false;
+            } else if self.accept('-') {
+                negative = true;
+            } else {
+                break;
+            }
+        }
+        Ok(Expr { terms, location })
+    }
+
+    fn operand(&mut self) -> Result<(i64, Expr), AsmError> {
+        if self.accept('[') {
+            let expr = self.expr()?;
+            self.expect(']')?;
+            Ok((POSITION_MODE, expr))
+        } else if self.peek_ident() == Some("rel") {
+            self.next();
+            self.expect('[')?;
+            let expr = self.expr()?;
+            self.expect(']')?;
+            Ok((RELATIVE_MODE, expr))
+        } else {
+            Ok((IMMEDIATE_MODE, self.expr()?))
+        }
+    }
+
+    fn operands(&mut self) -> Result<Vec<(i64, Expr)>, AsmError> {
+        let mut operands = Vec::new();
+        if self.pos < self.lexemes.len() {
+            operands.push(self.operand()?);
+            while self.accept(',') {
+                operands.push(self.operand()?);
+            }
+        }
+        self.end()?;
+        Ok(operands)
+    }
+
+    fn data(&mut self) -> Result<Vec<DataItem>, AsmError> {
+        let mut items = Vec::new();
+        loop {
+            if let Some(Token::Str(string)) = self.peek() {
+                self.pos += 1;
+                items.push(DataItem::Str(string.clone()));
+            } else {
+                items.push(DataItem::Value(self.expr()?));
+            }
+            if !self.accept(',') {
+                break;
+            }
+        }
+        self.end()?;
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm::Disassembly;
+    use crate::{IntcodeComputer, SimpleInputOutput};
+
+    fn run(program: &[i64]) -> Vec<i64> {
+        let mut test_io = SimpleInputOutput::default();
+        let mut computer = IntcodeComputer::new(program, false);
+        assert_eq!(computer.run(&mut test_io), Ok(true));
+        test_io.get_output()
+    }
+
+    #[test]
+    fn quine() {
+        let source = "
+            rbo   1
+            out   rel[-1]       ; print the next program position
+            add   [100], 1, [100]
+            eq    [100], 16, [101]
+            jf    [101], 0
+            hlt
+            ";
+        let program = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101,
+            0, 99,
+        ];
+        assert_eq!(assemble(source), Ok(program));
+    }
+
+    #[test]
+    fn labels_and_data() {
+        let source = "
+                    rbo   msg
+            loop:   jf    rel[0], end
+                    out   rel[0]
+                    rbo   1
+                    jt    1, loop
+            end:    out   [end + 1]
+                    out   end - $
+                    hlt
+            msg:    .data \"Hi!\", '\\n', 0
+            ";
+        let program = assemble(source).unwrap();
+        assert_eq!(run(&program), vec![72, 105, 33, 10, 13, -2]);
+    }
+
+    #[test]
+    fn macros() {
+        let source = "
+            .macro push value
+                    add   %value, 0, rel[0]
+                    rbo   1
+            .endm
+            .macro mov src, dst
+                    add   %src, 0, %dst
+            .endm
+            .macro skip_zero value
+                    jf    %value, skip%@
+                    out   %value
+            skip%@:
+            .endm
+                    rbo   stack
+                    push  7
+                    push  'a'
+                    mov   rel[-1], [result]
+                    out   [result]
+                    skip_zero 0
+                    skip_zero rel[-2]
+                    hlt
+            result: .data 0
+            stack:  .data 0, 0
+            ";
+        let program = assemble(source).unwrap();
+        assert_eq!(run(&program), vec![97, 7]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let program =
+            vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+        let source = Disassembly::new(&program)
+            .items()
+            .iter()
+            .map(|item| item.to_string()[6..].to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(assemble(&source), Ok(program));
+    }
+
+    #[test]
+    fn errors() {
+        let tests = vec![
+            ("  nop", 1, 3, "unknown mnemonic 'nop'"),
+            ("add 1, 2", 1, 1, "'add' takes 3 operands, found 2"),
+            ("hlt\nin 5", 2, 4, "cannot write to an immediate operand"),
+            ("jt 1, nowhere", 1, 7, "undefined label 'nowhere'"),
+            ("a: hlt\na: hlt", 2, 1, "label 'a' is defined twice"),
+            (".data \"abc", 1, 7, "unterminated literal"),
+            ("out [1", 1, 7, "expected ']'"),
+            ("out 1 ?", 1, 7, "unexpected character '?'"),
+            (".macro m\nhlt", 1, 1, "macro 'm' has no .endm"),
+            (".macro m\n.endmacro", 1, 1, "macro 'm' has no .endm"),
+            (".macro m\nhlt\n.endm hlt", 3, 7, "unexpected input"),
+            (
+                ".macro m x\nout %x\n.endm\nm [y]",
+                2,
+                6,
+                "undefined label 'y' (in macro 'm' used at line 4)",
+            ),
+            (
+                ".macro a\nout [y]\n.endm\n.macro b\na\n.endm\nb",
+                2,
+                6,
+                "undefined label 'y' (in macro 'a' used at line 5) \
+                 (in macro 'b' used at line 7)",
+            ),
+        ];
+        for (source, line, column, message) in tests {
+            let error = AsmError::new(line, column, message.to_string());
+            assert_eq!(assemble(source), Err(error));
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use crate::memory::Memory;
+use crate::{InputOutput, IntcodeComputer, IntcodeError, Status};
+use std::future::poll_fn;
+use std::task::{Context, Poll};
+
+/// Input and output for programs driven by an async executor: instead of
+/// blocking, a value that isn't ready yet is `Poll::Pending`, with the task
+/// woken once it is; as with `InputOutput`, `None` means there is none
+pub trait AsyncInputOutput {
+    fn poll_input(&mut self, cx: &mut Context<'_>) -> Poll<Option<i64>>;
+    fn poll_output(
+        &mut self,
+        cx: &mut Context<'_>,
+        value: i64,
+    ) -> Poll<Option<()>>;
+}
+
+/// Lets the computer use async I/O, blocking the instruction when it's not
+/// ready
+struct Polled<'a, 'b, IO: ?Sized> {
+    io: &'a mut IO,
+    cx: &'a mut Context<'b>,
+    pending: bool,
+}
+
+impl<IO: AsyncInputOutput + ?Sized> Polled<'_, '_, IO> {
+    fn ready<T>(&mut self, poll: Poll<Option<T>>) -> Option<T> {
+        match poll {
+            Poll::Ready(value) => value,
+            Poll::Pending => {
+                self.pending = true;
+                None
+            }
+        }
+    }
+}
+
+impl<IO: AsyncInputOutput + ?Sized> InputOutput for Polled<'_, '_, IO> {
+    fn provide_input(&mut self) -> Option<i64> {
+        let poll = self.io.poll_input(self.cx);
+        self.ready(poll)
+    }
+
+    fn take_output(&mut self, value: i64) -> Option<()> {
+        let poll = self.io.poll_output(self.cx, value);
+        self.ready(poll)
+    }
+
+    fn is_pending(&self) -> bool {
+        self.pending
+    }
+}
+
+impl<M: Memory<Word = i64>> IntcodeComputer<M> {
+    /// Like `run`, but waits for I/O that isn't ready yet, letting the
+    /// executor run other tasks meanwhile; the program only yields at input
+    /// and output instructions
+    pub async fn run_async<IO>(
+        &mut self,
+        io: &mut IO,
+    ) -> Result<bool, IntcodeError>
+    where
+        IO: AsyncInputOutput + ?Sized,
+    {
+        poll_fn(|cx| self.poll_run(io, cx)).await
+    }
+
+    /// Runs until the program halts, blocks or waits for I/O, in which case
+    /// the task is woken once the I/O is ready
+    pub fn poll_run<IO>(
+        &mut self,
+        io: &mut IO,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<bool, IntcodeError>>
+    where
+        IO: AsyncInputOutput + ?Sized,
+    {
+        let mut polled = Polled {
+            io,
+            cx,
+            pending: false,
+        };
+        loop {
+            match self.step(&mut polled) {
+                Ok(Status::Blocked) if polled.pending => return Poll::Pending,
+                Ok(Status::Blocked) => return Poll::Ready(Ok(false)),
+                Ok(Status::Halted) => return Poll::Ready(Ok(true)),
+                Ok(_) => {}
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Wake, Waker};
+
+    // Outputs its input plus 1 and halts
+    const INCREMENT: [i64; 9] = [3, 100, 1001, 100, 1, 100, 4, 100, 99];
+
+    type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+    /// Runs tasks on this thread, polling each one only when it's woken
+    #[derive(Default)]
+    struct Executor {
+        tasks: Vec<Option<Task>>,
+        ready: Arc<ReadyQueue>,
+    }
+
+    #[derive(Default)]
+    struct ReadyQueue(Mutex<VecDeque<usize>>);
+
+    struct TaskWaker {
+        task: usize,
+        ready: Arc<ReadyQueue>,
+    }
+
+    impl Wake for TaskWaker {
+        fn wake(self: Arc<Self>) {
+            self.ready.0.lock().unwrap().push_back(self.task);
+        }
+    }
+
+    impl Executor {
+        fn spawn<F: Future<Output = ()> + 'static>(&mut self, future: F) {
+            self.ready.0.lock().unwrap().push_back(self.tasks.len());
+            self.tasks.push(Some(Box::pin(future)));
+        }
+
+        /// Runs until no task is ready; returns how many are unfinished
+        fn run(&mut self) -> usize {
+            loop {
+                let task = self.ready.0.lock().unwrap().pop_front();
+                let task = match task {
+                    Some(task) => task,
+                    None => break,
+                };
+                let waker = Waker::from(Arc::new(TaskWaker {
+                    task,
+                    ready: Arc::clone(&self.ready),
+                }));
+                let mut cx = Context::from_waker(&waker);
+                if let Some(future) = &mut self.tasks[task] {
+                    if future.as_mut().poll(&mut cx).is_ready() {
+                        self.tasks[task] = None;
+                    }
+                }
+            }
+            self.tasks.iter().filter(|task| task.is_some()).count()
+        }
+    }
+
+    /// One-way connection between tasks on the same thread
+    #[derive(Clone, Default)]
+    struct Pipe(Rc<RefCell<PipeState>>);
+
+    #[derive(Default)]
+    struct PipeState {
+        values: VecDeque<i64>,
+        closed: bool,
+        reader: Option<Waker>,
+    }
+
+    impl Pipe {
+        fn send(&self, value: i64) {
+            let mut state = self.0.borrow_mut();
+            state.values.push_back(value);
+            if let Some(reader) = state.reader.take() {
+                reader.wake();
+            }
+        }
+
+        fn close(&self) {
+            let mut state = self.0.borrow_mut();
+            state.closed = true;
+            if let Some(reader) = state.reader.take() {
+                reader.wake();
+            }
+        }
+
+        fn values(&self) -> Vec<i64> {
+            self.0.borrow().values.iter().copied().collect()
+        }
+    }
+
+    struct PipeInputOutput {
+        input: Pipe,
+        output: Pipe,
+    }
+
+    impl AsyncInputOutput for PipeInputOutput {
+        fn poll_input(&mut self, cx: &mut Context<'_>) -> Poll<Option<i64>> {
+            let mut state = self.input.0.borrow_mut();
+            match state.values.pop_front() {
+                Some(value) => Poll::Ready(Some(value)),
+                None if state.closed => Poll::Ready(None),
+                None => {
+                    state.reader = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+
+        fn poll_output(
+            &mut self,
+            _cx: &mut Context<'_>,
+            value: i64,
+        ) -> Poll<Option<()>> {
+            self.output.send(value);
+            Poll::Ready(Some(()))
+        }
+    }
+
+    fn spawn_computer(
+        executor: &mut Executor,
+        mut computer: IntcodeComputer,
+        input: Pipe,
+        output: Pipe,
+    ) -> Rc<RefCell<Option<Result<bool, IntcodeError>>>> {
+        let result = Rc::new(RefCell::new(None));
+        let task_result = Rc::clone(&result);
+        executor.spawn(async move {
+            let mut io = PipeInputOutput { input, output };
+            *task_result.borrow_mut() = Some(computer.run_async(&mut io).await);
+        });
+        result
+    }
+
+    #[test]
+    fn thousand_machines() {
+        let mut executor = Executor::default();
+        let pipes = (0..=1000).map(|_| Pipe::default()).collect::<Vec<_>>();
+        // Spawned last to first, so every machine waits for its input
+        let results = pipes
+            .windows(2)
+            .rev()
+            .map(|pipes| {
+                let computer = IntcodeComputer::new(&INCREMENT, false);
+                spawn_computer(
+                    &mut executor,
+                    computer,
+                    pipes[0].clone(),
+                    pipes[1].clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(executor.run(), 1000);
+
+        pipes[0].send(0);
+        assert_eq!(executor.run(), 0);
+        assert_eq!(pipes[1000].values(), vec![1000]);
+        assert!(results
+            .iter()
+            .all(|result| *result.borrow() == Some(Ok(true))));
+    }
+
+    #[test]
+    fn input_closed() {
+        for &blocking_io in &[true, false] {
+            let mut executor = Executor::default();
+            let input = Pipe::default();
+            let computer = IntcodeComputer::new(&INCREMENT, blocking_io);
+            let result = spawn_computer(
+                &mut executor,
+                computer,
+                input.clone(),
+                Pipe::default(),
+            );
+            assert_eq!(executor.run(), 1);
+            assert_eq!(*result.borrow(), None);
+
+            input.close();
+            assert_eq!(executor.run(), 0);
+            let result = result.borrow_mut().take().unwrap();
+            if blocking_io {
+                assert_eq!(result, Ok(false));
+            } else {
+                assert!(matches!(result, Err(IntcodeError::NoInput(_))));
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use clap::{App, Arg};
+use intcode2::asm::assemble;
+use std::fs::read_to_string;
+use std::process::exit;
+
+fn main() {
+    let args = App::new("Intcode assembler")
+        .arg(
+            Arg::with_name("INPUT")
+                .help("Sets the assembly source file to use")
+                .required(true)
+                .index(1),
+        )
+        .get_matches();
+
+    let filename = args.value_of("INPUT").unwrap();
+    let source = read_to_string(filename).unwrap_or_else(|err| {
+        println!("Failed to read file '{}': {}", filename, err);
+        exit(2);
+    });
+    match assemble(&source) {
+        Ok(program) => {
+            let values = program
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>();
+            println!("{}", values.join(","));
+        }
+        Err(err) => {
+            println!("{}: {}", filename, err);
+            exit(3);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use clap::{value_t_or_exit, values_t_or_exit, App, Arg};
+use intcode2::console::Console;
+use std::collections::VecDeque;
+use std::fs::{read_to_string, File};
+use std::io::{stdin, stdout, Write};
+use std::num::ParseIntError;
+use std::process::exit;
+
+fn main() {
+    let args = App::new("Intcode ASCII console")
+        .after_help(
+            "Lines typed are sent to the program as input. '!!' repeats the \
+             last line, '!n' repeats the nth one and '!history' lists them.",
+        )
+        .arg(
+            Arg::with_name("INPUT")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("SCRIPT")
+                .help("File with lines to send before reading the terminal")
+                .short("s")
+                .long("script")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("SCRIPT_LINES")
+                .help("Only send this many lines of the script")
+                .short("n")
+                .long("script-lines")
+                .takes_value(true)
+                .requires("SCRIPT"),
+        )
+        .arg(
+            Arg::with_name("TRANSCRIPT")
+                .help("File to log the session to")
+                .short("l")
+                .long("log")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("POKE")
+                .help("Sets a memory position before starting, as addr=value")
+                .short("p")
+                .long("poke")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .get_matches();
+
+    let program = read_input(args.value_of("INPUT").unwrap());
+    let mut console = Console::new(&program);
+    if args.is_present("POKE") {
+        for poke in values_t_or_exit!(args, "POKE", String) {
+            let (addr, value) = parse_poke(&poke).unwrap_or_else(|| {
+                println!("Invalid memory change '{}'", poke);
+                exit(3);
+            });
+            console.computer_mut().write_memory(addr, value);
+        }
+    }
+    if let Some(filename) = args.value_of("TRANSCRIPT") {
+        match File::create(filename) {
+            Ok(file) => console.set_transcript(file),
+            Err(err) => {
+                println!("Failed to create file '{}': {}", filename, err);
+                exit(2);
+            }
+        }
+    }
+    let mut script =
+        args.value_of("SCRIPT").map(read_script).unwrap_or_default();
+    if args.is_present("SCRIPT_LINES") {
+        script.truncate(value_t_or_exit!(args, "SCRIPT_LINES", usize));
+    }
+
+    loop {
+        match console.run() {
+            Ok((halted, text)) => {
+                print!("{}", text);
+                if halted {
+                    println!("\nProgram halted");
+                    break;
+                }
+            }
+            Err(err) => {
+                println!("\nProgram failed: {}", err);
+                exit(4);
+            }
+        }
+        let _ = stdout().flush();
+        let line = match script.pop_front() {
+            Some(line) => {
+                println!("{}", line);
+                line
+            }
+            None => match read_line() {
+                Some(line) => line,
+                None => break,
+            },
+        };
+        if line == "!history" {
+            for (number, line) in console.history().iter().enumerate() {
+                println!("{:4}  {}", number + 1, line);
+            }
+            continue;
+        }
+        if let Err(err) = console.send(&line) {
+            println!("{}", err);
+        }
+    }
+}
+
+fn read_line() -> Option<String> {
+    let mut line = String::new();
+    match stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end_matches(&['\r', '\n'][..]).to_string()),
+    }
+}
+
+fn parse_poke(text: &str) -> Option<(usize, i64)> {
+    let mut parts = text.splitn(2, '=');
+    let addr = parts.next()?.trim().parse().ok()?;
+    let value = parts.next()?.trim().parse().ok()?;
+    Some((addr, value))
+}
+
+fn read_script(filename: &str) -> VecDeque<String> {
+    let script = read_to_string(filename).unwrap_or_else(|err| {
+        println!("Failed to read file '{}': {}", filename, err);
+        exit(2);
+    });
+    script.lines().map(|line| line.to_string()).collect()
+}
+
+fn read_input(filename: &str) -> Vec<i64> {
+    let input = read_to_string(filename).unwrap_or_else(|err| {
+        println!("Failed to read file '{}': {}", filename, err);
+        exit(2);
+    });
+    input
+        .split(',')
+        .map(|s| s.trim().parse())
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|err: ParseIntError| {
+            println!("Failed to parse input: {}", err);
+            exit(3);
+        })
+}
@@ -0,0 +1,103 @@
+use clap::{App, Arg};
+use intcode2::debug::{Command, Debugger};
+use intcode2::{InputOutput, IntcodeComputer};
+use std::collections::VecDeque;
+use std::fs::read_to_string;
+use std::io::{stdin, stdout, Write};
+use std::num::ParseIntError;
+use std::process::exit;
+
+/// Takes input from a queue first and then from the terminal, and prints
+/// every output value
+struct ConsoleInputOutput {
+    input: VecDeque<i64>,
+}
+
+impl InputOutput for ConsoleInputOutput {
+    fn provide_input(&mut self) -> Option<i64> {
+        if let Some(value) = self.input.pop_front() {
+            return Some(value);
+        }
+        loop {
+            let line = prompt("input> ")?;
+            match line.trim().parse() {
+                Ok(value) => return Some(value),
+                Err(err) => println!("Invalid input: {}", err),
+            }
+        }
+    }
+
+    fn take_output(&mut self, value: i64) -> Option<()> {
+        println!("output: {}", value);
+        Some(())
+    }
+}
+
+fn main() {
+    let args = App::new("Intcode debugger")
+        .arg(
+            Arg::with_name("INPUT")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("PROGRAM_INPUT")
+                .help("Comma separated values to feed the program first")
+                .short("i")
+                .long("input")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let program = read_input(args.value_of("INPUT").unwrap());
+    let input = args
+        .value_of("PROGRAM_INPUT")
+        .map(parse_values)
+        .unwrap_or_default();
+    let mut computer = IntcodeComputer::new(&program, false);
+    let mut console_io = ConsoleInputOutput {
+        input: input.into_iter().collect(),
+    };
+    let mut debugger = Debugger::new(&mut computer, &mut console_io);
+
+    println!("{}", debugger.execute(Command::Print(None)));
+    while let Some(line) = prompt("(icdb) ") {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse() {
+            Ok(Command::Quit) => break,
+            Ok(command) => println!("{}", debugger.execute(command)),
+            Err(err) => println!("{}", err),
+        }
+    }
+}
+
+fn prompt(text: &str) -> Option<String> {
+    print!("{}", text);
+    stdout().flush().ok()?;
+    let mut line = String::new();
+    match stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line),
+    }
+}
+
+fn parse_values(text: &str) -> Vec<i64> {
+    text.split(',')
+        .map(|s| s.trim().parse())
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|err: ParseIntError| {
+            println!("Failed to parse input: {}", err);
+            exit(3);
+        })
+}
+
+fn read_input(filename: &str) -> Vec<i64> {
+    let input = read_to_string(filename).unwrap_or_else(|err| {
+        println!("Failed to read file '{}': {}", filename, err);
+        exit(2);
+    });
+    parse_values(&input)
+}
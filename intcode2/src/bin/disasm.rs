@@ -0,0 +1,55 @@
+use clap::{App, Arg};
+use intcode2::cfg::Cfg;
+use intcode2::decompile::decompile;
+use intcode2::disasm::Disassembly;
+use std::fs::read_to_string;
+use std::num::ParseIntError;
+use std::process::exit;
+
+fn main() {
+    let args = App::new("Intcode disassembler")
+        .arg(
+            Arg::with_name("INPUT")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("CFG")
+                .help("Print the control-flow graph in DOT format instead")
+                .short("g")
+                .long("cfg"),
+        )
+        .arg(
+            Arg::with_name("DECOMPILE")
+                .help("Print C-like pseudo-code instead")
+                .short("d")
+                .long("decompile")
+                .conflicts_with("CFG"),
+        )
+        .get_matches();
+
+    let program = read_input(args.value_of("INPUT").unwrap());
+    if args.is_present("CFG") {
+        print!("{}", Cfg::new(&program).to_dot());
+    } else if args.is_present("DECOMPILE") {
+        print!("{}", decompile(&program));
+    } else {
+        print!("{}", Disassembly::new(&program));
+    }
+}
+
+fn read_input(filename: &str) -> Vec<i64> {
+    let input = read_to_string(filename).unwrap_or_else(|err| {
+        println!("Failed to read file '{}': {}", filename, err);
+        exit(2);
+    });
+    input
+        .split(',')
+        .map(|s| s.trim().parse())
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|err: ParseIntError| {
+            println!("Failed to parse input: {}", err);
+            exit(3);
+        })
+}
@@ -0,0 +1,124 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use intcode2::trace::{read_trace, replay, TraceEntry, TraceWriter};
+use intcode2::{IntcodeComputer, SimpleInputOutput};
+use std::fs::{read_to_string, File};
+use std::io::{BufReader, BufWriter};
+use std::num::ParseIntError;
+use std::process::exit;
+
+fn main() {
+    let program_arg = Arg::with_name("PROGRAM")
+        .help("Sets the program file to use")
+        .required(true)
+        .index(1);
+    let trace_arg = Arg::with_name("TRACE")
+        .help("Sets the trace file to use")
+        .required(true)
+        .index(2);
+    let args = App::new("Intcode tracer")
+        .subcommand(
+            SubCommand::with_name("record")
+                .about("Runs a program and writes its execution trace")
+                .arg(program_arg.clone())
+                .arg(trace_arg.clone())
+                .arg(
+                    Arg::with_name("PROGRAM_INPUT")
+                        .help("Comma separated values to feed the program")
+                        .short("i")
+                        .long("input")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("Checks a program runs exactly as recorded in a trace")
+                .arg(program_arg)
+                .arg(trace_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("dump")
+                .about("Lists the instructions recorded in a trace")
+                .arg(trace_arg.index(1)),
+        )
+        .get_matches();
+
+    match args.subcommand() {
+        ("record", Some(args)) => record(args),
+        ("replay", Some(args)) => {
+            let program = read_program(args.value_of("PROGRAM").unwrap());
+            let trace = load_trace(args.value_of("TRACE").unwrap());
+            match replay(&program, &trace) {
+                Ok(()) => println!("Replay matches {} steps", trace.len()),
+                Err(divergence) => {
+                    println!("Replay diverged at {}", divergence);
+                    exit(1);
+                }
+            }
+        }
+        ("dump", Some(args)) => {
+            for entry in load_trace(args.value_of("TRACE").unwrap()) {
+                println!("{}", entry);
+            }
+        }
+        _ => {
+            println!("{}", args.usage());
+            exit(1);
+        }
+    }
+}
+
+fn record(args: &ArgMatches) {
+    let program = read_program(args.value_of("PROGRAM").unwrap());
+    let input = args
+        .value_of("PROGRAM_INPUT")
+        .map(parse_values)
+        .unwrap_or_default();
+    let filename = args.value_of("TRACE").unwrap();
+    let mut tracer = File::create(filename)
+        .and_then(|file| TraceWriter::new(BufWriter::new(file)))
+        .unwrap_or_else(|err| {
+            println!("Failed to create file '{}': {}", filename, err);
+            exit(2);
+        });
+
+    let mut computer = IntcodeComputer::new(&program, true);
+    let mut test_io = SimpleInputOutput::new(&input);
+    let result = computer.run_traced(&mut test_io, &mut tracer);
+    if let Err(err) = tracer.finish() {
+        println!("Failed to write file '{}': {}", filename, err);
+        exit(2);
+    }
+    match result {
+        Ok(true) => println!("Program halted"),
+        Ok(false) => println!("Program blocked waiting for input"),
+        Err(err) => println!("Program failed: {}", err),
+    }
+    println!("Output: {:?}", test_io.get_output());
+}
+
+fn load_trace(filename: &str) -> Vec<TraceEntry> {
+    File::open(filename)
+        .and_then(|file| read_trace(BufReader::new(file)))
+        .unwrap_or_else(|err| {
+            println!("Failed to read trace '{}': {}", filename, err);
+            exit(2);
+        })
+}
+
+fn parse_values(text: &str) -> Vec<i64> {
+    text.split(',')
+        .map(|s| s.trim().parse())
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|err: ParseIntError| {
+            println!("Failed to parse input: {}", err);
+            exit(3);
+        })
+}
+
+fn read_program(filename: &str) -> Vec<i64> {
+    let input = read_to_string(filename).unwrap_or_else(|err| {
+        println!("Failed to read file '{}': {}", filename, err);
+        exit(2);
+    });
+    parse_values(&input)
+}
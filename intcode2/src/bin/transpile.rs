@@ -0,0 +1,62 @@
+use clap::{App, Arg};
+use intcode2::transpile::transpile;
+use std::fs::{read_to_string, write};
+use std::num::ParseIntError;
+use std::process::exit;
+
+fn main() {
+    let args = App::new("Intcode to Rust transpiler")
+        .after_help(
+            "The module generated uses the intcode2 crate, which must be a \
+             dependency of the crate including it.",
+        )
+        .arg(
+            Arg::with_name("INPUT")
+                .help("Sets the input file to use")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("NAME")
+                .help("Name of the function that runs the program")
+                .short("n")
+                .long("name")
+                .takes_value(true)
+                .default_value("run"),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .help("File to write the module to instead of the output")
+                .short("o")
+                .long("output")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let program = read_input(args.value_of("INPUT").unwrap());
+    let source = transpile(&program, args.value_of("NAME").unwrap());
+    match args.value_of("OUTPUT") {
+        Some(filename) => {
+            if let Err(err) = write(filename, source) {
+                println!("Failed to write file '{}': {}", filename, err);
+                exit(2);
+            }
+        }
+        None => print!("{}", source),
+    }
+}
+
+fn read_input(filename: &str) -> Vec<i64> {
+    let input = read_to_string(filename).unwrap_or_else(|err| {
+        println!("Failed to read file '{}': {}", filename, err);
+        exit(2);
+    });
+    input
+        .split(',')
+        .map(|s| s.trim().parse())
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|err: ParseIntError| {
+            println!("Failed to parse input: {}", err);
+            exit(3);
+        })
+}
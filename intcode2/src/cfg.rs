@@ -0,0 +1,342 @@
+use crate::disasm::{
+    is_jump, return_address, Disassembly, Instruction, Item, Operand,
+};
+use crate::HALT_OP;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::mem::take;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EdgeKind {
+    /// To the next instruction, including conditional jumps not taken
+    FallThrough,
+    /// A jump to an immediate address
+    Jump,
+    /// A jump that stored its return address in a relative position first
+    Call,
+    /// From a call to the instruction the callee returns to
+    ReturnSite,
+    /// An unconditional jump through a relative position, which is how
+    /// functions return to their caller
+    Return,
+    /// Any other jump to a computed address
+    Indirect,
+}
+
+/// An edge between blocks, identified by their start address; `Return`
+/// edges have no known target and neither do `Indirect` ones, unless the
+/// jump goes through a jump table, which gives an edge to each entry
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: Option<usize>,
+    pub kind: EdgeKind,
+}
+
+/// Instructions that always execute in sequence: only the first one is
+/// jumped to and only the last one jumps
+#[derive(Clone, Debug, PartialEq)]
+pub struct BasicBlock {
+    pub instrs: Vec<Instruction>,
+}
+
+impl BasicBlock {
+    pub fn start(&self) -> usize {
+        self.instrs[0].addr
+    }
+
+    /// Address right after the last instruction
+    pub fn end(&self) -> usize {
+        let last = &self.instrs[self.instrs.len() - 1];
+        last.addr + last.size()
+    }
+}
+
+/// Control-flow graph of the code found by the disassembler
+pub struct Cfg {
+    blocks: BTreeMap<usize, BasicBlock>,
+    edges: Vec<Edge>,
+}
+
+impl Cfg {
+    pub fn new(program: &[i64]) -> Self {
+        let disassembly = Disassembly::new(program);
+        let instrs = disassembly
+            .items()
+            .iter()
+            .filter_map(|item| match item {
+                Item::Code(instr) => Some(instr.clone()),
+                Item::Data(_, _) => None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut leaders = disassembly.table_targets().clone();
+        leaders.insert(0);
+        for instr in &instrs {
+            if is_jump(instr) {
+                leaders.insert(instr.addr + instr.size());
+                if let Some(target) = jump_target(instr) {
+                    leaders.insert(target);
+                }
+            }
+            if let Some(ret_addr) = call_return_address(program, instr) {
+                leaders.insert(ret_addr);
+            }
+        }
+
+        let mut blocks = BTreeMap::new();
+        let mut add_block = |instrs: Vec<Instruction>| {
+            if !instrs.is_empty() {
+                blocks.insert(instrs[0].addr, BasicBlock { instrs });
+            }
+        };
+        let mut current: Vec<Instruction> = Vec::new();
+        for instr in instrs {
+            let contiguous = current
+                .last()
+                .is_some_and(|last| last.addr + last.size() == instr.addr);
+            if !contiguous || leaders.contains(&instr.addr) {
+                add_block(take(&mut current));
+            }
+            let ends_block = is_jump(&instr) || instr.opcode == HALT_OP;
+            current.push(instr);
+            if ends_block {
+                add_block(take(&mut current));
+            }
+        }
+        add_block(current);
+
+        let edges = blocks
+            .values()
+            .flat_map(|block| {
+                block_edges(program, block, &blocks, disassembly.jump_tables())
+            })
+            .collect();
+        Self { blocks, edges }
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &BasicBlock> {
+        self.blocks.values()
+    }
+
+    pub fn block(&self, start: usize) -> Option<&BasicBlock> {
+        self.blocks.get(&start)
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+    /// The graph in Graphviz DOT format; unresolved edges end in a "?" node
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph cfg {{").unwrap();
+        writeln!(dot, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+        for block in self.blocks() {
+            let label = block
+                .instrs
+                .iter()
+                .map(|instr| format!("{:04}  {}\\l", instr.addr, instr))
+                .collect::<String>();
+            writeln!(dot, "    b{} [label=\"{}\"];", block.start(), label)
+                .unwrap();
+        }
+        for edge in &self.edges {
+            let to = match edge.to {
+                Some(addr) => format!("b{}", addr),
+                None => {
+                    let node = format!("u{}", edge.from);
+                    writeln!(dot, "    {} [shape=none, label=\"?\"];", node)
+                        .unwrap();
+                    node
+                }
+            };
+            let attrs = match edge.kind {
+                EdgeKind::FallThrough | EdgeKind::Jump => "",
+                EdgeKind::Call => " [label=\"call\"]",
+                EdgeKind::ReturnSite => " [style=dashed]",
+                EdgeKind::Return => " [style=dotted, label=\"return\"]",
+                EdgeKind::Indirect => " [style=dotted]",
+            };
+            writeln!(dot, "    b{} -> {}{};", edge.from, to, attrs).unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+/// Where a jump goes when taken, if that's an immediate address
+fn jump_target(instr: &Instruction) -> Option<usize> {
+    match instr.operands.get(1) {
+        Some(Operand::Immediate(addr)) if instr.branch_outcomes().0 => {
+            usize::try_from(*addr).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Return address of a call sequence that keeps it on the stack, i.e. in a
+/// position relative to the base
+fn call_return_address(program: &[i64], instr: &Instruction) -> Option<usize> {
+    match instr.operands.last() {
+        Some(Operand::Relative(_)) => return_address(program, instr),
+        _ => None,
+    }
+}
+
+fn block_edges(
+    program: &[i64],
+    block: &BasicBlock,
+    blocks: &BTreeMap<usize, BasicBlock>,
+    jump_tables: &BTreeMap<usize, Vec<usize>>,
+) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    let mut add_edge = |to, kind| {
+        edges.push(Edge {
+            from: block.start(),
+            to,
+            kind,
+        })
+    };
+    let next = block.end();
+    let last = &block.instrs[block.instrs.len() - 1];
+    if last.opcode == HALT_OP {
+        return edges;
+    }
+    if !is_jump(last) {
+        if blocks.contains_key(&next) {
+            add_edge(Some(next), EdgeKind::FallThrough);
+        }
+        return edges;
+    }
+
+    let (jumps, falls_through) = last.branch_outcomes();
+    if falls_through && blocks.contains_key(&next) {
+        add_edge(Some(next), EdgeKind::FallThrough);
+    }
+    if !jumps {
+        return edges;
+    }
+    let ret_addr = block
+        .instrs
+        .iter()
+        .rev()
+        .nth(1)
+        .and_then(|instr| call_return_address(program, instr));
+    match (jump_target(last), &last.operands[1]) {
+        (Some(target), _) if ret_addr.is_some() => {
+            add_edge(Some(target), EdgeKind::Call)
+        }
+        (Some(target), _) => add_edge(Some(target), EdgeKind::Jump),
+        (None, Operand::Immediate(_)) => {}
+        (None, Operand::Relative(_)) if !falls_through => {
+            add_edge(None, EdgeKind::Return)
+        }
+        (None, _) => match jump_tables.get(&last.addr) {
+            Some(targets) => {
+                for &target in targets {
+                    add_edge(Some(target), EdgeKind::Indirect);
+                }
+            }
+            None => add_edge(None, EdgeKind::Indirect),
+        },
+    }
+    if let Some(ret_addr) = ret_addr {
+        add_edge(Some(ret_addr), EdgeKind::ReturnSite);
+    }
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_ranges(cfg: &Cfg) -> Vec<(usize, usize)> {
+        cfg.blocks()
+            .map(|block| (block.start(), block.end()))
+            .collect()
+    }
+
+    fn edge(from: usize, to: Option<usize>, kind: EdgeKind) -> Edge {
+        Edge { from, to, kind }
+    }
+
+    #[test]
+    fn loop_blocks() {
+        let program = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101,
+            0, 99,
+        ];
+        let cfg = Cfg::new(&program);
+        assert_eq!(block_ranges(&cfg), vec![(0, 15), (15, 16)]);
+        assert_eq!(
+            cfg.edges(),
+            [
+                edge(0, Some(15), EdgeKind::FallThrough),
+                edge(0, Some(0), EdgeKind::Jump),
+            ]
+        );
+    }
+
+    #[test]
+    fn calls_and_returns() {
+        // Calls 9 with return address 7, which returns through the stack
+        let program = [21101, 7, 0, 0, 1105, 1, 9, 99, 0, 2106, 0, 0];
+        let cfg = Cfg::new(&program);
+        assert_eq!(block_ranges(&cfg), vec![(0, 7), (7, 8), (9, 12)]);
+        assert_eq!(
+            cfg.edges(),
+            [
+                edge(0, Some(9), EdgeKind::Call),
+                edge(0, Some(7), EdgeKind::ReturnSite),
+                edge(9, None, EdgeKind::Return),
+            ]
+        );
+
+        // Jumps to the address stored at 6
+        let program = [5, 5, 6, 99, 0, 1, 3];
+        let cfg = Cfg::new(&program);
+        assert_eq!(block_ranges(&cfg), vec![(0, 3), (3, 4)]);
+        assert_eq!(
+            cfg.edges(),
+            [
+                edge(0, Some(3), EdgeKind::FallThrough),
+                edge(0, None, EdgeKind::Indirect),
+            ]
+        );
+
+        // Jumps to the input-th address in the table at 11
+        let program = [
+            3, 22, 1001, 22, 11, 10, 109, 1, 105, 1, 0, 13, 15, 104, 1, 99,
+        ];
+        let cfg = Cfg::new(&program);
+        assert_eq!(block_ranges(&cfg), vec![(0, 11), (13, 15), (15, 16)]);
+        assert_eq!(
+            cfg.edges(),
+            [
+                edge(0, Some(13), EdgeKind::Indirect),
+                edge(0, Some(15), EdgeKind::Indirect),
+                edge(13, Some(15), EdgeKind::FallThrough),
+            ]
+        );
+    }
+
+    #[test]
+    fn dot_export() {
+        let program = [21101, 7, 0, 0, 1105, 1, 9, 99, 0, 2106, 0, 0];
+        let dot = [
+            "digraph cfg {",
+            "    node [shape=box, fontname=\"monospace\"];",
+            "    b0 [label=\"0000  add   7, 0, rel[0]\\l0004  jt    1, 9\\l\"];",
+            "    b7 [label=\"0007  hlt\\l\"];",
+            "    b9 [label=\"0009  jf    0, rel[0]\\l\"];",
+            "    b0 -> b9 [label=\"call\"];",
+            "    b0 -> b7 [style=dashed];",
+            "    u9 [shape=none, label=\"?\"];",
+            "    b9 -> u9 [style=dotted, label=\"return\"];",
+            "}\n",
+        ]
+        .join("\n");
+        assert_eq!(Cfg::new(&program).to_dot(), dot);
+    }
+}
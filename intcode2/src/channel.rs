@@ -0,0 +1,159 @@
+use crate::memory::Memory;
+use crate::word::Word;
+use crate::{InputOutput, IntcodeComputer, IntcodeError};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread::{spawn, JoinHandle};
+
+/// Takes input from one channel and sends output to another, so a program
+/// can talk to other threads; input waits for a value unless an idle input
+/// is set
+pub struct ChannelInputOutput<W = i64> {
+    input: Receiver<W>,
+    output: Sender<W>,
+    idle_input: Option<W>,
+}
+
+impl<W> ChannelInputOutput<W> {
+    pub fn new(input: Receiver<W>, output: Sender<W>) -> Self {
+        Self {
+            input,
+            output,
+            idle_input: None,
+        }
+    }
+
+    /// Gives `value` instead of waiting when no input is queued
+    pub fn set_idle_input(&mut self, value: W) {
+        self.idle_input = Some(value);
+    }
+}
+
+impl<W: Word> InputOutput<W> for ChannelInputOutput<W> {
+    /// Gives no input once every sender is gone
+    fn provide_input(&mut self) -> Option<W> {
+        match &self.idle_input {
+            Some(idle) => match self.input.try_recv() {
+                Ok(value) => Some(value),
+                Err(TryRecvError::Empty) => Some(idle.clone()),
+                Err(TryRecvError::Disconnected) => None,
+            },
+            None => self.input.recv().ok(),
+        }
+    }
+
+    /// Rejects output once the receiver is gone
+    fn take_output(&mut self, value: W) -> Option<()> {
+        self.output.send(value).ok()
+    }
+}
+
+/// A computer running on its own thread
+pub struct Machine<W = i64> {
+    pub input: Sender<W>,
+    pub output: Receiver<W>,
+    handle: JoinHandle<Result<bool, IntcodeError>>,
+}
+
+impl<W> Machine<W> {
+    /// Waits for the program to stop, which it also does when it runs out of
+    /// input in blocking mode; returns whether it halted
+    ///
+    /// Panics if the computer's thread panicked.
+    pub fn join(self) -> Result<bool, IntcodeError> {
+        self.handle.join().expect("computer thread panicked")
+    }
+}
+
+/// Runs the computer on a new thread, with new channels for its input and
+/// output; dropping the input sender stops it once it needs more input
+pub fn spawn_machine<M>(computer: IntcodeComputer<M>) -> Machine<M::Word>
+where
+    M: Memory + Send + 'static,
+    M::Word: Send,
+{
+    let (input, receiver) = channel();
+    let (sender, output) = channel();
+    let handle =
+        spawn_with(computer, ChannelInputOutput::new(receiver, sender));
+    Machine {
+        input,
+        output,
+        handle,
+    }
+}
+
+/// Runs the computer on a new thread with the given I/O, such as a
+/// `ChannelInputOutput` connected to other machines
+pub fn spawn_with<M, IO>(
+    mut computer: IntcodeComputer<M>,
+    mut io: IO,
+) -> JoinHandle<Result<bool, IntcodeError>>
+where
+    M: Memory + Send + 'static,
+    M::Word: Send,
+    IO: InputOutput<M::Word> + Send + 'static,
+{
+    spawn(move || computer.run(&mut io))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Outputs each input plus 1 until it reads 0
+    const INCREMENT: [i64; 15] = [
+        3, 100, 1006, 100, 14, 101, 1, 100, 100, 4, 100, 1105, 1, 0, 99,
+    ];
+
+    fn increment() -> IntcodeComputer {
+        IntcodeComputer::new(&INCREMENT, false)
+    }
+
+    #[test]
+    fn machine() {
+        let machine = spawn_machine(increment());
+        for value in 1..=3 {
+            machine.input.send(value).unwrap();
+            assert_eq!(machine.output.recv(), Ok(value + 1));
+        }
+        machine.input.send(0).unwrap();
+        assert_eq!(machine.join(), Ok(true));
+    }
+
+    #[test]
+    fn pipeline() {
+        let (input, first) = channel();
+        let (between, second) = channel();
+        let (last, output) = channel();
+        let handles = vec![
+            spawn_with(increment(), ChannelInputOutput::new(first, between)),
+            spawn_with(increment(), ChannelInputOutput::new(second, last)),
+        ];
+        input.send(40).unwrap();
+        assert_eq!(output.recv(), Ok(42));
+        drop(input);
+        for handle in handles {
+            assert!(matches!(
+                handle.join().unwrap(),
+                Err(IntcodeError::NoInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn idle_input() {
+        let (input, receiver) = channel();
+        let (sender, output) = channel();
+        let mut io = ChannelInputOutput::new(receiver, sender);
+        io.set_idle_input(-1);
+        input.send(7).unwrap();
+        assert_eq!(io.provide_input(), Some(7));
+        assert_eq!(io.provide_input(), Some(-1));
+        assert_eq!(io.take_output(3), Some(()));
+        assert_eq!(output.recv(), Ok(3));
+        drop(input);
+        drop(output);
+        assert_eq!(io.provide_input(), None);
+        assert_eq!(io.take_output(3), None);
+    }
+}
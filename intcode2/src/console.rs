@@ -0,0 +1,168 @@
+use crate::ascii::AsciiInputOutput;
+use crate::{IntcodeComputer, IntcodeError};
+use std::io::Write;
+use std::mem::take;
+
+/// Runs an ASCII program one line of input at a time, keeping the lines sent
+/// so they can be repeated and optionally logging the session to a writer
+pub struct Console {
+    computer: IntcodeComputer,
+    io: AsciiInputOutput,
+    history: Vec<String>,
+    transcript: Option<Box<dyn Write + Send>>,
+    // Characters of the current output line returned so far
+    shown: usize,
+    values_shown: usize,
+}
+
+impl Console {
+    pub fn new(program: &[i64]) -> Self {
+        Self {
+            computer: IntcodeComputer::new(program, true),
+            io: AsciiInputOutput::new(),
+            history: Vec::new(),
+            transcript: None,
+            shown: 0,
+            values_shown: 0,
+        }
+    }
+
+    pub fn computer_mut(&mut self) -> &mut IntcodeComputer {
+        &mut self.computer
+    }
+
+    /// Logs the output and every line sent to `writer`, as they appear on a
+    /// terminal
+    pub fn set_transcript<W: Write + Send + 'static>(&mut self, writer: W) {
+        self.transcript = Some(Box::new(writer));
+    }
+
+    /// Runs until the program halts or waits for more input; returns whether
+    /// it halted along with what it output meanwhile, with each value outside
+    /// the ASCII range on a line of its own
+    pub fn run(&mut self) -> Result<(bool, String), IntcodeError> {
+        let result = self.computer.run(&mut self.io);
+        let text = self.new_output();
+        self.log(&text);
+        result.map(|halted| (halted, text))
+    }
+
+    fn new_output(&mut self) -> String {
+        let mut text = String::new();
+        for line in self.io.take_lines() {
+            text.push_str(&line[take(&mut self.shown)..]);
+            text.push('\n');
+        }
+        let partial = self.io.partial_line();
+        text.push_str(&partial[self.shown..]);
+        self.shown = partial.len();
+        for value in &self.io.values()[self.values_shown..] {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&format!("{}\n", value));
+        }
+        self.values_shown = self.io.values().len();
+        text
+    }
+
+    /// Queues a line of input, after replacing `!!` with the last line sent
+    /// or `!n` with the nth one; returns the line queued
+    pub fn send(&mut self, line: &str) -> Result<String, String> {
+        let line = self.expand(line)?;
+        self.io.push_line(&line);
+        self.log(&format!("{}\n", line));
+        self.history.push(line.clone());
+        Ok(line)
+    }
+
+    fn expand(&self, line: &str) -> Result<String, String> {
+        let index = match line.strip_prefix('!') {
+            Some("!") => self.history.len().checked_sub(1),
+            Some(number) => number
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid history reference: {}", line))?
+                .checked_sub(1),
+            None => return Ok(line.to_string()),
+        };
+        index
+            .and_then(|index| self.history.get(index))
+            .cloned()
+            .ok_or_else(|| format!("No such line in history: {}", line))
+    }
+
+    /// Lines sent so far, oldest first
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    fn log(&mut self, text: &str) {
+        if let Some(transcript) = &mut self.transcript {
+            // Failing to log is no reason to interrupt the session
+            let _ = transcript
+                .write_all(text.as_bytes())
+                .and_then(|_| transcript.flush());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    // Prompts with "> " and echoes a line of input, forever
+    const ECHO: [i64; 18] = [
+        104, 62, 104, 32, 3, 100, 4, 100, 1008, 100, 10, 101, 1006, 101, 4,
+        1105, 1, 0,
+    ];
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn session() {
+        let transcript = SharedBuffer::default();
+        let mut console = Console::new(&ECHO);
+        console.set_transcript(transcript.clone());
+        assert_eq!(console.run(), Ok((false, "> ".to_string())));
+        assert_eq!(console.send("hello"), Ok("hello".to_string()));
+        assert_eq!(console.run(), Ok((false, "hello\n> ".to_string())));
+        assert_eq!(console.send("!!"), Ok("hello".to_string()));
+        assert_eq!(console.run(), Ok((false, "hello\n> ".to_string())));
+        assert_eq!(
+            String::from_utf8(transcript.0.lock().unwrap().clone()).unwrap(),
+            "> hello\nhello\n> hello\nhello\n> "
+        );
+    }
+
+    #[test]
+    fn history() {
+        let mut console = Console::new(&ECHO);
+        assert!(console.send("!!").is_err());
+        console.send("one").unwrap();
+        console.send("two").unwrap();
+        assert_eq!(console.send("!1"), Ok("one".to_string()));
+        assert_eq!(console.send("!!"), Ok("one".to_string()));
+        assert!(console.send("!5").is_err());
+        assert!(console.send("!x").is_err());
+        assert_eq!(console.history(), ["one", "two", "one", "one"]);
+    }
+
+    #[test]
+    fn results() {
+        let mut console = Console::new(&[104, 33, 104, 1000, 99]);
+        assert_eq!(console.run(), Ok((true, "!\n1000\n".to_string())));
+    }
+}
@@ -0,0 +1,530 @@
+use crate::disasm::Instruction;
+use crate::watch::WatchMode;
+use crate::{InputOutput, IntcodeComputer, Status};
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::str::FromStr;
+
+const MEM_PER_LINE: usize = 8;
+
+/// Most positions `mem` shows at once
+const MEM_LIMIT: usize = 4096;
+
+/// Instructions kept by `record` unless told otherwise
+const DEFAULT_HISTORY: usize = 1_000_000;
+
+pub const HELP: &str = "\
+step [count]        execute one or more instructions
+continue            run until a breakpoint, halt or blocked I/O
+record [count]      keep the last instructions so they can be undone
+rstep [count]       undo one or more instructions
+rcontinue           undo instructions back to the previous breakpoint
+break [addr]        set a breakpoint, or list breakpoints
+delete addr         remove a breakpoint
+watch [addr [count]] pause when positions are written, or list watchpoints
+rwatch addr [count] pause when positions are read
+awatch addr [count] pause when positions are read or written
+unwatch addr [count] remove a watchpoint
+print [ip|base|addr] show registers and next instruction, or a value
+set ip|base|addr value
+                    change a register or a memory position
+mem addr [count]    dump memory
+quit                leave the debugger";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    InstrPtr,
+    Base,
+    Memory(usize),
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ip" => Ok(Target::InstrPtr),
+            "base" => Ok(Target::Base),
+            _ => parse_number(s).map(Target::Memory),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Step(usize),
+    Continue,
+    Record(usize),
+    ReverseStep(usize),
+    ReverseContinue,
+    Break(Option<usize>),
+    Delete(usize),
+    Watch(Option<(usize, usize, WatchMode)>),
+    Unwatch(usize, usize),
+    Print(Option<Target>),
+    Set(Target, i64),
+    Mem(usize, usize),
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s.split_whitespace().collect::<Vec<_>>();
+        let command = match words.as_slice() {
+            ["step"] | ["s"] => Command::Step(1),
+            ["step", count] | ["s", count] => {
+                Command::Step(parse_number(count)?)
+            }
+            ["continue"] | ["c"] => Command::Continue,
+            ["record"] => Command::Record(DEFAULT_HISTORY),
+            ["record", count] => Command::Record(parse_number(count)?),
+            ["rstep"] | ["rs"] => Command::ReverseStep(1),
+            ["rstep", count] | ["rs", count] => {
+                Command::ReverseStep(parse_number(count)?)
+            }
+            ["rcontinue"] | ["rc"] => Command::ReverseContinue,
+            ["break"] | ["b"] => Command::Break(None),
+            ["break", addr] | ["b", addr] => {
+                Command::Break(Some(parse_number(addr)?))
+            }
+            ["delete", addr] | ["d", addr] => {
+                Command::Delete(parse_number(addr)?)
+            }
+            ["watch"] | ["w"] => Command::Watch(None),
+            [name, addr] | [name, addr, _]
+                if ["watch", "w", "rwatch", "awatch"].contains(name) =>
+            {
+                let mode = match *name {
+                    "rwatch" => WatchMode::Read,
+                    "awatch" => WatchMode::ReadWrite,
+                    _ => WatchMode::Write,
+                };
+                let count = match words.get(2) {
+                    Some(count) => parse_number(count)?,
+                    None => 1,
+                };
+                let addr = parse_number(addr)?;
+                positions(addr, count)?;
+                Command::Watch(Some((addr, count, mode)))
+            }
+            ["unwatch", addr] => Command::Unwatch(parse_number(addr)?, 1),
+            ["unwatch", addr, count] => {
+                let (addr, count) = (parse_number(addr)?, parse_number(count)?);
+                positions(addr, count)?;
+                Command::Unwatch(addr, count)
+            }
+            ["print"] | ["p"] => Command::Print(None),
+            ["print", target] | ["p", target] => {
+                Command::Print(Some(target.parse()?))
+            }
+            ["set", target, value] => {
+                Command::Set(target.parse()?, parse_number(value)?)
+            }
+            ["mem", addr] | ["m", addr] => {
+                Command::Mem(parse_number(addr)?, MEM_PER_LINE)
+            }
+            ["mem", addr, count] | ["m", addr, count] => {
+                let (addr, count) = (parse_number(addr)?, parse_number(count)?);
+                if count > MEM_LIMIT {
+                    return Err(format!(
+                        "mem shows at most {} positions",
+                        MEM_LIMIT
+                    ));
+                }
+                positions(addr, count)?;
+                Command::Mem(addr, count)
+            }
+            ["help"] | ["h"] => Command::Help,
+            ["quit"] | ["q"] => Command::Quit,
+            _ => return Err(format!("invalid command '{}'", s.trim())),
+        };
+        Ok(command)
+    }
+}
+
+/// The `count` positions from `addr` on, unless they go past the last one
+fn positions(addr: usize, count: usize) -> Result<Range<usize>, String> {
+    match addr.checked_add(count) {
+        Some(end) => Ok(addr..end),
+        None => {
+            Err(format!("{} positions from {} is out of range", count, addr))
+        }
+    }
+}
+
+fn range_name(range: &Range<usize>) -> String {
+    if range.len() == 1 {
+        range.start.to_string()
+    } else {
+        format!("{}..{}", range.start, range.end)
+    }
+}
+
+fn parse_number<T: FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("invalid number '{}'", s))
+}
+
+/// Drives an `IntcodeComputer` one command at a time
+pub struct Debugger<'a> {
+    computer: &'a mut IntcodeComputer,
+    io: &'a mut dyn InputOutput,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(
+        computer: &'a mut IntcodeComputer,
+        io: &'a mut dyn InputOutput,
+    ) -> Self {
+        Self { computer, io }
+    }
+
+    /// Executes a command and returns the text to show for it
+    pub fn execute(&mut self, command: Command) -> String {
+        match command {
+            Command::Step(count) => {
+                let mut status = Status::Running;
+                for _ in 0..count {
+                    status = match self.computer.step_to_watchpoint(self.io) {
+                        Ok(status) => status,
+                        Err(err) => return format!("error: {}", err),
+                    };
+                    if status != Status::Running {
+                        break;
+                    }
+                }
+                self.describe(status)
+            }
+            Command::Continue => {
+                match self.computer.run_to_breakpoint(self.io) {
+                    Ok(status) => self.describe(status),
+                    Err(err) => format!("error: {}", err),
+                }
+            }
+            Command::Record(count) => {
+                self.computer.record_history(count);
+                format!("recording the last {} instructions", count)
+            }
+            Command::ReverseStep(count) => {
+                for _ in 0..count {
+                    if !self.computer.reverse_step() {
+                        return format!(
+                            "start of recorded history\n{}",
+                            self.location()
+                        );
+                    }
+                }
+                self.location()
+            }
+            Command::ReverseContinue => {
+                if self.computer.reverse_continue() {
+                    let addr = self.computer.instr_ptr();
+                    format!("breakpoint at {}\n{}", addr, self.location())
+                } else {
+                    format!("start of recorded history\n{}", self.location())
+                }
+            }
+            Command::Break(Some(addr)) => {
+                self.computer.add_breakpoint(addr);
+                format!("breakpoint set at {}", addr)
+            }
+            Command::Break(None) => {
+                let breakpoints = self
+                    .computer
+                    .breakpoints()
+                    .iter()
+                    .map(|addr| addr.to_string())
+                    .collect::<Vec<_>>();
+                if breakpoints.is_empty() {
+                    "no breakpoints".to_string()
+                } else {
+                    format!("breakpoints: {}", breakpoints.join(", "))
+                }
+            }
+            Command::Delete(addr) => {
+                if self.computer.remove_breakpoint(addr) {
+                    format!("breakpoint at {} removed", addr)
+                } else {
+                    format!("no breakpoint at {}", addr)
+                }
+            }
+            Command::Watch(Some((addr, count, mode))) => {
+                let range = match positions(addr, count) {
+                    Ok(range) => range,
+                    Err(err) => return err,
+                };
+                let name = range_name(&range);
+                self.computer.add_watchpoint(range, mode);
+                format!("watchpoint set on {}", name)
+            }
+            Command::Watch(None) => {
+                let watchpoints = self
+                    .computer
+                    .watchpoints()
+                    .iter()
+                    .map(|watchpoint| {
+                        let name = range_name(&watchpoint.range);
+                        match watchpoint.mode {
+                            WatchMode::Read => format!("{} (read)", name),
+                            WatchMode::Write => name,
+                            WatchMode::ReadWrite => {
+                                format!("{} (access)", name)
+                            }
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                if watchpoints.is_empty() {
+                    "no watchpoints".to_string()
+                } else {
+                    format!("watchpoints: {}", watchpoints.join(", "))
+                }
+            }
+            Command::Unwatch(addr, count) => {
+                let range = match positions(addr, count) {
+                    Ok(range) => range,
+                    Err(err) => return err,
+                };
+                let name = range_name(&range);
+                if self.computer.remove_watchpoint(range) {
+                    format!("watchpoint on {} removed", name)
+                } else {
+                    format!("no watchpoint on {}", name)
+                }
+            }
+            Command::Print(None) => self.location(),
+            Command::Print(Some(Target::InstrPtr)) => {
+                format!("ip = {}", self.computer.instr_ptr())
+            }
+            Command::Print(Some(Target::Base)) => {
+                format!("base = {}", self.computer.base())
+            }
+            Command::Print(Some(Target::Memory(addr))) => {
+                format!("[{}] = {}", addr, self.computer.read_memory(addr))
+            }
+            Command::Set(Target::InstrPtr, value) => {
+                let addr = match usize::try_from(value) {
+                    Ok(addr) => addr,
+                    Err(_) => return format!("invalid address {}", value),
+                };
+                // The longest instruction must fit before the end of memory
+                if let Err(err) = positions(addr, 4) {
+                    return err;
+                }
+                self.computer.set_instr_ptr(addr);
+                self.location()
+            }
+            Command::Set(Target::Base, value) => {
+                self.computer.set_base(value);
+                format!("base = {}", value)
+            }
+            Command::Set(Target::Memory(addr), value) => {
+                self.computer.write_memory(addr, value);
+                format!("[{}] = {}", addr, value)
+            }
+            Command::Mem(addr, count) => {
+                let range = match positions(addr, count.min(MEM_LIMIT)) {
+                    Ok(range) => range,
+                    Err(err) => return err,
+                };
+                self.dump(range)
+            }
+            Command::Help => HELP.to_string(),
+            Command::Quit => String::new(),
+        }
+    }
+
+    fn describe(&self, status: Status) -> String {
+        match status {
+            Status::Running => self.location(),
+            Status::Halted => "program halted".to_string(),
+            Status::Blocked => {
+                format!("blocked on I/O\n{}", self.location())
+            }
+            Status::Breakpoint(addr) => {
+                format!("breakpoint at {}\n{}", addr, self.location())
+            }
+            Status::Watchpoint(event) => {
+                format!("{}\n{}", event, self.location())
+            }
+        }
+    }
+
+    fn dump(&self, range: Range<usize>) -> String {
+        range
+            .clone()
+            .step_by(MEM_PER_LINE)
+            .map(|start| {
+                let end = start.saturating_add(MEM_PER_LINE).min(range.end);
+                let values = (start..end)
+                    .map(|pos| self.computer.read_memory(pos).to_string())
+                    .collect::<Vec<_>>();
+                format!("{:04}  {}", start, values.join(" "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Registers and the instruction about to be executed
+    fn location(&self) -> String {
+        let instr_ptr = self.computer.instr_ptr();
+        let instr = Instruction::decode_with(
+            |pos| Some(self.computer.read_memory(pos)),
+            instr_ptr,
+        )
+        .map(|instr| instr.to_string())
+        .unwrap_or_else(|| {
+            format!(".data {}", self.computer.read_memory(instr_ptr))
+        });
+        format!(
+            "ip = {}, base = {}\n{:04}  {}",
+            instr_ptr,
+            self.computer.base(),
+            instr_ptr,
+            instr
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimpleInputOutput;
+
+    #[test]
+    fn parse_commands() {
+        let tests = vec![
+            ("step", Command::Step(1)),
+            ("s 10", Command::Step(10)),
+            ("c", Command::Continue),
+            ("record 50", Command::Record(50)),
+            ("rs 3", Command::ReverseStep(3)),
+            ("rcontinue", Command::ReverseContinue),
+            ("break 12", Command::Break(Some(12))),
+            ("print base", Command::Print(Some(Target::Base))),
+            ("set 100 -3", Command::Set(Target::Memory(100), -3)),
+            ("set ip 4", Command::Set(Target::InstrPtr, 4)),
+            ("mem 100 4", Command::Mem(100, 4)),
+            (
+                "watch 100",
+                Command::Watch(Some((100, 1, WatchMode::Write))),
+            ),
+            (
+                "awatch 100 2",
+                Command::Watch(Some((100, 2, WatchMode::ReadWrite))),
+            ),
+            ("unwatch 100 2", Command::Unwatch(100, 2)),
+        ];
+        for (text, command) in tests {
+            assert_eq!(text.parse(), Ok(command));
+        }
+        assert!("step x".parse::<Command>().is_err());
+        assert!("jump 5".parse::<Command>().is_err());
+        let huge = usize::MAX.to_string();
+        for text in &[
+            format!("watch 1 {}", huge),
+            format!("rwatch {} 2", huge),
+            format!("unwatch 1 {}", huge),
+            format!("mem 1 {}", huge),
+            format!("mem {} 2", huge),
+            format!("mem 0 {}", MEM_LIMIT + 1),
+        ] {
+            assert!(text.parse::<Command>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn session() {
+        let program = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101,
+            0, 99,
+        ];
+        let mut computer = IntcodeComputer::new(&program, false);
+        let mut test_io = SimpleInputOutput::default();
+        let mut debugger = Debugger::new(&mut computer, &mut test_io);
+        let mut run =
+            |command: &str| debugger.execute(command.parse().unwrap());
+
+        assert_eq!(run("step"), "ip = 2, base = 1\n0002  out   rel[-1]");
+        assert_eq!(run("break 12"), "breakpoint set at 12");
+        assert_eq!(
+            run("continue"),
+            "breakpoint at 12\nip = 12, base = 1\n0012  jf    [101], 0"
+        );
+        assert_eq!(run("print 100"), "[100] = 1");
+        assert_eq!(run("set 100 15"), "[100] = 15");
+        assert_eq!(
+            run("continue"),
+            "breakpoint at 12\nip = 12, base = 2\n0012  jf    [101], 0"
+        );
+        assert_eq!(run("mem 100 2"), "0100  16 1");
+        assert_eq!(run("delete 12"), "breakpoint at 12 removed");
+        assert_eq!(run("rwatch 101"), "watchpoint set on 101");
+        assert_eq!(run("watch"), "watchpoints: 101 (read)");
+        assert_eq!(
+            run("continue"),
+            "read [101] = 1 at 12\nip = 15, base = 2\n0015  hlt"
+        );
+        assert_eq!(run("unwatch 101"), "watchpoint on 101 removed");
+        assert_eq!(run("continue"), "program halted");
+        assert_eq!(
+            debugger.execute(Command::Watch(Some((
+                1,
+                usize::MAX,
+                WatchMode::Write
+            )))),
+            format!("{} positions from 1 is out of range", usize::MAX)
+        );
+        assert_eq!(
+            debugger.execute(Command::Mem(usize::MAX - 1, 1)),
+            format!("{}  0", usize::MAX - 1)
+        );
+        assert_eq!(
+            debugger.execute(Command::Set(Target::InstrPtr, -1)),
+            "invalid address -1"
+        );
+        assert_eq!(
+            debugger.execute(Command::Set(Target::InstrPtr, i64::MAX)),
+            format!("ip = {}, base = 2\n{:04}  .data 0", i64::MAX, i64::MAX)
+        );
+        assert_eq!(test_io.get_output(), vec![109, 1]);
+    }
+
+    #[test]
+    fn reverse_session() {
+        let program = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101,
+            0, 99,
+        ];
+        let mut computer = IntcodeComputer::new(&program, false);
+        let mut test_io = SimpleInputOutput::default();
+        let mut debugger = Debugger::new(&mut computer, &mut test_io);
+        let mut run =
+            |command: &str| debugger.execute(command.parse().unwrap());
+
+        assert_eq!(
+            run("rs"),
+            "start of recorded history\nip = 0, base = 0\n0000  rbo   1"
+        );
+        assert_eq!(run("record"), "recording the last 1000000 instructions");
+        assert_eq!(run("break 12"), "breakpoint set at 12");
+        run("continue");
+        run("continue");
+        assert_eq!(run("print 100"), "[100] = 2");
+        assert_eq!(
+            run("rcontinue"),
+            "breakpoint at 12\nip = 12, base = 1\n0012  jf    [101], 0"
+        );
+        assert_eq!(run("print 100"), "[100] = 1");
+        assert_eq!(
+            run("rs 2"),
+            "ip = 4, base = 1\n0004  add   [100], 1, [100]"
+        );
+        assert_eq!(run("print 100"), "[100] = 0");
+        assert_eq!(
+            run("rc"),
+            "start of recorded history\nip = 0, base = 0\n0000  rbo   1"
+        );
+    }
+}
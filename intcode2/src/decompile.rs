@@ -0,0 +1,909 @@
+use crate::cfg::{BasicBlock, Cfg, EdgeKind};
+use crate::disasm::{Instruction, Operand};
+use crate::{
+    ADD_OP, BASE_OP, EQ_OP, HALT_OP, IN_OP, JIF_OP, JIT_OP, LT_OP, MULT_OP,
+    OUT_OP,
+};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+
+const INDENT: &str = "    ";
+
+/// Turns a program into C-like pseudo-code, one function per call target.
+///
+/// Relative positions are named after the function's stack frame: `local1`
+/// and up are the slots below the frame size set by the function's `rbo`,
+/// `ret_addr` holds its return address and `arg1` and up are the arguments
+/// of the functions it calls. Loops and if/else are recovered from the
+/// jumps; whatever doesn't fit them becomes a `goto`. Jump table targets
+/// are labelled blocks of the function jumping through the table, and any
+/// code none of the functions reach starts a function of its own, so every
+/// block of the CFG is printed.
+pub fn decompile(program: &[i64]) -> String {
+    let cfg = Cfg::new(program);
+    let code = cfg
+        .blocks()
+        .flat_map(|block| block.start()..block.end())
+        .collect::<HashSet<_>>();
+    let mut entries = BTreeSet::new();
+    entries.insert(0);
+    entries.extend(
+        cfg.edges()
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::Call)
+            .filter_map(|edge| edge.to),
+    );
+    entries.retain(|&entry| cfg.block(entry).is_some());
+
+    // A function never reaches code added as an entry after it, so the
+    // earlier ones don't change as the uncovered blocks become functions
+    let mut functions = BTreeMap::new();
+    let mut covered = HashSet::new();
+    let mut pending = entries.iter().copied().collect::<Vec<_>>();
+    let mut leftover = cfg.blocks().map(BasicBlock::start);
+    loop {
+        let entry = match pending.pop() {
+            Some(entry) => entry,
+            None => match leftover.find(|start| !covered.contains(start)) {
+                Some(start) => {
+                    entries.insert(start);
+                    start
+                }
+                None => break,
+            },
+        };
+        let function = Function::new(&cfg, entry, &entries, &code);
+        covered.extend(function.blocks.keys().copied());
+        functions.insert(entry, function);
+    }
+
+    let mut output = String::new();
+    for function in functions.values() {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&function.to_string(&entries));
+    }
+    output
+}
+
+fn function_name(entry: usize) -> String {
+    if entry == 0 {
+        "main".to_string()
+    } else {
+        format!("sub_{}", entry)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Cond {
+    NonZero(String),
+    Zero(String),
+    Compare(String, &'static str, String),
+}
+
+impl Cond {
+    fn negate(&self) -> Self {
+        match self {
+            Cond::NonZero(value) => Cond::Zero(value.clone()),
+            Cond::Zero(value) => Cond::NonZero(value.clone()),
+            Cond::Compare(left, op, right) => {
+                let op = match *op {
+                    "==" => "!=",
+                    "!=" => "==",
+                    "<" => ">=",
+                    _ => "<",
+                };
+                Cond::Compare(left.clone(), op, right.clone())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Cond {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cond::NonZero(value) => write!(f, "{}", value),
+            Cond::Zero(value) => write!(f, "!{}", value),
+            Cond::Compare(left, op, right) => {
+                write!(f, "{} {} {}", left, op, right)
+            }
+        }
+    }
+}
+
+/// How control leaves a block
+#[derive(Clone, Debug, PartialEq)]
+enum Exit {
+    Next,
+    Halt,
+    Jump(usize),
+    Branch(Cond, usize),
+    Call(usize),
+    Return,
+    Indirect(Option<Cond>, String),
+}
+
+struct FnBlock {
+    end: usize,
+    stmts: Vec<String>,
+    exit: Exit,
+}
+
+struct Function {
+    entry: usize,
+    frame: i64,
+    blocks: BTreeMap<usize, FnBlock>,
+    /// Where the function's jumps through tables may go
+    table_targets: HashSet<usize>,
+}
+
+impl Function {
+    /// Collects the blocks reachable from `entry` without entering other
+    /// functions, tracking the relative base from the function's entry
+    fn new(
+        cfg: &Cfg,
+        entry: usize,
+        entries: &BTreeSet<usize>,
+        code: &HashSet<usize>,
+    ) -> Self {
+        let mut offsets = BTreeMap::new();
+        let mut pending = VecDeque::new();
+        let mut table_targets = HashSet::new();
+        pending.push_back((entry, Some(0)));
+        while let Some((start, offset)) = pending.pop_front() {
+            if offsets.contains_key(&start) {
+                continue;
+            }
+            let block = match cfg.block(start) {
+                Some(block) => block,
+                None => continue,
+            };
+            offsets.insert(start, offset);
+            let offset_out = block
+                .instrs
+                .iter()
+                .fold(offset, |offset, instr| base_after(instr, offset));
+            for edge in cfg.edges().iter().filter(|edge| edge.from == start) {
+                let to = match (edge.kind, edge.to) {
+                    (EdgeKind::FallThrough, Some(to))
+                    | (EdgeKind::Jump, Some(to))
+                    | (EdgeKind::ReturnSite, Some(to))
+                    | (EdgeKind::Indirect, Some(to)) => to,
+                    _ => continue,
+                };
+                if edge.kind == EdgeKind::Indirect {
+                    table_targets.insert(to);
+                }
+                if !entries.contains(&to) {
+                    pending.push_back((to, offset_out));
+                }
+            }
+        }
+
+        let frame = offsets
+            .iter()
+            .flat_map(|(&start, &offset)| {
+                let instrs = &cfg.block(start).unwrap().instrs;
+                instrs.iter().scan(offset, |offset, instr| {
+                    *offset = base_after(instr, *offset);
+                    Some(*offset)
+                })
+            })
+            .flatten()
+            .max()
+            .unwrap_or(0)
+            .max(0);
+
+        let blocks = offsets
+            .into_iter()
+            .map(|(start, offset)| {
+                let block = cfg.block(start).unwrap();
+                let edges =
+                    cfg.edges().iter().filter(|edge| edge.from == start);
+                let kinds =
+                    edges.map(|edge| (edge.kind, edge.to)).collect::<Vec<_>>();
+                let fn_block = FnBlock::new(block, offset, frame, &kinds, code);
+                (start, fn_block)
+            })
+            .collect();
+        Self {
+            entry,
+            frame,
+            blocks,
+            table_targets,
+        }
+    }
+
+    fn to_string(&self, entries: &BTreeSet<usize>) -> String {
+        let mut emitter = Emitter {
+            function: self,
+            entries,
+            lines: Vec::new(),
+            // Jump table targets are labelled for the indirect jumps
+            gotos: self.table_targets.clone(),
+        };
+        if self.blocks.keys().next() != Some(&self.entry) {
+            emitter.goto(self.entry, 1);
+        }
+        emitter.emit_range(0, usize::MAX, usize::MAX, None, 1);
+
+        let mut text = format!("fn {}() {{\n", function_name(self.entry));
+        if self.frame > 0 {
+            let plural = if self.frame == 1 { "" } else { "s" };
+            writeln!(text, "{}// frame: {} slot{}", INDENT, self.frame, plural)
+                .unwrap();
+        }
+        for line in emitter.lines {
+            match line {
+                Line::Text(indent, stmt) => {
+                    writeln!(text, "{}{}", INDENT.repeat(indent), stmt)
+                        .unwrap();
+                }
+                Line::Label(indent, addr) if emitter.gotos.contains(&addr) => {
+                    let indent = INDENT.repeat(indent - 1);
+                    writeln!(text, "{}L_{}:", indent, addr).unwrap();
+                }
+                Line::Label(_, _) => {}
+            }
+        }
+        text.push_str("}\n");
+        text
+    }
+}
+
+fn base_after(instr: &Instruction, offset: Option<i64>) -> Option<i64> {
+    match (instr.opcode, instr.operands.first()) {
+        (BASE_OP, Some(Operand::Immediate(value))) => {
+            offset.map(|offset| offset + value)
+        }
+        (BASE_OP, _) => None,
+        _ => offset,
+    }
+}
+
+impl FnBlock {
+    fn new(
+        block: &BasicBlock,
+        offset: Option<i64>,
+        frame: i64,
+        edges: &[(EdgeKind, Option<usize>)],
+        code: &HashSet<usize>,
+    ) -> Self {
+        let has_edge = |kind| edges.iter().any(|&(edge, _)| edge == kind);
+        let mut instrs = block.instrs.iter().collect::<Vec<_>>();
+        let last = instrs[instrs.len() - 1];
+        let operand = |instr: &Instruction, param: usize, offset| {
+            operand_name(&instr.operands[param], offset, frame)
+        };
+
+        let end_offset = instrs
+            .iter()
+            .fold(offset, |offset, instr| base_after(instr, offset));
+        let exit = match last.opcode {
+            HALT_OP => Exit::Halt,
+            JIT_OP | JIF_OP if has_edge(EdgeKind::Call) => {
+                let target = edges
+                    .iter()
+                    .find(|&&(kind, _)| kind == EdgeKind::Call)
+                    .and_then(|&(_, to)| to)
+                    .unwrap();
+                // The return address is stored just before the jump
+                instrs.pop();
+                Exit::Call(target)
+            }
+            JIT_OP | JIF_OP if has_edge(EdgeKind::Return) => Exit::Return,
+            JIT_OP | JIF_OP => {
+                let value = operand(last, 0, end_offset);
+                let cond = if last.opcode == JIT_OP {
+                    Cond::NonZero(value)
+                } else {
+                    Cond::Zero(value)
+                };
+                match (last.branch_outcomes(), &last.operands[1]) {
+                    ((false, _), _) => Exit::Next,
+                    ((true, falls_through), Operand::Immediate(target))
+                        if *target >= 0 =>
+                    {
+                        let target = *target as usize;
+                        if falls_through {
+                            Exit::Branch(cond, target)
+                        } else {
+                            Exit::Jump(target)
+                        }
+                    }
+                    ((true, falls_through), _) => {
+                        let target = operand(last, 1, end_offset);
+                        Exit::Indirect(
+                            Some(cond).filter(|_| falls_through),
+                            target,
+                        )
+                    }
+                }
+            }
+            _ => Exit::Next,
+        };
+        if exit != Exit::Next {
+            instrs.pop();
+        }
+
+        // A comparison feeding the jump is folded into its condition
+        let mut exit = exit;
+        if let (Exit::Branch(cond, target), Some(compare)) =
+            (&exit, instrs.last())
+        {
+            let op = match compare.opcode {
+                LT_OP => Some("<"),
+                EQ_OP => Some("=="),
+                _ => None,
+            };
+            if let Some(op) = op
+                .filter(|_| Some(&compare.operands[2]) == last.operands.first())
+            {
+                let compare_offset = instrs[..instrs.len() - 1]
+                    .iter()
+                    .fold(offset, |offset, instr| base_after(instr, offset));
+                let folded = Cond::Compare(
+                    operand(compare, 0, compare_offset),
+                    op,
+                    operand(compare, 1, compare_offset),
+                );
+                let folded = match cond {
+                    Cond::Zero(_) => folded.negate(),
+                    _ => folded,
+                };
+                exit = Exit::Branch(folded, *target);
+                instrs.pop();
+            }
+        }
+
+        let mut stmts = Vec::new();
+        let mut offset = offset;
+        for instr in instrs {
+            if let Some(stmt) = statement(instr, offset, frame, code) {
+                stmts.push(stmt);
+            }
+            offset = base_after(instr, offset);
+        }
+        Self {
+            end: block.end(),
+            stmts,
+            exit,
+        }
+    }
+}
+
+/// Name of an operand in the pseudo-code; relative positions are named
+/// after their slot in the frame when the relative base is known
+fn operand_name(operand: &Operand, offset: Option<i64>, frame: i64) -> String {
+    match (operand, offset) {
+        (Operand::Immediate(value), _) => value.to_string(),
+        (Operand::Position(addr), _) => format!("mem[{}]", addr),
+        (Operand::Relative(delta), None) => format!("rel[{}]", delta),
+        (Operand::Relative(delta), Some(offset)) => {
+            let slot = offset + delta;
+            if slot < 0 {
+                format!("caller{}", -slot)
+            } else if slot == 0 {
+                "ret_addr".to_string()
+            } else if slot < frame {
+                format!("local{}", slot)
+            } else if slot == frame {
+                "callee_ret_addr".to_string()
+            } else {
+                format!("arg{}", slot - frame)
+            }
+        }
+    }
+}
+
+fn statement(
+    instr: &Instruction,
+    offset: Option<i64>,
+    frame: i64,
+    code: &HashSet<usize>,
+) -> Option<String> {
+    let operand =
+        |param: usize| operand_name(&instr.operands[param], offset, frame);
+    let stmt = match instr.opcode {
+        ADD_OP => format!("{} = {};", operand(2), sum(operand(0), operand(1))),
+        MULT_OP => {
+            format!("{} = {};", operand(2), product(operand(0), operand(1)))
+        }
+        LT_OP => format!("{} = {} < {};", operand(2), operand(0), operand(1)),
+        EQ_OP => format!("{} = {} == {};", operand(2), operand(0), operand(1)),
+        IN_OP => format!("{} = input();", operand(0)),
+        OUT_OP => format!("output({});", operand(0)),
+        // Immediate adjustments set up and tear down the frame
+        BASE_OP => match instr.operands[0] {
+            Operand::Immediate(_) => return None,
+            _ => format!("base += {};", operand(0)),
+        },
+        _ => return None,
+    };
+    match instr.operands.last() {
+        Some(Operand::Position(addr))
+            if instr.opcode != OUT_OP
+                && usize::try_from(*addr)
+                    .is_ok_and(|addr| code.contains(&addr)) =>
+        {
+            Some(format!("{} // modifies code", stmt))
+        }
+        _ => Some(stmt),
+    }
+}
+
+fn sum(left: String, right: String) -> String {
+    if left == "0" {
+        right
+    } else if right == "0" {
+        left
+    } else if let Some(negated) = right.strip_prefix('-') {
+        format!("{} - {}", left, negated)
+    } else {
+        format!("{} + {}", left, right)
+    }
+}
+
+fn product(left: String, right: String) -> String {
+    if left == "0" || right == "0" {
+        "0".to_string()
+    } else if left == "1" {
+        right
+    } else if right == "1" {
+        left
+    } else {
+        format!("{} * {}", left, right)
+    }
+}
+
+enum Line {
+    Text(usize, String),
+    Label(usize, usize),
+}
+
+#[derive(Clone, Copy)]
+struct Loop {
+    header: usize,
+    exit: usize,
+    do_while: bool,
+}
+
+/// Writes the blocks of a function as nested statements
+struct Emitter<'a> {
+    function: &'a Function,
+    entries: &'a BTreeSet<usize>,
+    lines: Vec<Line>,
+    gotos: HashSet<usize>,
+}
+
+impl<'a> Emitter<'a> {
+    fn line(&mut self, indent: usize, text: String) {
+        self.lines.push(Line::Text(indent, text));
+    }
+
+    fn goto(&mut self, target: usize, indent: usize) {
+        let own_block = self.function.blocks.contains_key(&target);
+        if self.entries.contains(&target) && target != self.function.entry {
+            // Jumping to a function makes it return to our caller
+            let name = function_name(target);
+            self.line(indent, format!("{}();", name));
+            self.line(indent, "return;".to_string());
+        } else if own_block {
+            self.gotos.insert(target);
+            self.line(indent, format!("goto L_{};", target));
+        } else {
+            // The disassembly found no instruction there, so there's no
+            // block to label
+            let stmt =
+                format!("goto *{}; /* not an instruction start */", target);
+            self.line(indent, stmt);
+        }
+    }
+
+    /// Emits the blocks starting in `start..end`; `follow` is where control
+    /// goes after the last one, so jumping there needs no statement
+    fn emit_range(
+        &mut self,
+        start: usize,
+        end: usize,
+        follow: usize,
+        current_loop: Option<Loop>,
+        indent: usize,
+    ) {
+        let blocks = &self.function.blocks;
+        let mut addr = start;
+        while addr < end {
+            let (&pos, block) = match blocks.range(addr..end).next() {
+                Some(entry) => entry,
+                None => break,
+            };
+            // The loop's own header is labelled before the loop statement
+            if current_loop.is_none_or(|lp| lp.header != pos) {
+                if let Some(exit) = self.loop_exit(pos, end) {
+                    self.emit_loop(pos, exit, indent);
+                    addr = exit;
+                    continue;
+                }
+                self.lines.push(Line::Label(indent, pos));
+            }
+            for stmt in &block.stmts {
+                self.line(indent, stmt.clone());
+            }
+            let is_last = block.end >= end
+                || blocks.range(block.end..end).next().is_none();
+            let natural = if is_last { follow } else { block.end };
+            match &block.exit {
+                Exit::Next => {}
+                Exit::Halt => self.line(indent, "halt();".to_string()),
+                Exit::Call(target) => {
+                    self.line(indent, format!("{}();", function_name(*target)))
+                }
+                Exit::Return => self.line(indent, "return;".to_string()),
+                Exit::Indirect(None, target) => {
+                    self.line(indent, format!("goto *{};", target))
+                }
+                Exit::Indirect(Some(cond), target) => self
+                    .line(indent, format!("if ({}) goto *{};", cond, target)),
+                Exit::Jump(target) if *target == natural => {}
+                Exit::Jump(target) => match current_loop {
+                    Some(lp) if lp.header == *target => {
+                        self.line(indent, "continue;".to_string())
+                    }
+                    Some(lp) if lp.exit == *target => {
+                        self.line(indent, "break;".to_string())
+                    }
+                    _ => self.goto(*target, indent),
+                },
+                Exit::Branch(cond, target) => {
+                    if let Some(next) = self.emit_branch(
+                        cond,
+                        *target,
+                        block,
+                        end,
+                        is_last,
+                        natural,
+                        current_loop,
+                        indent,
+                    ) {
+                        addr = next;
+                        continue;
+                    }
+                }
+            }
+            addr = block.end;
+        }
+    }
+
+    /// Emits a conditional jump, as an `if` around the blocks it skips when
+    /// possible; returns where to carry on if it took more blocks
+    #[allow(clippy::too_many_arguments)]
+    fn emit_branch(
+        &mut self,
+        cond: &Cond,
+        target: usize,
+        block: &FnBlock,
+        end: usize,
+        is_last: bool,
+        natural: usize,
+        current_loop: Option<Loop>,
+        indent: usize,
+    ) -> Option<usize> {
+        match current_loop {
+            Some(lp) if lp.header == target && is_last && natural == target => {
+                if !lp.do_while {
+                    self.line(indent, format!("if ({}) break;", cond.negate()));
+                }
+                return None;
+            }
+            Some(lp) if lp.header == target => {
+                self.line(indent, format!("if ({}) continue;", cond));
+                return None;
+            }
+            Some(lp) if lp.exit == target => {
+                self.line(indent, format!("if ({}) break;", cond));
+                return None;
+            }
+            _ => {}
+        }
+        if target == natural && !is_last {
+            return None;
+        }
+
+        let blocks = &self.function.blocks;
+        let last_then = if target > block.end && target <= end {
+            blocks.range(block.end..target).next_back()
+        } else {
+            None
+        };
+        if last_then.is_none() {
+            self.line(indent, format!("if ({}) {{", cond));
+            self.goto(target, indent + 1);
+            self.line(indent, "}".to_string());
+            return None;
+        }
+
+        let else_end = match last_then.map(|(_, last)| &last.exit) {
+            Some(Exit::Jump(join))
+                if *join > target
+                    && *join <= end
+                    && current_loop.is_none_or(|lp| lp.exit != *join) =>
+            {
+                Some(*join)
+            }
+            _ => None,
+        };
+        self.line(indent, format!("if ({}) {{", cond.negate()));
+        let join = else_end.unwrap_or(target);
+        self.emit_range(block.end, target, join, current_loop, indent + 1);
+        if let Some(join) = else_end {
+            self.line(indent, "} else {".to_string());
+            self.emit_range(target, join, join, current_loop, indent + 1);
+        }
+        self.line(indent, "}".to_string());
+        Some(join)
+    }
+
+    /// End of the loop starting at `header`, if a block before `end` jumps
+    /// back to it
+    fn loop_exit(&self, header: usize, end: usize) -> Option<usize> {
+        self.function
+            .blocks
+            .range(header..end)
+            .filter(|(_, block)| match &block.exit {
+                Exit::Jump(target) | Exit::Branch(_, target) => {
+                    *target == header
+                }
+                _ => false,
+            })
+            .map(|(_, block)| block.end)
+            .next_back()
+    }
+
+    fn emit_loop(&mut self, header: usize, exit: usize, indent: usize) {
+        let blocks = &self.function.blocks;
+        let head = &blocks[&header];
+        let latch = blocks.range(header..exit).next_back().unwrap().1;
+        let while_cond = match &head.exit {
+            Exit::Branch(cond, target)
+                if head.stmts.is_empty()
+                    && *target == exit
+                    && head.end != exit =>
+            {
+                Some(cond.negate())
+            }
+            _ => None,
+        };
+        let do_while = match &latch.exit {
+            Exit::Branch(cond, target)
+                if *target == header && while_cond.is_none() =>
+            {
+                Some(cond.clone())
+            }
+            _ => None,
+        };
+
+        self.lines.push(Line::Label(indent, header));
+        let lp = Loop {
+            header,
+            exit,
+            do_while: do_while.is_some(),
+        };
+        let body_start = match &while_cond {
+            Some(cond) => {
+                self.line(indent, format!("while ({}) {{", cond));
+                head.end
+            }
+            None if do_while.is_some() => {
+                self.line(indent, "do {".to_string());
+                header
+            }
+            None => {
+                self.line(indent, "loop {".to_string());
+                header
+            }
+        };
+        self.emit_range(body_start, exit, header, Some(lp), indent + 1);
+        match do_while {
+            Some(cond) => self.line(indent, format!("}} while ({});", cond)),
+            None => self.line(indent, "}".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+
+    fn decompile_source(source: &str) -> String {
+        decompile(&assemble(source).unwrap())
+    }
+
+    #[test]
+    fn loops() {
+        let program = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101,
+            0, 99,
+        ];
+        let code = "\
+fn main() {
+    // frame: 1 slot
+    do {
+        output(ret_addr);
+        mem[100] = mem[100] + 1;
+    } while (mem[100] != 16);
+    halt();
+}
+";
+        assert_eq!(decompile(&program), code);
+
+        let source = "
+            loop:   in    [100]
+                    jf    [100], end
+                    out   [100]
+                    jt    1, loop
+            end:    hlt
+            ";
+        let code = "\
+fn main() {
+    loop {
+        mem[100] = input();
+        if (!mem[100]) break;
+        output(mem[100]);
+    }
+    halt();
+}
+";
+        assert_eq!(decompile_source(source), code);
+    }
+
+    #[test]
+    fn if_else() {
+        let source = "
+                    in    [100]
+                    lt    [100], 10, [101]
+                    jf    [101], big
+                    out   1
+                    jt    1, done
+            big:    out   2
+                    eq    [100], 20, [101]
+                    jt    [101], done
+                    out   3
+            done:   hlt
+            ";
+        let code = "\
+fn main() {
+    mem[100] = input();
+    if (mem[100] < 10) {
+        output(1);
+    } else {
+        output(2);
+        if (mem[100] != 20) {
+            output(3);
+        }
+    }
+    halt();
+}
+";
+        assert_eq!(decompile_source(source), code);
+    }
+
+    #[test]
+    fn functions() {
+        // Calls a function that doubles its argument, keeping it in a local
+        let source = "
+                    rbo   100
+                    in    rel[1]
+                    add   ret, 0, rel[0]
+                    jt    1, double
+            ret:    out   rel[1]
+                    hlt
+            double: rbo   2
+                    mul   rel[-1], 2, rel[-1]
+                    rbo   -2
+                    jt    1, rel[0]
+            ";
+        let code = "\
+fn main() {
+    // frame: 100 slots
+    arg1 = input();
+    sub_14();
+    output(arg1);
+    halt();
+}
+
+fn sub_14() {
+    // frame: 2 slots
+    local1 = local1 * 2;
+    return;
+}
+";
+        assert_eq!(decompile_source(source), code);
+    }
+
+    #[test]
+    fn gotos() {
+        // Jumps into the middle of the loop, which is only entered at the top
+        // by structured code
+        let source = "
+                    jt    1, middle
+            top:    out   1
+            middle: in    [100]
+                    jt    [100], top
+                    hlt
+            ";
+        let code = "\
+fn main() {
+    goto L_5;
+    do {
+        output(1);
+    L_5:
+        mem[100] = input();
+    } while (mem[100]);
+    halt();
+}
+";
+        assert_eq!(decompile_source(source), code);
+
+        // Jumps into the middle of its own instruction, which has no label
+        let code = "\
+fn main() {
+    if (mem[4]) {
+        goto *1; /* not an instruction start */
+    }
+    halt();
+}
+";
+        assert_eq!(decompile(&[1005, 4, 1, 99, 0]), code);
+    }
+
+    #[test]
+    fn jump_tables() {
+        // Jumps to the input-th address in the table at 11
+        let program = [
+            3, 22, 1001, 22, 11, 10, 109, 1, 105, 1, 0, 13, 15, 104, 1, 99,
+        ];
+        let code = "\
+fn main() {
+    // frame: 1 slot
+    mem[22] = input();
+    mem[10] = mem[22] + 11; // modifies code
+    goto *mem[0];
+L_13:
+    output(1);
+L_15:
+    halt();
+}
+";
+        assert_eq!(decompile(&program), code);
+    }
+
+    #[test]
+    fn unreached_code() {
+        // The return address is kept in an absolute position, so the CFG has
+        // no edge back to it
+        let source = "
+                    add   ret, 0, [100]
+                    jt    1, sub
+            ret:    out   1
+                    hlt
+            sub:    jt    1, [100]
+            ";
+        let code = "\
+fn main() {
+    mem[100] = 7;
+    goto L_10;
+L_10:
+    goto *mem[100];
+}
+
+fn sub_7() {
+    output(1);
+    halt();
+}
+";
+        assert_eq!(decompile_source(source), code);
+    }
+}
@@ -0,0 +1,437 @@
+use crate::{
+    InstrState, ADD_OP, HALT_OP, IMMEDIATE_MODE, JIF_OP, JIT_OP, MULT_OP,
+    POSITION_MODE, RELATIVE_MODE,
+};
+use intcode::instr_info;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    Position(i64),
+    Immediate(i64),
+    Relative(i64),
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Position(addr) => write!(f, "[{}]", addr),
+            Operand::Immediate(value) => write!(f, "{}", value),
+            Operand::Relative(offset) => write!(f, "rel[{}]", offset),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instruction {
+    pub addr: usize,
+    pub opcode: i64,
+    pub operands: Vec<Operand>,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `addr`, if it's one the engine can execute
+    pub fn decode(program: &[i64], addr: usize) -> Option<Self> {
+        Self::decode_with(|pos| program.get(pos).copied(), addr)
+    }
+
+    /// Decodes the instruction at `addr` reading memory through `read`
+    pub fn decode_with<F>(read: F, addr: usize) -> Option<Self>
+    where
+        F: Fn(usize) -> Option<i64>,
+    {
+        let state = InstrState::new(addr, read(addr)?, 0);
+        let (_, params, writes) = instr_info(state.opcode)?;
+        let operands = (0..params)
+            .map(|param| {
+                let value = read(addr.checked_add(param + 1)?)?;
+                match state.modes[param] {
+                    POSITION_MODE => Some(Operand::Position(value)),
+                    IMMEDIATE_MODE if writes && param == params - 1 => None,
+                    IMMEDIATE_MODE => Some(Operand::Immediate(value)),
+                    RELATIVE_MODE => Some(Operand::Relative(value)),
+                    _ => None,
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            addr,
+            opcode: state.opcode,
+            operands,
+        })
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        instr_info(self.opcode)
+            .map(|(name, _, _)| name)
+            .unwrap_or("???")
+    }
+
+    /// Number of memory positions taken by the instruction
+    pub fn size(&self) -> usize {
+        self.operands.len() + 1
+    }
+
+    /// Addresses where execution may continue after this instruction, and
+    /// whether they are all known (i.e. no jump to a computed address)
+    pub fn successors(&self) -> (Vec<usize>, bool) {
+        let next = self.addr + self.size();
+        match (self.opcode, self.operands.as_slice()) {
+            (HALT_OP, _) => (Vec::new(), true),
+            (JIT_OP, [_, target]) | (JIF_OP, [_, target]) => {
+                let (jumps, falls_through) = self.branch_outcomes();
+                let mut successors = Vec::new();
+                if falls_through {
+                    successors.push(next);
+                }
+                if !jumps {
+                    return (successors, true);
+                }
+                match target {
+                    Operand::Immediate(addr) => {
+                        if let Ok(addr) = usize::try_from(*addr) {
+                            successors.push(addr);
+                        }
+                        (successors, true)
+                    }
+                    _ => (successors, false),
+                }
+            }
+            _ => (vec![next], true),
+        }
+    }
+
+    /// Whether a jump instruction may jump and whether it may fall through,
+    /// which is only known for certain when its condition is immediate
+    pub(crate) fn branch_outcomes(&self) -> (bool, bool) {
+        match self.operands.first() {
+            Some(Operand::Immediate(value)) => {
+                let taken = (*value != 0) == (self.opcode == JIT_OP);
+                (taken, !taken)
+            }
+            _ => (true, true),
+        }
+    }
+
+    /// Constant written by this instruction when both inputs are immediate
+    fn stored_constant(&self) -> Option<i64> {
+        match (self.opcode, self.operands.as_slice()) {
+            (
+                ADD_OP,
+                [Operand::Immediate(val1), Operand::Immediate(val2), _],
+            ) => val1.checked_add(*val2),
+            (
+                MULT_OP,
+                [Operand::Immediate(val1), Operand::Immediate(val2), _],
+            ) => val1.checked_mul(*val2),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operands = self
+            .operands
+            .iter()
+            .map(|operand| operand.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if operands.is_empty() {
+            write!(f, "{}", self.mnemonic())
+        } else {
+            write!(f, "{:<5} {}", self.mnemonic(), operands)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Item {
+    Code(Instruction),
+    Data(usize, Vec<i64>),
+}
+
+impl Item {
+    pub fn addr(&self) -> usize {
+        match self {
+            Item::Code(instr) => instr.addr,
+            Item::Data(addr, _) => *addr,
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Item::Code(instr) => write!(f, "{:04}  {}", instr.addr, instr),
+            Item::Data(addr, values) => {
+                let values = values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "{:04}  .data {}", addr, values)
+            }
+        }
+    }
+}
+
+pub struct Disassembly {
+    items: Vec<Item>,
+    table_targets: BTreeSet<usize>,
+    jump_tables: BTreeMap<usize, Vec<usize>>,
+}
+
+impl Disassembly {
+    /// Disassembles the code reachable from address 0, following jumps with
+    /// immediate targets, jumps through tables of addresses and the return
+    /// addresses pushed by call sequences; everything else is listed as data
+    pub fn new(program: &[i64]) -> Self {
+        let mut code = BTreeMap::new();
+        let mut covered = HashSet::new();
+        let mut table_targets = BTreeSet::new();
+        let mut jump_tables = BTreeMap::new();
+        let mut pending = vec![0];
+
+        while let Some(addr) = pending.pop() {
+            if covered.contains(&addr) {
+                continue;
+            }
+            let instr = match Instruction::decode(program, addr) {
+                Some(instr) => instr,
+                None => continue,
+            };
+            if (addr..addr + instr.size()).any(|pos| covered.contains(&pos)) {
+                // Overlaps code that was already decoded
+                continue;
+            }
+            covered.extend(addr..addr + instr.size());
+            let (successors, _) = instr.successors();
+            pending.extend(successors);
+            if let Some(ret_addr) = return_address(program, &instr) {
+                pending.push(ret_addr);
+            }
+            let targets = jump_table(program, &code, &instr);
+            if !targets.is_empty() {
+                table_targets.extend(&targets);
+                pending.extend(&targets);
+                jump_tables.insert(addr, targets);
+            }
+            code.insert(addr, instr);
+        }
+
+        let mut items = Vec::new();
+        let mut addr = 0;
+        while addr < program.len() {
+            if let Some(instr) = code.remove(&addr) {
+                addr += instr.size();
+                items.push(Item::Code(instr));
+                continue;
+            }
+            let start = addr;
+            while addr < program.len()
+                && !covered.contains(&addr)
+                && addr - start < DATA_PER_LINE
+            {
+                addr += 1;
+            }
+            items.push(Item::Data(start, program[start..addr].to_vec()));
+        }
+        Self {
+            items,
+            table_targets,
+            jump_tables,
+        }
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// Addresses found in jump tables, which are only jumped to indirectly
+    pub fn table_targets(&self) -> &BTreeSet<usize> {
+        &self.table_targets
+    }
+
+    /// The targets of each jump through a table, by the jump's address
+    pub fn jump_tables(&self) -> &BTreeMap<usize, Vec<usize>> {
+        &self.jump_tables
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for item in &self.items {
+            writeln!(f, "{}", item)?;
+        }
+        Ok(())
+    }
+}
+
+/// Calls store a constant return address and then jump unconditionally; the
+/// return address is the instruction right after the jump
+pub(crate) fn return_address(
+    program: &[i64],
+    instr: &Instruction,
+) -> Option<usize> {
+    let ret_addr = usize::try_from(instr.stored_constant()?).ok()?;
+    let jump = Instruction::decode(program, instr.addr + instr.size())?;
+    let unconditional = (jump.opcode == JIT_OP || jump.opcode == JIF_OP)
+        && jump.branch_outcomes() == (true, false);
+    if unconditional && ret_addr == jump.addr + jump.size() {
+        Some(ret_addr)
+    } else {
+        None
+    }
+}
+
+/// Targets of a jump through a table of addresses, which is set up by an
+/// instruction in the straight-line code before it adding an index to the
+/// address of the table and storing the sum as the jump's position operand;
+/// the table is taken to end at the first entry that isn't the address of
+/// an instruction
+fn jump_table(
+    program: &[i64],
+    code: &BTreeMap<usize, Instruction>,
+    jump: &Instruction,
+) -> Vec<usize> {
+    let target_addr = jump.addr + 2;
+    let jumps = jump.branch_outcomes().0;
+    if !matches!(jump.operands.get(1), Some(Operand::Position(_))) || !jumps {
+        return Vec::new();
+    }
+    let mut next = jump.addr;
+    let setup = code.range(..jump.addr).rev().find_map(|(_, instr)| {
+        if instr.addr + instr.size() != next || is_jump(instr) {
+            return Some(None);
+        }
+        next = instr.addr;
+        match instr.operands.last() {
+            Some(Operand::Position(dest))
+                if writes(instr)
+                    && usize::try_from(*dest) == Ok(target_addr) =>
+            {
+                Some(Some(instr))
+            }
+            _ => None,
+        }
+    });
+    let setup = match setup {
+        Some(Some(setup)) => setup,
+        _ => return Vec::new(),
+    };
+    let table = match (setup.opcode, setup.operands.as_slice()) {
+        (ADD_OP, [Operand::Immediate(table), _, Operand::Position(dest)])
+        | (ADD_OP, [_, Operand::Immediate(table), Operand::Position(dest)])
+            if usize::try_from(*dest) == Ok(target_addr) =>
+        {
+            usize::try_from(*table).ok()
+        }
+        _ => None,
+    };
+    table
+        .and_then(|table| program.get(table..))
+        .unwrap_or_default()
+        .iter()
+        .map_while(|&entry| {
+            let addr = usize::try_from(entry).ok()?;
+            Instruction::decode(program, addr).map(|_| addr)
+        })
+        .collect()
+}
+
+pub(crate) fn is_jump(instr: &Instruction) -> bool {
+    instr.opcode == JIT_OP || instr.opcode == JIF_OP
+}
+
+fn writes(instr: &Instruction) -> bool {
+    instr_info(instr.opcode).is_some_and(|(_, _, writes)| writes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let program = [1001, 100, 1, 100, 204, -1, 1105, 1, 0, 3, 5];
+        let add = Instruction::decode(&program, 0).unwrap();
+        assert_eq!(add.to_string(), "add   [100], 1, [100]");
+        let out = Instruction::decode(&program, 4).unwrap();
+        assert_eq!(out.to_string(), "out   rel[-1]");
+        let jump = Instruction::decode(&program, 6).unwrap();
+        assert_eq!(jump.successors(), (vec![0], true));
+        // Truncated instruction
+        assert_eq!(Instruction::decode(&program, 10), None);
+        // Write parameter in immediate mode
+        assert_eq!(Instruction::decode(&[11101, 1, 1, 1], 0), None);
+    }
+
+    #[test]
+    fn listing() {
+        let program = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101,
+            0, 99,
+        ];
+        let listing = "0000  rbo   1\n\
+                       0002  out   rel[-1]\n\
+                       0004  add   [100], 1, [100]\n\
+                       0008  eq    [100], 16, [101]\n\
+                       0012  jf    [101], 0\n\
+                       0015  hlt\n";
+        assert_eq!(Disassembly::new(&program).to_string(), listing);
+    }
+
+    #[test]
+    fn data_regions() {
+        let program = [1105, 1, 7, 10, 20, 30, 40, 104, 3, 99];
+        let listing = "0000  jt    1, 7\n\
+                       0003  .data 10, 20, 30, 40\n\
+                       0007  out   3\n\
+                       0009  hlt\n";
+        assert_eq!(Disassembly::new(&program).to_string(), listing);
+    }
+
+    #[test]
+    fn jump_table() {
+        // Jumps to the input-th address in the table at 11
+        let program = [
+            3, 22, 1001, 22, 11, 10, 109, 1, 105, 1, 0, 13, 15, 104, 1, 99,
+        ];
+        let disassembly = Disassembly::new(&program);
+        let listing = "0000  in    [22]\n\
+                       0002  add   [22], 11, [10]\n\
+                       0006  rbo   1\n\
+                       0008  jt    1, [0]\n\
+                       0011  .data 13, 15\n\
+                       0013  out   1\n\
+                       0015  hlt\n";
+        assert_eq!(disassembly.to_string(), listing);
+        assert_eq!(
+            disassembly
+                .table_targets()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![13, 15]
+        );
+    }
+
+    #[test]
+    fn call_return_address() {
+        // Push return address 7 and jump to 9, which returns through it
+        let program = [21101, 7, 0, 0, 1105, 1, 9, 99, 0, 2106, 0, 0];
+        let items = Disassembly::new(&program);
+        let code = items
+            .items()
+            .iter()
+            .filter(|item| matches!(item, Item::Code(_)))
+            .map(|item| item.addr())
+            .collect::<Vec<_>>();
+        assert_eq!(code, vec![0, 4, 7, 9]);
+    }
+}
@@ -0,0 +1,360 @@
+use crate::memory::Memory;
+use crate::{InputOutput, InstrState, IntcodeComputer, IntcodeError, Status};
+use intcode::{instr_info, instr_offset};
+use std::sync::Arc;
+
+type Handler<M> =
+    dyn Fn(&mut OpcodeContext<M>) -> Result<Flow, IntcodeError> + Send + Sync;
+
+/// An instruction added to the instruction set; like the standard ones, it
+/// reads all its parameters but the last, which it may write instead
+pub struct CustomOpcode<M: Memory> {
+    params: usize,
+    writes: bool,
+    handler: Arc<Handler<M>>,
+}
+
+impl<M: Memory> Clone for CustomOpcode<M> {
+    fn clone(&self) -> Self {
+        Self {
+            params: self.params,
+            writes: self.writes,
+            handler: Arc::clone(&self.handler),
+        }
+    }
+}
+
+impl<M: Memory> CustomOpcode<M> {
+    /// An instruction with up to 3 parameters, the last of them written if
+    /// `writes` is set, executed by `handler`
+    pub fn new<F>(params: usize, writes: bool, handler: F) -> Self
+    where
+        F: Fn(&mut OpcodeContext<M>) -> Result<Flow, IntcodeError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        assert!(params <= 3, "instructions have at most 3 parameters");
+        assert!(params > 0 || !writes, "no parameter to write");
+        Self {
+            params,
+            writes,
+            handler: Arc::new(handler),
+        }
+    }
+}
+
+/// Where execution continues after a custom instruction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Flow {
+    /// With the instruction after this one
+    Next,
+    Jump(usize),
+    Halt,
+    /// The instruction is retried once more I/O is possible; it must not
+    /// have changed anything yet
+    Block,
+}
+
+/// What a custom instruction's handler gets to work with: its parameters,
+/// the machine's memory and registers, and the program's I/O
+pub struct OpcodeContext<'a, M: Memory> {
+    computer: &'a mut IntcodeComputer<M>,
+    io: &'a mut dyn InputOutput<M::Word>,
+    state: InstrState,
+    args: Vec<M::Word>,
+    dest: Option<usize>,
+}
+
+impl<M: Memory> OpcodeContext<'_, M> {
+    pub fn state(&self) -> &InstrState {
+        &self.state
+    }
+
+    /// Values of the parameters that are read, in order
+    pub fn args(&self) -> &[M::Word] {
+        &self.args
+    }
+
+    /// Writes to the position given by the last parameter
+    ///
+    /// Panics if the instruction doesn't write.
+    pub fn write(&mut self, value: M::Word) {
+        let addr = self.dest.expect("instruction has no write parameter");
+        self.computer.set_value(addr, value);
+    }
+
+    pub fn read_memory(&self, addr: usize) -> M::Word {
+        self.computer.get_memory(addr)
+    }
+
+    /// Writes anywhere in memory; unlike `write`, `reverse_step` doesn't
+    /// undo it
+    pub fn write_memory(&mut self, addr: usize, value: M::Word) {
+        self.computer.set_value(addr, value);
+    }
+
+    pub fn base(&self) -> i64 {
+        self.computer.base
+    }
+
+    pub fn set_base(&mut self, base: i64) {
+        self.computer.base = base;
+    }
+
+    /// The value as an address, failing like the standard instructions do
+    /// for negative ones
+    pub fn addr(&self, value: M::Word) -> Result<usize, IntcodeError> {
+        crate::to_addr(&self.state, &value)
+    }
+
+    /// The next input; `None` if there is none yet and the computer blocks
+    /// on I/O, in which case the handler should return `Flow::Block`
+    pub fn input(&mut self) -> Result<Option<M::Word>, IntcodeError> {
+        match self.io.provide_input() {
+            Some(value) => Ok(Some(value)),
+            None if self.computer.blocking_io || self.io.is_pending() => {
+                Ok(None)
+            }
+            None => Err(IntcodeError::NoInput(self.state)),
+        }
+    }
+
+    /// Outputs a value; false if it wasn't taken and the computer blocks on
+    /// I/O, in which case the handler should return `Flow::Block`
+    pub fn output(&mut self, value: M::Word) -> Result<bool, IntcodeError> {
+        match self.io.take_output(value) {
+            Some(()) => Ok(true),
+            None if self.computer.blocking_io || self.io.is_pending() => {
+                Ok(false)
+            }
+            None => Err(IntcodeError::OutputRejected(self.state)),
+        }
+    }
+}
+
+impl<M: Memory> IntcodeComputer<M> {
+    /// Adds an instruction to the instruction set, replacing any custom one
+    /// with the same opcode
+    ///
+    /// Panics if the opcode is one of the standard instructions or doesn't
+    /// fit two digits.
+    pub fn add_opcode(&mut self, opcode: i64, custom: CustomOpcode<M>) {
+        assert!(
+            (0..100).contains(&opcode) && instr_info(opcode).is_none(),
+            "opcode {} can't be customized",
+            opcode
+        );
+        self.opcodes.insert(opcode, custom);
+    }
+
+    pub fn remove_opcode(&mut self, opcode: i64) -> bool {
+        self.opcodes.remove(&opcode).is_some()
+    }
+
+    /// Number of parameters of an instruction and whether it writes the
+    /// last one
+    pub(crate) fn instr_shape(&self, opcode: i64) -> Option<(usize, bool)> {
+        instr_info(opcode)
+            .map(|(_, params, writes)| (params, writes))
+            .or_else(|| {
+                self.opcodes
+                    .get(&opcode)
+                    .map(|custom| (custom.params, custom.writes))
+            })
+    }
+
+    pub(crate) fn execute_custom(
+        &mut self,
+        state: InstrState,
+        io: &mut dyn InputOutput<M::Word>,
+    ) -> Result<Status<M::Word>, IntcodeError> {
+        let custom = match self.opcodes.get(&state.opcode) {
+            Some(custom) => custom.clone(),
+            None => return Err(IntcodeError::InvalidOpcode(state)),
+        };
+        let reads = custom.params - usize::from(custom.writes);
+        let args = (1..=reads)
+            .map(|param| self.get_value(&state, param))
+            .collect::<Result<Vec<_>, _>>()?;
+        let dest = if custom.writes {
+            Some(self.get_addr(&state, custom.params)?)
+        } else {
+            None
+        };
+        let mut context = OpcodeContext {
+            computer: self,
+            io,
+            state,
+            args,
+            dest,
+        };
+        match (custom.handler)(&mut context)? {
+            Flow::Next => {
+                self.instr_ptr = instr_offset(&state, custom.params + 1)?
+            }
+            Flow::Jump(addr) => self.instr_ptr = addr,
+            Flow::Halt => return Ok(Status::Halted),
+            Flow::Block => return Ok(Status::Blocked),
+        }
+        Ok(Status::Running)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::TraceEntry;
+    use crate::SimpleInputOutput;
+    use std::sync::Mutex;
+
+    const PRINT_OP: i64 = 20;
+    const PUTS_OP: i64 = 21;
+    const SQUARE_OP: i64 = 22;
+    const LOOP_OP: i64 = 23;
+
+    /// Outputs the zero-terminated string starting at the given position
+    fn puts<M: Memory<Word = i64>>() -> CustomOpcode<M> {
+        CustomOpcode::new(1, false, |context| {
+            let mut addr = context.addr(context.args()[0])?;
+            loop {
+                match context.read_memory(addr) {
+                    0 => return Ok(Flow::Next),
+                    ch => context.output(ch)?,
+                };
+                addr += 1;
+            }
+        })
+    }
+
+    fn square<M: Memory<Word = i64>>() -> CustomOpcode<M> {
+        CustomOpcode::new(2, true, |context| {
+            let value = context.args()[0];
+            context.write(value * value);
+            Ok(Flow::Next)
+        })
+    }
+
+    #[test]
+    fn debug_print() {
+        let printed = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&printed);
+        let mut computer = IntcodeComputer::new(&[20, 4, 120, -5, 99], false);
+        computer.add_opcode(
+            PRINT_OP,
+            CustomOpcode::new(1, false, move |context| {
+                let message = format!(
+                    "{} @ {}",
+                    context.args()[0],
+                    context.state().instr_ptr
+                );
+                log.lock().unwrap().push(message);
+                Ok(Flow::Next)
+            }),
+        );
+        let mut io = SimpleInputOutput::new(&[]);
+        assert_eq!(computer.run(&mut io), Ok(true));
+        assert_eq!(*printed.lock().unwrap(), vec!["99 @ 0", "-5 @ 2"]);
+    }
+
+    #[test]
+    fn string_output() {
+        let program = [121, 3, 99, 72, 105, 0];
+        let mut computer = IntcodeComputer::new(&program, false);
+        computer.add_opcode(PUTS_OP, puts());
+        let mut io = SimpleInputOutput::new(&[]);
+        assert_eq!(computer.run(&mut io), Ok(true));
+        assert_eq!(io.get_output(), vec![72, 105]);
+    }
+
+    #[test]
+    fn write_parameter() {
+        // Squares the input into position 9 and outputs it
+        let program = [3, 9, 22, 9, 9, 4, 9, 99];
+        let mut computer = IntcodeComputer::new(&program, false);
+        computer.add_opcode(SQUARE_OP, square());
+        computer.record_history(10);
+        let mut trace = Vec::<TraceEntry>::new();
+        let mut io = SimpleInputOutput::new(&[-12]);
+        assert_eq!(computer.run_traced(&mut io, &mut trace), Ok(true));
+        assert_eq!(io.get_output(), vec![144]);
+
+        let entry = &trace[1];
+        assert_eq!(entry.params, vec![9, 9]);
+        assert_eq!(entry.reads, vec![-12]);
+        assert_eq!(entry.write, Some((9, 144)));
+
+        // Undoes the halt, the output and the square
+        for _ in 0..3 {
+            assert!(computer.reverse_step());
+        }
+        assert_eq!(computer.instr_ptr(), 2);
+        assert_eq!(computer.read_memory(9), -12);
+    }
+
+    #[test]
+    fn jumps_and_registers() {
+        // Counts down position 10 and moves the relative base along, jumping
+        // back to itself until the count reaches zero
+        let program = [23, 10, 204, 6, 99, 0, 0, 0, 0, 0, 3];
+        let mut computer = IntcodeComputer::new(&program, false);
+        computer.add_opcode(
+            LOOP_OP,
+            CustomOpcode::new(1, false, |context| {
+                let count = context.args()[0];
+                context.set_base(context.base() + 1);
+                if count == 0 {
+                    return Ok(Flow::Next);
+                }
+                context.write_memory(10, count - 1);
+                Ok(Flow::Jump(context.state().instr_ptr))
+            }),
+        );
+        let mut io = SimpleInputOutput::new(&[]);
+        assert_eq!(computer.run(&mut io), Ok(true));
+        assert_eq!(computer.base(), 4);
+        assert_eq!(io.get_output(), vec![0]);
+    }
+
+    #[test]
+    fn blocking() {
+        let mut computer = IntcodeComputer::new(&[20, 99], true);
+        computer.add_opcode(
+            PRINT_OP,
+            CustomOpcode::new(0, false, |context| match context.input()? {
+                Some(value) => {
+                    context.output(value * 2)?;
+                    Ok(Flow::Next)
+                }
+                None => Ok(Flow::Block),
+            }),
+        );
+        let mut io = SimpleInputOutput::new(&[]);
+        assert_eq!(computer.run(&mut io), Ok(false));
+        assert_eq!(computer.instr_ptr(), 0);
+
+        let mut io = SimpleInputOutput::new(&[21]);
+        assert_eq!(computer.run(&mut io), Ok(true));
+        assert_eq!(io.get_output(), vec![42]);
+    }
+
+    #[test]
+    fn unknown_opcode() {
+        let mut computer = IntcodeComputer::new(&[22, 0, 0, 99], false);
+        computer.add_opcode(SQUARE_OP, square());
+        assert!(computer.remove_opcode(SQUARE_OP));
+        assert!(!computer.remove_opcode(SQUARE_OP));
+        let mut io = SimpleInputOutput::new(&[]);
+        assert!(matches!(
+            computer.run(&mut io),
+            Err(IntcodeError::InvalidOpcode(_))
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "opcode 1 can't be customized")]
+    fn standard_opcode() {
+        let mut computer = IntcodeComputer::new(&[99], false);
+        computer.add_opcode(1, square());
+    }
+}
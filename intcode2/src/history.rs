@@ -0,0 +1,190 @@
+use crate::memory::Memory;
+use crate::{InstrState, IntcodeComputer};
+use std::collections::VecDeque;
+
+/// What an instruction changed: the registers before it executed and the
+/// previous value of the position it wrote
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Delta<W> {
+    instr_ptr: usize,
+    base: i64,
+    write: Option<(usize, W)>,
+}
+
+impl<W> Delta<W> {
+    pub(crate) fn capture<M: Memory<Word = W>>(
+        computer: &IntcodeComputer<M>,
+        state: &InstrState,
+    ) -> Self {
+        let (count, writes) =
+            computer.instr_shape(state.opcode).unwrap_or((0, false));
+        // Invalid operands make the instruction fail, so it is never recorded
+        let write = if writes {
+            computer
+                .get_addr(state, count)
+                .ok()
+                .map(|addr| (addr, computer.get_memory(addr)))
+        } else {
+            None
+        };
+        Self {
+            instr_ptr: state.instr_ptr,
+            base: computer.base,
+            write,
+        }
+    }
+}
+
+/// The most recent deltas, up to a maximum number of instructions
+#[derive(Clone, Debug)]
+pub(crate) struct History<W> {
+    deltas: VecDeque<Delta<W>>,
+    max_len: usize,
+}
+
+impl<W> History<W> {
+    pub(crate) fn push(&mut self, delta: Delta<W>) {
+        if self.max_len == 0 {
+            return;
+        }
+        if self.deltas.len() == self.max_len {
+            self.deltas.pop_front();
+        }
+        self.deltas.push_back(delta);
+    }
+}
+
+impl<M: Memory> IntcodeComputer<M> {
+    /// Starts recording the changes made by each instruction, keeping those
+    /// of the last `max_len` instructions, so they can be undone with
+    /// `reverse_step`; changes made with `write_memory`, `set_instr_ptr` and
+    /// `set_base` are not recorded, and I/O is never undone
+    pub fn record_history(&mut self, max_len: usize) {
+        self.history = Some(History {
+            deltas: VecDeque::new(),
+            max_len,
+        });
+    }
+
+    /// Stops recording and forgets the recorded history
+    pub fn stop_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Number of instructions that can be undone
+    pub fn history_len(&self) -> usize {
+        self.history
+            .as_ref()
+            .map_or(0, |history| history.deltas.len())
+    }
+
+    /// Undoes the last executed instruction; returns false if there is no
+    /// recorded history left
+    pub fn reverse_step(&mut self) -> bool {
+        let delta = match self
+            .history
+            .as_mut()
+            .and_then(|history| history.deltas.pop_back())
+        {
+            Some(delta) => delta,
+            None => return false,
+        };
+        if let Some((addr, value)) = delta.write {
+            self.set_value(addr, value);
+        }
+        self.instr_ptr = delta.instr_ptr;
+        self.base = delta.base;
+        self.steps = self.steps.saturating_sub(1);
+        self.loop_detector.reset();
+        true
+    }
+
+    /// Undoes instructions until the instruction pointer is back on a
+    /// breakpoint, after undoing at least one; returns false if the start of
+    /// the recorded history was reached first
+    pub fn reverse_continue(&mut self) -> bool {
+        while self.reverse_step() {
+            if self.breakpoints.contains(&self.instr_ptr) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimpleInputOutput;
+
+    // Counts up to 15 and halts, keeping the counter at 100
+    const COUNTER: [i64; 16] = [
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0,
+        99,
+    ];
+
+    #[test]
+    fn reverse_step() {
+        let mut computer = IntcodeComputer::new(&COUNTER, false);
+        computer.record_history(1000);
+        let mut test_io = SimpleInputOutput::default();
+        assert_eq!(computer.run(&mut test_io), Ok(true));
+        assert_eq!(computer.history_len(), 81);
+
+        // Undoes the halt, the jump and the comparison
+        for _ in 0..3 {
+            assert!(computer.reverse_step());
+        }
+        assert_eq!(computer.instr_ptr(), 8);
+        assert_eq!(computer.read_memory(100), 16);
+        assert_eq!(computer.read_memory(101), 0);
+        assert!(computer.reverse_step());
+        assert_eq!(computer.read_memory(100), 15);
+
+        while computer.reverse_step() {}
+        assert_eq!(computer.instr_ptr(), 0);
+        assert_eq!(computer.base(), 0);
+        assert_eq!(computer.steps(), 0);
+        for (addr, &value) in COUNTER.iter().enumerate() {
+            assert_eq!(computer.read_memory(addr), value);
+        }
+        assert_eq!(computer.read_memory(100), 0);
+        assert_eq!(computer.run(&mut test_io), Ok(true));
+        // Outputs are not undone, so both runs are there
+        assert_eq!(test_io.get_output(), [COUNTER, COUNTER].concat());
+    }
+
+    #[test]
+    fn reverse_continue() {
+        let mut computer = IntcodeComputer::new(&COUNTER, false);
+        computer.record_history(1000);
+        computer.add_breakpoint(12);
+        let mut test_io = SimpleInputOutput::default();
+        assert_eq!(computer.run(&mut test_io), Ok(true));
+
+        assert!(computer.reverse_continue());
+        assert_eq!(computer.instr_ptr(), 12);
+        assert_eq!(computer.read_memory(100), 16);
+        assert!(computer.reverse_continue());
+        assert_eq!(computer.read_memory(100), 15);
+
+        computer.remove_breakpoint(12);
+        assert!(!computer.reverse_continue());
+        assert_eq!(computer.instr_ptr(), 0);
+    }
+
+    #[test]
+    fn limited_history() {
+        let mut computer = IntcodeComputer::new(&COUNTER, false);
+        computer.record_history(10);
+        let mut test_io = SimpleInputOutput::default();
+        assert_eq!(computer.run(&mut test_io), Ok(true));
+        assert_eq!(computer.history_len(), 10);
+        while computer.reverse_step() {}
+        assert_eq!(computer.steps(), 71);
+        assert_eq!(computer.read_memory(100), 14);
+
+        computer.stop_history();
+        assert!(!computer.reverse_step());
+    }
+}
@@ -1,211 +1,403 @@
-use std::collections::HashMap;
-use std::convert::TryFrom;
-use std::mem::replace;
-
-const ADD_OP: i64 = 1;
-const MULT_OP: i64 = 2;
-const IN_OP: i64 = 3;
-const OUT_OP: i64 = 4;
-const JIT_OP: i64 = 5;
-const JIF_OP: i64 = 6;
-const LT_OP: i64 = 7;
-const EQ_OP: i64 = 8;
-const BASE_OP: i64 = 9;
-const HALT_OP: i64 = 99;
-
-const POSITION_MODE: i64 = 0;
-const IMMEDIATE_MODE: i64 = 1;
-const RELATIVE_MODE: i64 = 2;
-
-type Memory = HashMap<usize, i64>;
-
-pub struct IntcodeComputer {
+use std::collections::{HashMap, HashSet};
+
+pub mod asm;
+pub mod async_io;
+pub mod cfg;
+pub mod channel;
+pub mod console;
+pub mod debug;
+pub mod decompile;
+pub mod disasm;
+pub mod extension;
+mod history;
+mod snapshot;
+pub mod symbolic;
+pub mod trace;
+pub mod transpile;
+pub mod watch;
+
+// Lets tests use code generated for other crates
+#[cfg(test)]
+extern crate self as intcode2;
+
+pub use intcode::{ascii, cache, limits, memory, profile, word};
+pub use intcode::{InputOutput, InstrState, IntcodeError, SimpleInputOutput};
+
+use extension::CustomOpcode;
+use history::{Delta, History};
+use intcode::cache::InstrCache;
+use intcode::word::Word;
+use intcode::{
+    decode_instr, get_addr, get_pos, get_value, instr_offset, move_base,
+    to_addr,
+};
+use intcode::{
+    ADD_OP, BASE_OP, EQ_OP, HALT_OP, IMMEDIATE_MODE, IN_OP, JIF_OP, JIT_OP,
+    LT_OP, MULT_OP, OUT_OP, POSITION_MODE, RELATIVE_MODE,
+};
+use limits::{Limits, LoopDetector};
+use memory::{Memory, PagedMemory};
+use trace::{Operands, Tracer};
+use watch::{WatchEvent, Watchpoint};
+
+/// An Intcode machine; cloning a paused machine forks it, and with
+/// `CowMemory` the fork shares memory pages with the original until either
+/// of them writes to them
+#[derive(Clone)]
+pub struct IntcodeComputer<M: Memory = PagedMemory> {
     base: i64,
     instr_ptr: usize,
-    memory: Memory,
+    memory: M,
+    cache: InstrCache,
     blocking_io: bool,
+    breakpoints: HashSet<usize>,
+    watchpoints: Vec<Watchpoint>,
+    history: Option<History<M::Word>>,
+    limits: Limits,
+    steps: u64,
+    loop_detector: LoopDetector<M::Word>,
+    opcodes: HashMap<i64, CustomOpcode<M>>,
 }
 
-pub trait InputOutput {
-    fn provide_input(&mut self) -> Option<i64>;
-    fn take_output(&mut self, value: i64) -> Option<()>;
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status<W = i64> {
+    Running,
+    Halted,
+    Blocked,
+    Breakpoint(usize),
+    Watchpoint(WatchEvent<W>),
 }
 
 impl IntcodeComputer {
     pub fn new(program: &[i64], blocking_io: bool) -> Self {
+        Self::with_memory(program, blocking_io)
+    }
+}
+
+impl<M: Memory> IntcodeComputer<M> {
+    /// Creates a computer that keeps its memory in the given backend
+    pub fn with_memory(program: &[M::Word], blocking_io: bool) -> Self {
         Self {
             base: 0,
             instr_ptr: 0,
-            memory: program.iter().cloned().enumerate().collect::<Memory>(),
+            memory: M::load(program),
+            cache: InstrCache::default(),
             blocking_io,
+            breakpoints: HashSet::new(),
+            watchpoints: Vec::new(),
+            history: None,
+            limits: Limits::default(),
+            steps: 0,
+            loop_detector: LoopDetector::default(),
+            opcodes: HashMap::new(),
         }
     }
 
-    pub fn run(&mut self, io: &mut dyn InputOutput) -> Option<bool> {
+    pub fn run(
+        &mut self,
+        io: &mut dyn InputOutput<M::Word>,
+    ) -> Result<bool, IntcodeError> {
         loop {
-            match self.fetch_instr() {
-                Some((HALT_OP, _, _, _)) => break,
-                Some((ADD_OP, mode1, mode2, mode3)) => {
-                    let val1 = self.get_value(self.instr_ptr + 1, mode1)?;
-                    let val2 = self.get_value(self.instr_ptr + 2, mode2)?;
-                    let addr = self.get_addr(self.instr_ptr + 3, mode3)?;
-                    self.set_value(addr, val1 + val2);
-                    self.instr_ptr += 4;
-                }
-                Some((MULT_OP, mode1, mode2, mode3)) => {
-                    let val1 = self.get_value(self.instr_ptr + 1, mode1)?;
-                    let val2 = self.get_value(self.instr_ptr + 2, mode2)?;
-                    let addr = self.get_addr(self.instr_ptr + 3, mode3)?;
-                    self.set_value(addr, val1 * val2);
-                    self.instr_ptr += 4;
-                }
-                Some((IN_OP, mode, _, _)) => {
-                    if let Some(value) = io.provide_input() {
-                        let addr = self.get_addr(self.instr_ptr + 1, mode)?;
-                        self.set_value(addr, value);
-                        self.instr_ptr += 2;
-                    } else if self.blocking_io {
-                        return Some(false);
-                    } else {
-                        return None;
-                    }
-                }
-                Some((OUT_OP, mode, _, _)) => {
-                    let value = self.get_value(self.instr_ptr + 1, mode)?;
-                    if io.take_output(value).is_some() {
-                        self.instr_ptr += 2;
-                    } else if self.blocking_io {
-                        return Some(false);
-                    } else {
-                        return None;
-                    }
-                }
-                Some((JIT_OP, mode1, mode2, _)) => {
-                    let val1 = self.get_value(self.instr_ptr + 1, mode1)?;
-                    let val2 = self.get_value(self.instr_ptr + 2, mode2)?;
-                    if val1 != 0 {
-                        self.instr_ptr = usize::try_from(val2).ok()?;
-                    } else {
-                        self.instr_ptr += 3;
-                    }
-                }
-                Some((JIF_OP, mode1, mode2, _)) => {
-                    let val1 = self.get_value(self.instr_ptr + 1, mode1)?;
-                    let val2 = self.get_value(self.instr_ptr + 2, mode2)?;
-                    if val1 == 0 {
-                        self.instr_ptr = usize::try_from(val2).ok()?;
-                    } else {
-                        self.instr_ptr += 3;
-                    }
-                }
-                Some((LT_OP, mode1, mode2, mode3)) => {
-                    let val1 = self.get_value(self.instr_ptr + 1, mode1)?;
-                    let val2 = self.get_value(self.instr_ptr + 2, mode2)?;
-                    let addr = self.get_addr(self.instr_ptr + 3, mode3)?;
-                    self.set_value(addr, i64::from(val1 < val2));
-                    self.instr_ptr += 4;
+            match self.step(io)? {
+                Status::Blocked => return Ok(false),
+                Status::Halted => return Ok(true),
+                _ => {}
+            }
+        }
+    }
+
+    /// Runs until the program halts or blocks, until an instruction accesses
+    /// a watched position, or until the instruction pointer reaches a
+    /// breakpoint after executing at least one instruction
+    pub fn run_to_breakpoint(
+        &mut self,
+        io: &mut dyn InputOutput<M::Word>,
+    ) -> Result<Status<M::Word>, IntcodeError> {
+        loop {
+            match self.step_to_watchpoint(io)? {
+                Status::Running
+                    if self.breakpoints.contains(&self.instr_ptr) =>
+                {
+                    return Ok(Status::Breakpoint(self.instr_ptr))
                 }
-                Some((EQ_OP, mode1, mode2, mode3)) => {
-                    let val1 = self.get_value(self.instr_ptr + 1, mode1)?;
-                    let val2 = self.get_value(self.instr_ptr + 2, mode2)?;
-                    let addr = self.get_addr(self.instr_ptr + 3, mode3)?;
-                    self.set_value(addr, i64::from(val1 == val2));
-                    self.instr_ptr += 4;
+                Status::Running => {}
+                status => return Ok(status),
+            }
+        }
+    }
+
+    /// Like `run`, recording every executed instruction in `tracer`
+    pub fn run_traced(
+        &mut self,
+        io: &mut dyn InputOutput<M::Word>,
+        tracer: &mut dyn Tracer<M::Word>,
+    ) -> Result<bool, IntcodeError> {
+        loop {
+            match self.step_traced(io, tracer)? {
+                Status::Blocked => return Ok(false),
+                Status::Halted => return Ok(true),
+                _ => {}
+            }
+        }
+    }
+
+    /// Executes a single instruction
+    pub fn step(
+        &mut self,
+        io: &mut dyn InputOutput<M::Word>,
+    ) -> Result<Status<M::Word>, IntcodeError> {
+        let state = self.fetch_instr();
+        self.execute(state, io)
+    }
+
+    /// Executes a single instruction and records it in `tracer`, unless the
+    /// instruction blocked on I/O
+    pub fn step_traced(
+        &mut self,
+        io: &mut dyn InputOutput<M::Word>,
+        tracer: &mut dyn Tracer<M::Word>,
+    ) -> Result<Status<M::Word>, IntcodeError> {
+        let state = self.fetch_instr();
+        let shape = self.instr_shape(state.opcode).unwrap_or((0, false));
+        let operands = Operands::capture(&self.memory, &state, shape);
+        let status = self.execute(state, io)?;
+        if status != Status::Blocked {
+            tracer.record(operands.into_entry(&self.memory, &state));
+        }
+        Ok(status)
+    }
+
+    /// Executes an instruction within the configured limits
+    fn execute(
+        &mut self,
+        state: InstrState,
+        io: &mut dyn InputOutput<M::Word>,
+    ) -> Result<Status<M::Word>, IntcodeError> {
+        if let Some(max_steps) = self.limits.max_steps {
+            if self.steps >= max_steps {
+                return Err(IntcodeError::BudgetExhausted(state, max_steps));
+            }
+        }
+        let delta = if self.history.is_some() {
+            Some(Delta::capture(self, &state))
+        } else {
+            None
+        };
+        let status = self.execute_instr(state, io)?;
+        if status == Status::Blocked {
+            return Ok(status);
+        }
+        if let (Some(history), Some(delta)) = (&mut self.history, delta) {
+            history.push(delta);
+        }
+        self.steps += 1;
+        if self.limits.detect_loops && status == Status::Running {
+            let backward_jump = (state.opcode == JIT_OP
+                || state.opcode == JIF_OP)
+                && self.instr_ptr <= state.instr_ptr;
+            // Custom instructions may do I/O or have effects on the host
+            if state.opcode == IN_OP
+                || state.opcode == OUT_OP
+                || self.opcodes.contains_key(&state.opcode)
+            {
+                self.loop_detector.reset();
+            } else if backward_jump
+                && self.loop_detector.revisits(
+                    &self.memory,
+                    self.instr_ptr,
+                    self.base,
+                )
+            {
+                return Err(IntcodeError::InfiniteLoop(state));
+            }
+        }
+        Ok(status)
+    }
+
+    fn execute_instr(
+        &mut self,
+        state: InstrState,
+        io: &mut dyn InputOutput<M::Word>,
+    ) -> Result<Status<M::Word>, IntcodeError> {
+        match state.opcode {
+            HALT_OP => return Ok(Status::Halted),
+            ADD_OP | MULT_OP => {
+                let val1 = self.get_value(&state, 1)?;
+                let val2 = self.get_value(&state, 2)?;
+                let addr = self.get_addr(&state, 3)?;
+                let result = if state.opcode == ADD_OP {
+                    val1.checked_add(&val2)
+                } else {
+                    val1.checked_mul(&val2)
+                };
+                let value = result.ok_or(IntcodeError::Overflow(state))?;
+                self.set_value(addr, value);
+                self.instr_ptr = instr_offset(&state, 4)?;
+            }
+            IN_OP => {
+                let addr = self.get_addr(&state, 1)?;
+                if let Some(value) = io.provide_input() {
+                    self.set_value(addr, value);
+                    self.instr_ptr = instr_offset(&state, 2)?;
+                } else if self.blocking_io || io.is_pending() {
+                    return Ok(Status::Blocked);
+                } else {
+                    return Err(IntcodeError::NoInput(state));
                 }
-                Some((BASE_OP, mode, _, _)) => {
-                    self.base += self.get_value(self.instr_ptr + 1, mode)?;
-                    self.instr_ptr += 2;
+            }
+            OUT_OP => {
+                let value = self.get_value(&state, 1)?;
+                if io.take_output(value).is_some() {
+                    self.instr_ptr = instr_offset(&state, 2)?;
+                } else if self.blocking_io || io.is_pending() {
+                    return Ok(Status::Blocked);
+                } else {
+                    return Err(IntcodeError::OutputRejected(state));
                 }
-                _ => {
-                    // Invalid instruction
-                    return None;
+            }
+            JIT_OP | JIF_OP => {
+                let val1 = self.get_value(&state, 1)?;
+                let val2 = self.get_value(&state, 2)?;
+                if (val1 != M::Word::zero()) == (state.opcode == JIT_OP) {
+                    self.instr_ptr = to_addr(&state, &val2)?;
+                } else {
+                    self.instr_ptr = instr_offset(&state, 3)?;
                 }
             }
-        }
-        Some(true)
-    }
-
-    fn fetch_instr(&self) -> Option<(i64, i64, i64, i64)> {
-        self.memory.get(&self.instr_ptr).map(|&instr| {
-            let mut opcode = instr;
-            let mode3 = opcode / 10_000;
-            opcode %= 10_000;
-            let mode2 = opcode / 1_000;
-            opcode %= 1_000;
-            let mode1 = opcode / 100;
-            opcode %= 100;
-            (opcode, mode1, mode2, mode3)
-        })
-    }
-
-    fn set_value(&mut self, addr: usize, value: i64) {
-        self.memory
-            .entry(addr)
-            .and_modify(|v| *v = value)
-            .or_insert(value);
-    }
-
-    fn get_value(&self, addr: usize, mode: i64) -> Option<i64> {
-        let pos = match mode {
-            IMMEDIATE_MODE => addr,
-            POSITION_MODE => usize::try_from(*self.memory.get(&addr)?).ok()?,
-            RELATIVE_MODE => {
-                usize::try_from(self.base + *self.memory.get(&addr)?).ok()?
+            LT_OP | EQ_OP => {
+                let val1 = self.get_value(&state, 1)?;
+                let val2 = self.get_value(&state, 2)?;
+                let addr = self.get_addr(&state, 3)?;
+                let holds = if state.opcode == LT_OP {
+                    val1 < val2
+                } else {
+                    val1 == val2
+                };
+                self.set_value(addr, M::Word::from_bool(holds));
+                self.instr_ptr = instr_offset(&state, 4)?;
             }
-            _ => return None,
-        };
-        // At this point, the memory position is an usize and hence valid;
-        // if it's not yet set, return a Some(0)
-        self.memory.get(&pos).cloned().or(Some(0))
+            BASE_OP => {
+                let offset = self.get_value(&state, 1)?;
+                self.base = move_base(&state, &offset)?;
+                self.instr_ptr = instr_offset(&state, 2)?;
+            }
+            _ => return self.execute_custom(state, io),
+        }
+        Ok(Status::Running)
     }
 
-    fn get_addr(&self, addr: usize, mode: i64) -> Option<usize> {
-        let value = match mode {
-            POSITION_MODE => *self.memory.get(&addr)?,
-            RELATIVE_MODE => self.base + *self.memory.get(&addr)?,
-            _ => return None,
-        };
-        usize::try_from(value).ok()
+    pub fn instr_ptr(&self) -> usize {
+        self.instr_ptr
     }
-}
 
-#[derive(Default)]
-pub struct SimpleInputOutput {
-    input: Vec<i64>,
-    output: Vec<i64>,
-}
+    pub fn set_instr_ptr(&mut self, instr_ptr: usize) {
+        self.instr_ptr = instr_ptr;
+    }
 
-impl SimpleInputOutput {
-    pub fn new(input_slice: &[i64]) -> Self {
-        Self {
-            input: input_slice.iter().rev().copied().collect(),
-            output: Vec::new(),
-        }
+    pub fn base(&self) -> i64 {
+        self.base
     }
 
-    pub fn get_output(&mut self) -> Vec<i64> {
-        replace(&mut self.output, Vec::new())
+    pub fn set_base(&mut self, base: i64) {
+        self.base = base;
+    }
+
+    pub fn read_memory(&self, addr: usize) -> M::Word {
+        self.get_memory(addr)
+    }
+
+    pub fn write_memory(&mut self, addr: usize, value: M::Word) {
+        self.set_value(addr, value);
+    }
+
+    /// The instruction at the instruction pointer, decoded
+    pub fn current_instr(&self) -> InstrState {
+        decode_instr(&self.memory, self.instr_ptr, self.base)
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Replaces the cache of decoded instructions, e.g. with
+    /// `InstrCache::disabled()` to decode every instruction as it's fetched
+    pub fn set_cache(&mut self, cache: InstrCache) {
+        self.cache = cache;
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+        self.loop_detector = LoopDetector::default();
+    }
+
+    /// Number of instructions executed so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn add_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.insert(addr);
     }
-}
 
-impl InputOutput for SimpleInputOutput {
-    fn provide_input(&mut self) -> Option<i64> {
-        self.input.pop()
+    pub fn remove_breakpoint(&mut self, addr: usize) -> bool {
+        self.breakpoints.remove(&addr)
     }
 
-    fn take_output(&mut self, value: i64) -> Option<()> {
-        self.output.push(value);
-        Some(())
+    pub fn breakpoints(&self) -> Vec<usize> {
+        let mut breakpoints =
+            self.breakpoints.iter().copied().collect::<Vec<_>>();
+        breakpoints.sort();
+        breakpoints
+    }
+
+    fn fetch_instr(&mut self) -> InstrState {
+        self.cache.fetch(&self.memory, self.instr_ptr, self.base)
+    }
+
+    fn get_memory(&self, addr: usize) -> M::Word {
+        self.memory.get(addr)
+    }
+
+    fn set_value(&mut self, addr: usize, value: M::Word) {
+        self.loop_detector.record_write(&self.memory, addr, &value);
+        self.memory.set(addr, value);
+        self.cache.invalidate(addr);
+    }
+
+    fn get_value(
+        &self,
+        state: &InstrState,
+        param: usize,
+    ) -> Result<M::Word, IntcodeError> {
+        get_value(&self.memory, state, param)
+    }
+
+    /// The memory position a parameter's value is read from
+    fn get_pos(
+        &self,
+        state: &InstrState,
+        param: usize,
+    ) -> Result<usize, IntcodeError> {
+        get_pos(&self.memory, state, param)
+    }
+
+    fn get_addr(
+        &self,
+        state: &InstrState,
+        param: usize,
+    ) -> Result<usize, IntcodeError> {
+        get_addr(&self.memory, state, param)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use memory::{CowMemory, HashMemory, PagedMemory};
+    use word::{to_words, BigInt};
 
-    #[test]
-    fn samples() {
-        let tests = vec![
+    fn sample_programs() -> Vec<(Vec<i64>, Vec<i64>)> {
+        vec![
             (
                 vec![
                     109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101,
@@ -221,12 +413,209 @@ mod tests {
                 vec![1219070632396864],
             ),
             (vec![104, 1125899906842624, 99], vec![1125899906842624]),
+        ]
+    }
+
+    #[test]
+    fn samples() {
+        for (program, output) in sample_programs() {
+            let mut test_io = SimpleInputOutput::default();
+            let mut computer = IntcodeComputer::new(&program, false);
+            assert_eq!(computer.run(&mut test_io), Ok(true));
+            assert_eq!(test_io.get_output(), output);
+
+            let mut computer =
+                IntcodeComputer::<HashMemory>::with_memory(&program, false);
+            assert_eq!(computer.run(&mut test_io), Ok(true));
+            assert_eq!(test_io.get_output(), output);
+        }
+    }
+
+    /// Runs the samples on words of type `W`, expecting the ones that don't
+    /// fit to overflow
+    fn samples_on<W: Word>() {
+        for (program, output) in sample_programs() {
+            let program = match to_words::<W>(&program) {
+                Some(program) => program,
+                None => continue,
+            };
+            let mut test_io = SimpleInputOutput::default();
+            let mut computer =
+                IntcodeComputer::<PagedMemory<W>>::with_memory(&program, false);
+            let result = computer.run(&mut test_io);
+            match to_words::<W>(&output) {
+                Some(output) => {
+                    assert_eq!(result, Ok(true));
+                    assert_eq!(test_io.get_output(), output);
+                }
+                None => {
+                    assert!(matches!(result, Err(IntcodeError::Overflow(_))))
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn samples_on_every_word() {
+        samples_on::<i32>();
+        samples_on::<i64>();
+        samples_on::<i128>();
+        samples_on::<BigInt>();
+    }
+
+    #[test]
+    fn self_modifying() {
+        // Turns its first instruction from `out 7` into `out [7]` and runs it
+        // again
+        let program = [
+            104, 7, 1101, 0, 4, 0, 1001, 20, 1, 20, 1008, 20, 2, 21, 1006, 21,
+            0, 99,
         ];
-        for (program, output) in tests {
+        let mut test_io = SimpleInputOutput::default();
+        let mut computer = IntcodeComputer::new(&program, false);
+        assert_eq!(computer.run(&mut test_io), Ok(true));
+        assert_eq!(test_io.get_output(), vec![7, 20]);
+    }
+
+    #[test]
+    fn fork() {
+        // Adds up its inputs until it reads a 0, then outputs the sum
+        let program = [
+            3, 100, 1006, 100, 12, 1, 100, 101, 101, 1105, 1, 0, 4, 101, 99,
+        ];
+        let mut computer =
+            IntcodeComputer::<CowMemory>::with_memory(&program, true);
+        let mut test_io = SimpleInputOutput::new(&[5]);
+        assert_eq!(computer.run(&mut test_io), Ok(false));
+
+        let mut fork = computer.clone();
+        let mut test_io = SimpleInputOutput::new(&[1, 0]);
+        assert_eq!(fork.run(&mut test_io), Ok(true));
+        assert_eq!(test_io.get_output(), vec![6]);
+        let mut test_io = SimpleInputOutput::new(&[10, 0]);
+        assert_eq!(computer.run(&mut test_io), Ok(true));
+        assert_eq!(test_io.get_output(), vec![15]);
+    }
+
+    #[test]
+    fn cached_instructions() {
+        // Outputs 7 for each input, with `out 7` patched into `out [7]` once
+        // it has run
+        let program = [3, 100, 104, 7, 1105, 1, 0, 99];
+        let mut computer = IntcodeComputer::new(&program, true);
+        let mut test_io = SimpleInputOutput::new(&[1]);
+        assert_eq!(computer.run(&mut test_io), Ok(false));
+        assert_eq!(test_io.get_output(), vec![7]);
+
+        computer.write_memory(2, 4);
+        let mut fork = computer.clone();
+        fork.write_memory(2, 104);
+        let mut test_io = SimpleInputOutput::new(&[1]);
+        assert_eq!(computer.run(&mut test_io), Ok(false));
+        assert_eq!(test_io.get_output(), vec![99]);
+        let mut test_io = SimpleInputOutput::new(&[1]);
+        assert_eq!(fork.run(&mut test_io), Ok(false));
+        assert_eq!(test_io.get_output(), vec![7]);
+    }
+
+    #[test]
+    fn limits() {
+        let run = |program: &[i64], limits| {
+            let mut computer = IntcodeComputer::new(program, false);
+            computer.set_limits(limits);
+            computer.run(&mut SimpleInputOutput::default())
+        };
+        let budget = Limits {
+            max_steps: Some(100),
+            detect_loops: false,
+        };
+        let detector = Limits {
+            max_steps: None,
+            detect_loops: true,
+        };
+        let state = |instr_ptr, instr| InstrState::new(instr_ptr, instr, 0);
+
+        // Jumps to itself
+        let program = [1105, 1, 0];
+        assert_eq!(
+            run(&program, budget),
+            Err(IntcodeError::BudgetExhausted(state(0, 1105), 100))
+        );
+        assert_eq!(
+            run(&program, detector),
+            Err(IntcodeError::InfiniteLoop(state(0, 1105)))
+        );
+
+        // Counts forever, so no state repeats
+        let program = [1001, 7, 1, 7, 1105, 1, 0, 0];
+        assert_eq!(
+            run(&program, budget),
+            Err(IntcodeError::BudgetExhausted(state(0, 1001), 100))
+        );
+
+        // Counts up to 15 and halts
+        let program = [
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101,
+            0, 99,
+        ];
+        assert_eq!(run(&program, detector), Ok(true));
+        let limits = Limits {
+            max_steps: Some(81),
+            detect_loops: true,
+        };
+        assert_eq!(run(&program, limits), Ok(true));
+        let limits = Limits {
+            max_steps: Some(80),
+            ..limits
+        };
+        assert!(run(&program, limits).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let state =
+            |instr_ptr, instr, base| InstrState::new(instr_ptr, instr, base);
+        let tests = vec![
+            (vec![42], IntcodeError::InvalidOpcode(state(0, 42, 0))),
+            (
+                vec![301, 0, 0, 0, 99],
+                IntcodeError::InvalidMode(state(0, 301, 0), 3),
+            ),
+            (
+                vec![109, -5, 204, 0, 99],
+                IntcodeError::NegativeAddress(state(2, 204, -5), -5),
+            ),
+            (
+                vec![11101, 1, 1, 0, 99],
+                IntcodeError::ImmediateWrite(state(0, 11101, 0)),
+            ),
+            (vec![3, 0, 99], IntcodeError::NoInput(state(0, 3, 0))),
+        ];
+        for (program, error) in tests {
             let mut test_io = SimpleInputOutput::default();
             let mut computer = IntcodeComputer::new(&program, false);
-            assert_eq!(computer.run(&mut test_io), Some(true));
-            assert_eq!(test_io.output, output);
+            assert_eq!(computer.run(&mut test_io), Err(error));
         }
+
+        // An instruction at the very end of memory, traced or not
+        let end = usize::MAX - 1;
+        let error = IntcodeError::PastEndOfMemory(state(end, 1101, 0));
+        let mut computer = IntcodeComputer::new(&[], false);
+        computer.write_memory(end, 1101);
+        computer.set_instr_ptr(end);
+        let mut test_io = SimpleInputOutput::default();
+        assert_eq!(computer.run(&mut test_io), Err(error));
+        let mut trace = Vec::new();
+        assert_eq!(computer.run_traced(&mut test_io, &mut trace), Err(error));
+    }
+
+    #[test]
+    fn blocking_io() {
+        let mut test_io = SimpleInputOutput::default();
+        let mut computer = IntcodeComputer::new(&[3, 7, 4, 7, 99], true);
+        assert_eq!(computer.run(&mut test_io), Ok(false));
+        test_io.add_input(&[42]);
+        assert_eq!(computer.run(&mut test_io), Ok(true));
+        assert_eq!(test_io.get_output(), vec![42]);
     }
 }
@@ -0,0 +1,160 @@
+use crate::limits::{Limits, LoopDetector};
+use crate::memory::Memory;
+use crate::IntcodeComputer;
+use intcode::cache::InstrCache;
+use intcode::encoding::{
+    invalid_data, read_byte, read_signed, read_unsigned, write_signed,
+    write_unsigned,
+};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+const SNAPSHOT_MAGIC: &[u8; 4] = b"ICSS";
+const SNAPSHOT_VERSION: u8 = 1;
+
+impl<M: Memory<Word = i64>> IntcodeComputer<M> {
+    /// Writes the machine state (memory, registers and I/O mode) so it can be
+    /// resumed later with `restore`; breakpoints, watchpoints, history
+    /// and custom opcodes are not saved
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut buffer = SNAPSHOT_MAGIC.to_vec();
+        buffer.push(SNAPSHOT_VERSION);
+        write_unsigned(&mut buffer, self.instr_ptr as u64);
+        write_signed(&mut buffer, self.base);
+        buffer.push(u8::from(self.blocking_io));
+
+        let mut cells = self.memory.cells();
+        cells.sort_unstable();
+        write_unsigned(&mut buffer, cells.len() as u64);
+        let mut last_addr = 0;
+        for (addr, value) in cells {
+            write_unsigned(&mut buffer, (addr - last_addr) as u64);
+            write_signed(&mut buffer, value);
+            last_addr = addr;
+        }
+        writer.write_all(&buffer)?;
+        writer.flush()
+    }
+
+    /// Recreates a machine saved with `save`
+    pub fn restore<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != SNAPSHOT_MAGIC {
+            return Err(invalid_data("not an Intcode snapshot"));
+        }
+        if header[4] != SNAPSHOT_VERSION {
+            return Err(invalid_data("unsupported snapshot version"));
+        }
+
+        let instr_ptr = usize::try_from(read_unsigned(&mut reader)?)
+            .map_err(|_| invalid_data("address out of range in snapshot"))?;
+        let base = read_signed(&mut reader)?;
+        let blocking_io = match read_byte(&mut reader)? {
+            Some(0) => false,
+            Some(1) => true,
+            _ => return Err(invalid_data("invalid I/O mode in snapshot")),
+        };
+        let count = read_unsigned(&mut reader)?;
+        let mut memory = M::load(&[]);
+        let mut addr: usize = 0;
+        for _ in 0..count {
+            addr = usize::try_from(read_unsigned(&mut reader)?)
+                .ok()
+                .and_then(|offset| addr.checked_add(offset))
+                .ok_or_else(|| {
+                    invalid_data("address out of range in snapshot")
+                })?;
+            memory.set(addr, read_signed(&mut reader)?);
+        }
+        if read_byte(&mut reader)?.is_some() {
+            return Err(invalid_data("unexpected data after snapshot"));
+        }
+
+        Ok(Self {
+            base,
+            instr_ptr,
+            memory,
+            cache: InstrCache::default(),
+            blocking_io,
+            breakpoints: HashSet::new(),
+            watchpoints: Vec::new(),
+            history: None,
+            limits: Limits::default(),
+            steps: 0,
+            loop_detector: LoopDetector::default(),
+            opcodes: HashMap::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::HashMemory;
+    use crate::SimpleInputOutput;
+
+    #[test]
+    fn save_and_restore() {
+        // Adds up its inputs until it reads a 0, then outputs the sum
+        let program = [
+            3, 100, 1006, 100, 12, 1, 100, 101, 101, 1105, 1, 0, 4, 101, 99,
+        ];
+        let mut computer = IntcodeComputer::new(&program, true);
+        let mut test_io = SimpleInputOutput::new(&[5, -12]);
+        assert_eq!(computer.run(&mut test_io), Ok(false));
+
+        let mut bytes = Vec::new();
+        computer.save(&mut bytes).unwrap();
+        let restored = IntcodeComputer::restore(bytes.as_slice()).unwrap();
+        assert_eq!(restored.instr_ptr(), computer.instr_ptr());
+        assert_eq!(restored.read_memory(101), -7);
+
+        for machine in &mut [computer, restored] {
+            let mut test_io = SimpleInputOutput::new(&[40, 0]);
+            assert_eq!(machine.run(&mut test_io), Ok(true));
+            assert_eq!(test_io.get_output(), vec![33]);
+        }
+    }
+
+    #[test]
+    fn invalid_snapshots() {
+        let computer = IntcodeComputer::new(&[109, -3, 99], false);
+        let mut bytes = Vec::new();
+        computer.save(&mut bytes).unwrap();
+        let restore = |bytes: &[u8]| {
+            IntcodeComputer::<HashMemory>::restore(bytes).map(|_| ())
+        };
+        assert!(restore(&bytes).is_ok());
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] += 1;
+        assert!(restore(&bad_version).is_err());
+        assert!(restore(&bytes[..bytes.len() - 1]).is_err());
+        bytes.push(0);
+        assert!(restore(&bytes).is_err());
+        assert!(restore(b"ICTR\x01").is_err());
+    }
+
+    #[test]
+    fn corrupt_memory() {
+        let restore = |bytes: &[u8]| {
+            IntcodeComputer::<HashMemory>::restore(bytes).map(|_| ())
+        };
+        let mut bytes = SNAPSHOT_MAGIC.to_vec();
+        bytes.extend(&[SNAPSHOT_VERSION, 0, 0, 0, 2]);
+        for _ in 0..2 {
+            write_unsigned(&mut bytes, u64::MAX);
+            write_signed(&mut bytes, 1);
+        }
+        let err = restore(&bytes).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "address out of range in snapshot");
+
+        // Cut off in the middle of the second cell
+        let truncated = &bytes[..bytes.len() - 3];
+        let err = restore(truncated).unwrap_err();
+        assert_eq!(err.to_string(), "unexpected end of data");
+    }
+}
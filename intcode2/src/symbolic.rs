@@ -0,0 +1,754 @@
+use crate::limits::Limits;
+use crate::memory::DENSE_LIMIT;
+use crate::{
+    InstrState, IntcodeComputer, IntcodeError, SimpleInputOutput, ADD_OP,
+    BASE_OP, EQ_OP, HALT_OP, IMMEDIATE_MODE, IN_OP, JIF_OP, JIT_OP, LT_OP,
+    MULT_OP, OUT_OP, POSITION_MODE, RELATIVE_MODE,
+};
+use intcode::instr_offset;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Instructions each run in `solve` may execute, so that values making the
+/// program loop forever count as not matching
+const SOLVE_MAX_STEPS: u64 = 1_000_000;
+
+/// Values given to symbols, by name
+pub type Assignment = BTreeMap<String, i64>;
+
+/// A sum of symbols multiplied by constant coefficients, plus a constant
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LinearExpr {
+    terms: BTreeMap<String, i64>,
+    constant: i64,
+}
+
+impl LinearExpr {
+    pub fn constant(value: i64) -> Self {
+        Self {
+            terms: BTreeMap::new(),
+            constant: value,
+        }
+    }
+
+    pub fn symbol(name: &str) -> Self {
+        let mut terms = BTreeMap::new();
+        terms.insert(name.to_string(), 1);
+        Self { terms, constant: 0 }
+    }
+
+    /// The value of the expression if it doesn't depend on any symbol
+    pub fn value(&self) -> Option<i64> {
+        if self.terms.is_empty() {
+            Some(self.constant)
+        } else {
+            None
+        }
+    }
+
+    pub fn coefficient(&self, name: &str) -> i64 {
+        self.terms.get(name).copied().unwrap_or(0)
+    }
+
+    /// The constant term
+    pub fn offset(&self) -> i64 {
+        self.constant
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &str> {
+        self.terms.keys().map(String::as_str)
+    }
+
+    /// Sum of both expressions, or `None` on overflow
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let mut terms = self.terms.clone();
+        for (name, &coefficient) in &other.terms {
+            let term = terms.entry(name.clone()).or_default();
+            *term = term.checked_add(coefficient)?;
+        }
+        terms.retain(|_, coefficient| *coefficient != 0);
+        Some(Self {
+            terms,
+            constant: self.constant.checked_add(other.constant)?,
+        })
+    }
+
+    /// The expression multiplied by a constant, or `None` on overflow
+    pub fn checked_scale(&self, factor: i64) -> Option<Self> {
+        if factor == 0 {
+            return Some(Self::default());
+        }
+        let terms = self
+            .terms
+            .iter()
+            .map(|(name, coefficient)| {
+                Some((name.clone(), coefficient.checked_mul(factor)?))
+            })
+            .collect::<Option<_>>()?;
+        Some(Self {
+            terms,
+            constant: self.constant.checked_mul(factor)?,
+        })
+    }
+
+    /// Value of the expression for the given symbols, or `None` if some
+    /// symbol is missing or the value overflows
+    pub fn eval(&self, assignment: &Assignment) -> Option<i64> {
+        self.terms
+            .iter()
+            .try_fold(self.constant, |sum, (name, coefficient)| {
+                coefficient
+                    .checked_mul(*assignment.get(name)?)?
+                    .checked_add(sum)
+            })
+    }
+
+    /// Finds values for the symbols, each taken from its domain, that make
+    /// the expression equal to `target`; if there are several, returns the
+    /// first in the order the domains are enumerated, the last one changing
+    /// fastest. Only the other symbols are enumerated: the last symbol with
+    /// a non-zero coefficient is solved for.
+    pub fn solve(
+        &self,
+        target: i64,
+        domains: &[(&str, RangeInclusive<i64>)],
+    ) -> Option<Assignment> {
+        if self
+            .symbols()
+            .any(|symbol| domains.iter().all(|(name, _)| *name != symbol))
+        {
+            return None;
+        }
+        let solved = match domains
+            .iter()
+            .rposition(|(name, _)| self.coefficient(name) != 0)
+        {
+            Some(solved) => solved,
+            None if self.constant == target => {
+                return enumerate(domains, |_| true)
+            }
+            None => return None,
+        };
+
+        let (name, values) = &domains[solved];
+        let coefficient = i128::from(self.coefficient(name));
+        let others = domains
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| index != solved)
+            .map(|(_, domain)| domain.clone())
+            .collect::<Vec<_>>();
+        let mut value = 0;
+        let mut assignment = enumerate(&others, |assignment| {
+            let rest = assignment.iter().fold(
+                i128::from(self.constant),
+                |sum, (name, &value)| {
+                    sum + i128::from(self.coefficient(name)) * i128::from(value)
+                },
+            );
+            let remainder = i128::from(target) - rest;
+            if remainder % coefficient != 0 {
+                return false;
+            }
+            match i64::try_from(remainder / coefficient) {
+                Ok(quotient) if values.contains(&quotient) => {
+                    value = quotient;
+                    true
+                }
+                _ => false,
+            }
+        })?;
+        assignment.insert(name.to_string(), value);
+        Some(assignment)
+    }
+}
+
+impl fmt::Display for LinearExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, (name, &coefficient)) in self.terms.iter().enumerate() {
+            match (index, coefficient < 0) {
+                (0, true) => write!(f, "-")?,
+                (0, false) => {}
+                (_, true) => write!(f, " - ")?,
+                (_, false) => write!(f, " + ")?,
+            }
+            if coefficient.unsigned_abs() != 1 {
+                write!(f, "{}*", coefficient.unsigned_abs())?;
+            }
+            write!(f, "{}", name)?;
+        }
+        match (self.terms.is_empty(), self.constant) {
+            (true, constant) => write!(f, "{}", constant),
+            (false, 0) => Ok(()),
+            (false, constant) if constant < 0 => {
+                write!(f, " - {}", constant.unsigned_abs())
+            }
+            (false, constant) => write!(f, " + {}", constant),
+        }
+    }
+}
+
+/// Calls `matches` with every combination of values from the domains, the
+/// last domain changing fastest, until it returns true
+fn enumerate(
+    domains: &[(&str, RangeInclusive<i64>)],
+    mut matches: impl FnMut(&Assignment) -> bool,
+) -> Option<Assignment> {
+    if domains.iter().any(|(_, values)| values.is_empty()) {
+        return None;
+    }
+    let mut assignment = domains
+        .iter()
+        .map(|(name, values)| (name.to_string(), *values.start()))
+        .collect::<Assignment>();
+    loop {
+        if matches(&assignment) {
+            return Some(assignment);
+        }
+        let mut advanced = false;
+        for (name, values) in domains.iter().rev() {
+            let value = assignment.get_mut(*name).unwrap();
+            if *value < *values.end() {
+                *value += 1;
+                advanced = true;
+                break;
+            }
+            *value = *values.start();
+        }
+        if !advanced {
+            return None;
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SymbolicError {
+    /// The program failed the way it would on concrete values
+    Intcode(IntcodeError),
+    /// An instruction, address or relative base depends on symbols
+    SymbolicAddress(InstrState),
+    /// A jump or comparison depends on symbols
+    SymbolicCondition(InstrState),
+    /// Two expressions that both depend on symbols were multiplied
+    NonLinear(InstrState),
+    /// A coefficient or constant overflowed
+    Overflow(InstrState),
+}
+
+impl SymbolicError {
+    pub fn state(&self) -> &InstrState {
+        match self {
+            SymbolicError::Intcode(err) => err.state(),
+            SymbolicError::SymbolicAddress(state)
+            | SymbolicError::SymbolicCondition(state)
+            | SymbolicError::NonLinear(state)
+            | SymbolicError::Overflow(state) => state,
+        }
+    }
+}
+
+impl fmt::Display for SymbolicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymbolicError::Intcode(err) => return write!(f, "{}", err),
+            SymbolicError::SymbolicAddress(_) => {
+                write!(f, "address depends on symbols")?
+            }
+            SymbolicError::SymbolicCondition(_) => {
+                write!(f, "condition depends on symbols")?
+            }
+            SymbolicError::NonLinear(_) => {
+                write!(f, "product of two symbolic values")?
+            }
+            SymbolicError::Overflow(_) => write!(f, "arithmetic overflow")?,
+        }
+        write!(f, " at {}", self.state())
+    }
+}
+
+impl Error for SymbolicError {}
+
+impl From<IntcodeError> for SymbolicError {
+    fn from(err: IntcodeError) -> Self {
+        SymbolicError::Intcode(err)
+    }
+}
+
+/// What a memory position holds: an expression, or the reason it couldn't
+/// be computed, which only becomes an error when the value is used
+type Value = Result<LinearExpr, SymbolicError>;
+
+/// Runs a program whose memory and inputs may hold linear expressions of
+/// symbols instead of numbers; it fails as soon as the path the program takes
+/// or the positions it writes depend on the symbols
+pub struct SymbolicComputer {
+    base: i64,
+    instr_ptr: usize,
+    memory: Vec<Value>,
+    /// Positions from `DENSE_LIMIT` on, as in `PagedMemory`
+    sparse: HashMap<usize, Value>,
+    inputs: VecDeque<LinearExpr>,
+    outputs: Vec<LinearExpr>,
+    max_steps: Option<u64>,
+    steps: u64,
+}
+
+impl SymbolicComputer {
+    pub fn new(program: &[i64]) -> Self {
+        Self {
+            base: 0,
+            instr_ptr: 0,
+            memory: program
+                .iter()
+                .map(|&value| Ok(LinearExpr::constant(value)))
+                .collect(),
+            sparse: HashMap::new(),
+            inputs: VecDeque::new(),
+            outputs: Vec::new(),
+            max_steps: None,
+            steps: 0,
+        }
+    }
+
+    /// Replaces the value at `addr` with a symbol
+    pub fn set_symbol(&mut self, addr: usize, name: &str) {
+        self.write_memory(addr, LinearExpr::symbol(name));
+    }
+
+    pub fn push_input(&mut self, value: LinearExpr) {
+        self.inputs.push_back(value);
+    }
+
+    pub fn outputs(&self) -> &[LinearExpr] {
+        &self.outputs
+    }
+
+    /// The expression at `addr`, or why there is none: reading a position
+    /// that depends on symbols, or multiplying symbols, gives a value that
+    /// is only an error if the program goes on to use it
+    pub fn read_memory(&self, addr: usize) -> Value {
+        self.memory
+            .get(addr)
+            .or_else(|| self.sparse.get(&addr))
+            .cloned()
+            .unwrap_or_else(|| Ok(LinearExpr::default()))
+    }
+
+    pub fn write_memory(&mut self, addr: usize, value: LinearExpr) {
+        self.set_value(addr, Ok(value));
+    }
+
+    /// Fails with `BudgetExhausted` after executing this many instructions
+    pub fn set_max_steps(&mut self, max_steps: Option<u64>) {
+        self.max_steps = max_steps;
+    }
+
+    /// Runs until the program halts
+    pub fn run(&mut self) -> Result<(), SymbolicError> {
+        while self.step()? {}
+        Ok(())
+    }
+
+    /// Executes a single instruction; returns false if it was a halt
+    pub fn step(&mut self) -> Result<bool, SymbolicError> {
+        let instr = self.read_memory(self.instr_ptr)?;
+        let state = match instr.value() {
+            Some(instr) => InstrState::new(self.instr_ptr, instr, self.base),
+            None => {
+                let state = InstrState::new(self.instr_ptr, 0, self.base);
+                return Err(SymbolicError::SymbolicAddress(state));
+            }
+        };
+        if let Some(max_steps) = self.max_steps {
+            if self.steps >= max_steps {
+                let err = IntcodeError::BudgetExhausted(state, max_steps);
+                return Err(err.into());
+            }
+        }
+        self.steps += 1;
+
+        match state.opcode {
+            HALT_OP => return Ok(false),
+            ADD_OP => {
+                let val1 = self.get_value(&state, 1)?;
+                let val2 = self.get_value(&state, 2)?;
+                let addr = self.get_addr(&state, 3)?;
+                let sum = val1.and_then(|val1| {
+                    val1.checked_add(&val2?)
+                        .ok_or(SymbolicError::Overflow(state))
+                });
+                self.set_value(addr, sum);
+                self.instr_ptr = instr_offset(&state, 4)?;
+            }
+            MULT_OP => {
+                let val1 = self.get_value(&state, 1)?;
+                let val2 = self.get_value(&state, 2)?;
+                let addr = self.get_addr(&state, 3)?;
+                let product = val1.and_then(|val1| {
+                    let val2 = val2?;
+                    let product = match (val1.value(), val2.value()) {
+                        (Some(factor), _) => val2.checked_scale(factor),
+                        (_, Some(factor)) => val1.checked_scale(factor),
+                        _ => return Err(SymbolicError::NonLinear(state)),
+                    };
+                    product.ok_or(SymbolicError::Overflow(state))
+                });
+                self.set_value(addr, product);
+                self.instr_ptr = instr_offset(&state, 4)?;
+            }
+            IN_OP => {
+                let addr = self.get_addr(&state, 1)?;
+                let value = self
+                    .inputs
+                    .pop_front()
+                    .ok_or(IntcodeError::NoInput(state))?;
+                self.write_memory(addr, value);
+                self.instr_ptr = instr_offset(&state, 2)?;
+            }
+            OUT_OP => {
+                let value = self.get_value(&state, 1)??;
+                self.outputs.push(value);
+                self.instr_ptr = instr_offset(&state, 2)?;
+            }
+            JIT_OP | JIF_OP => {
+                let val1 = self.get_value(&state, 1)??;
+                let val2 = self.get_value(&state, 2)??;
+                let cond = val1
+                    .value()
+                    .ok_or(SymbolicError::SymbolicCondition(state))?;
+                if (cond != 0) == (state.opcode == JIT_OP) {
+                    self.instr_ptr = to_addr(&state, &val2)?;
+                } else {
+                    self.instr_ptr = instr_offset(&state, 3)?;
+                }
+            }
+            LT_OP | EQ_OP => {
+                let val1 = self.get_value(&state, 1)?;
+                let val2 = self.get_value(&state, 2)?;
+                let addr = self.get_addr(&state, 3)?;
+                // Only the difference matters, so `x == x` is always true
+                let result = val1.and_then(|val1| {
+                    let diff = val2?
+                        .checked_scale(-1)
+                        .and_then(|neg| val1.checked_add(&neg))
+                        .ok_or(SymbolicError::Overflow(state))?
+                        .value()
+                        .ok_or(SymbolicError::SymbolicCondition(state))?;
+                    let result = if state.opcode == LT_OP {
+                        diff < 0
+                    } else {
+                        diff == 0
+                    };
+                    Ok(LinearExpr::constant(result.into()))
+                });
+                self.set_value(addr, result);
+                self.instr_ptr = instr_offset(&state, 4)?;
+            }
+            BASE_OP => {
+                let offset = self
+                    .get_value(&state, 1)??
+                    .value()
+                    .ok_or(SymbolicError::SymbolicAddress(state))?;
+                self.base = self
+                    .base
+                    .checked_add(offset)
+                    .ok_or(SymbolicError::Overflow(state))?;
+                self.instr_ptr = instr_offset(&state, 2)?;
+            }
+            _ => return Err(IntcodeError::InvalidOpcode(state).into()),
+        }
+        Ok(true)
+    }
+
+    fn set_value(&mut self, addr: usize, value: Value) {
+        if addr < self.memory.len() {
+            self.memory[addr] = value;
+        } else if addr < DENSE_LIMIT {
+            self.memory.resize(addr + 1, Ok(LinearExpr::default()));
+            self.memory[addr] = value;
+        } else {
+            self.sparse.insert(addr, value);
+        }
+    }
+
+    /// A parameter's value; positions that can't be worked out give a value
+    /// that fails when used, as the program may never use it
+    fn get_value(
+        &self,
+        state: &InstrState,
+        param: usize,
+    ) -> Result<Value, SymbolicError> {
+        let addr = instr_offset(state, param)?;
+        let pos = match state.modes[param - 1] {
+            IMMEDIATE_MODE => Ok(addr),
+            POSITION_MODE => self
+                .read_memory(addr)
+                .and_then(|value| to_addr(state, &value)),
+            RELATIVE_MODE => self.relative_addr(state, addr),
+            mode => return Err(IntcodeError::InvalidMode(*state, mode).into()),
+        };
+        Ok(pos.and_then(|pos| self.read_memory(pos)))
+    }
+
+    fn get_addr(
+        &self,
+        state: &InstrState,
+        param: usize,
+    ) -> Result<usize, SymbolicError> {
+        let addr = instr_offset(state, param)?;
+        match state.modes[param - 1] {
+            POSITION_MODE => to_addr(state, &self.read_memory(addr)?),
+            RELATIVE_MODE => self.relative_addr(state, addr),
+            IMMEDIATE_MODE => Err(IntcodeError::ImmediateWrite(*state).into()),
+            mode => Err(IntcodeError::InvalidMode(*state, mode).into()),
+        }
+    }
+
+    fn relative_addr(
+        &self,
+        state: &InstrState,
+        addr: usize,
+    ) -> Result<usize, SymbolicError> {
+        let value = LinearExpr::constant(state.base)
+            .checked_add(&self.read_memory(addr)?)
+            .ok_or(SymbolicError::Overflow(*state))?;
+        to_addr(state, &value)
+    }
+}
+
+fn to_addr(
+    state: &InstrState,
+    value: &LinearExpr,
+) -> Result<usize, SymbolicError> {
+    let value = value
+        .value()
+        .ok_or(SymbolicError::SymbolicAddress(*state))?;
+    usize::try_from(value)
+        .map_err(|_| IntcodeError::NegativeAddress(*state, value).into())
+}
+
+/// A memory position whose initial value is unknown but within a range
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unknown {
+    pub name: String,
+    pub addr: usize,
+    pub values: RangeInclusive<i64>,
+}
+
+impl Unknown {
+    pub fn new(name: &str, addr: usize, values: RangeInclusive<i64>) -> Self {
+        Self {
+            name: name.to_string(),
+            addr,
+            values,
+        }
+    }
+}
+
+/// What is left at `addr` when the program halts, in terms of the unknowns
+pub fn closed_form(
+    program: &[i64],
+    unknowns: &[Unknown],
+    addr: usize,
+) -> Result<LinearExpr, SymbolicError> {
+    closed_form_limited(program, unknowns, addr, None)
+}
+
+fn closed_form_limited(
+    program: &[i64],
+    unknowns: &[Unknown],
+    addr: usize,
+    max_steps: Option<u64>,
+) -> Result<LinearExpr, SymbolicError> {
+    let mut computer = SymbolicComputer::new(program);
+    computer.set_max_steps(max_steps);
+    for unknown in unknowns {
+        computer.set_symbol(unknown.addr, &unknown.name);
+    }
+    computer.run()?;
+    computer.read_memory(addr)
+}
+
+/// Values for the unknowns that leave `target` at `addr` when the program
+/// halts; solved directly from the closed form when there is one, otherwise
+/// by running the program for every combination of values, in which case
+/// runs that fail or take too long count as not matching
+pub fn solve(
+    program: &[i64],
+    unknowns: &[Unknown],
+    addr: usize,
+    target: i64,
+) -> Option<Assignment> {
+    let domains = unknowns
+        .iter()
+        .map(|unknown| (unknown.name.as_str(), unknown.values.clone()))
+        .collect::<Vec<_>>();
+    let max_steps = Some(SOLVE_MAX_STEPS);
+    if let Ok(expr) = closed_form_limited(program, unknowns, addr, max_steps) {
+        return expr.solve(target, &domains);
+    }
+    enumerate(&domains, |assignment| {
+        let mut computer = IntcodeComputer::new(program, false);
+        computer.set_limits(Limits {
+            max_steps,
+            ..Limits::default()
+        });
+        for unknown in unknowns {
+            computer.write_memory(unknown.addr, assignment[&unknown.name]);
+        }
+        let mut io = SimpleInputOutput::default();
+        computer.run(&mut io) == Ok(true)
+            && computer.read_memory(addr) == target
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expr(terms: &[(&str, i64)], constant: i64) -> LinearExpr {
+        terms
+            .iter()
+            .map(|&(name, coefficient)| {
+                LinearExpr::symbol(name).checked_scale(coefficient).unwrap()
+            })
+            .fold(LinearExpr::constant(constant), |sum, term| {
+                sum.checked_add(&term).unwrap()
+            })
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(expr(&[], 0).to_string(), "0");
+        assert_eq!(expr(&[], -3).to_string(), "-3");
+        assert_eq!(
+            expr(&[("noun", 360), ("verb", 1)], 5).to_string(),
+            "360*noun + verb + 5"
+        );
+        assert_eq!(
+            expr(&[("a", -1), ("b", -2)], -7).to_string(),
+            "-a - 2*b - 7"
+        );
+        let cancelled =
+            expr(&[("a", 2)], 0).checked_add(&expr(&[("a", -2)], 1));
+        assert_eq!(cancelled, Some(LinearExpr::constant(1)));
+    }
+
+    #[test]
+    fn solve_expr() {
+        let domains = [("noun", 0..=99), ("verb", 0..=99)];
+        let sum = expr(&[("noun", 100), ("verb", 1)], 3);
+        let assignment = sum.solve(4_206, &domains).unwrap();
+        assert_eq!((assignment["noun"], assignment["verb"]), (42, 3));
+        assert_eq!(sum.eval(&assignment), Some(4_206));
+        assert_eq!(sum.solve(10_003, &domains), None);
+
+        // Several solutions, of which the first one in enumeration order
+        let sum = expr(&[("noun", 1), ("verb", 1)], 0);
+        let assignment = sum.solve(50, &domains).unwrap();
+        assert_eq!((assignment["noun"], assignment["verb"]), (0, 50));
+
+        // Doesn't depend on the noun at all
+        let verb = expr(&[("verb", -2)], 10);
+        let assignment = verb.solve(0, &domains).unwrap();
+        assert_eq!((assignment["noun"], assignment["verb"]), (0, 5));
+        assert_eq!(verb.solve(1, &domains), None);
+        assert_eq!(expr(&[("x", 1)], 0).solve(0, &domains), None);
+    }
+
+    #[test]
+    fn closed_forms() {
+        // Like day 2, first adds the values at the unknown positions, which
+        // can't be tracked but are overwritten: mem[3] = noun + verb, then
+        // mem[0] = mem[3] * 3 + noun
+        let program = [1, 0, 0, 3, 1, 1, 2, 3, 1002, 3, 3, 0, 1, 0, 1, 0, 99];
+        let unknowns = [
+            Unknown::new("noun", 1, 0..=99),
+            Unknown::new("verb", 2, 0..=99),
+        ];
+        let result = closed_form(&program, &unknowns, 0).unwrap();
+        assert_eq!(result.to_string(), "4*noun + 3*verb");
+        let assignment = solve(&program, &unknowns, 0, 100).unwrap();
+        assert_eq!((assignment["noun"], assignment["verb"]), (1, 32));
+
+        // Inputs can be symbols too, and `eq` works when the difference is
+        // constant
+        let program = [3, 20, 101, 5, 20, 21, 8, 20, 20, 22, 4, 21, 4, 22, 99];
+        let mut computer = SymbolicComputer::new(&program);
+        computer.push_input(LinearExpr::symbol("x"));
+        assert_eq!(computer.run(), Ok(()));
+        assert_eq!(
+            computer.outputs(),
+            [expr(&[("x", 1)], 5), LinearExpr::constant(1)]
+        );
+    }
+
+    #[test]
+    fn fallback() {
+        // mem[0] = noun * verb, which is not linear
+        let program = [2, 5, 6, 0, 99, 0, 0];
+        let unknowns = [
+            Unknown::new("noun", 5, 0..=99),
+            Unknown::new("verb", 6, 0..=99),
+        ];
+        assert_eq!(
+            closed_form(&program, &unknowns, 0),
+            Err(SymbolicError::NonLinear(InstrState::new(0, 2, 0)))
+        );
+        let assignment = solve(&program, &unknowns, 0, 91).unwrap();
+        assert_eq!((assignment["noun"], assignment["verb"]), (1, 91));
+
+        // Clears mem[0] unless the noun is non-zero
+        let program = [1105, 0, 7, 1101, 0, 0, 0, 99];
+        let unknowns = [Unknown::new("noun", 1, 0..=99)];
+        assert_eq!(
+            closed_form(&program, &unknowns, 0),
+            Err(SymbolicError::SymbolicCondition(InstrState::new(
+                0, 1105, 0
+            )))
+        );
+        let assignment = solve(&program, &unknowns, 0, 1105).unwrap();
+        assert_eq!(assignment["noun"], 1);
+
+        // Keeps the value at the unknown position
+        let program = [1, 0, 0, 0, 99];
+        let unknowns = [Unknown::new("noun", 1, 0..=4)];
+        assert_eq!(
+            closed_form(&program, &unknowns, 0),
+            Err(SymbolicError::SymbolicAddress(InstrState::new(0, 1, 0)))
+        );
+        let assignment = solve(&program, &unknowns, 0, 100).unwrap();
+        assert_eq!(assignment["noun"], 4);
+
+        // Loops forever unless the noun is non-zero
+        let program = [1106, 0, 0, 99];
+        let unknowns = [Unknown::new("noun", 1, 0..=1)];
+        let assignment = solve(&program, &unknowns, 0, 1106).unwrap();
+        assert_eq!(assignment["noun"], 1);
+    }
+
+    #[test]
+    fn base_overflow() {
+        let mut computer = SymbolicComputer::new(&[109, i64::MAX, 109, 1, 99]);
+        assert_eq!(
+            computer.run(),
+            Err(SymbolicError::Overflow(InstrState::new(2, 109, i64::MAX)))
+        );
+    }
+
+    #[test]
+    fn far_writes() {
+        let far = 1 << 40;
+        let mut computer = SymbolicComputer::new(&[1101, 0, 1, far, 99]);
+        assert_eq!(computer.run(), Ok(()));
+        assert_eq!(computer.read_memory(far as usize), Ok(expr(&[], 1)));
+        assert_eq!(computer.memory.len(), 5);
+
+        let unknowns = [Unknown::new("noun", 2, 0..=9)];
+        let assignment = solve(&[1101, 0, 1, far, 99], &unknowns, 1 << 40, 5);
+        assert_eq!(assignment.unwrap()["noun"], 5);
+    }
+}
@@ -0,0 +1,159 @@
+pub use intcode::trace::{
+    read_trace, IoEvent, Operands, TraceEntry, TraceWriter, Tracer,
+};
+
+use crate::{InputOutput, IntcodeComputer, IntcodeError, Status};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// First difference between a recorded trace and its replay
+#[derive(Clone, Debug, PartialEq)]
+pub enum Divergence {
+    Mismatch(usize, Box<TraceEntry>, Box<TraceEntry>),
+    Missing(usize, Box<TraceEntry>),
+    Extra(usize, Box<TraceEntry>),
+    Failed(usize, IntcodeError),
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Divergence::Mismatch(step, expected, actual) => write!(
+                f,
+                "step {}: expected {}, found {}",
+                step, expected, actual
+            ),
+            Divergence::Missing(step, expected) => {
+                write!(f, "step {}: replay stopped before {}", step, expected)
+            }
+            Divergence::Extra(step, actual) => {
+                write!(f, "step {}: replay went on to {}", step, actual)
+            }
+            Divergence::Failed(step, err) => {
+                write!(f, "step {}: replay failed: {}", step, err)
+            }
+        }
+    }
+}
+
+/// Feeds the inputs recorded in a trace back to a program
+struct ReplayInputOutput {
+    input: VecDeque<i64>,
+}
+
+impl InputOutput for ReplayInputOutput {
+    fn provide_input(&mut self) -> Option<i64> {
+        self.input.pop_front()
+    }
+
+    fn take_output(&mut self, _value: i64) -> Option<()> {
+        Some(())
+    }
+}
+
+/// Runs `program` on a fresh computer with the inputs found in `trace` and
+/// checks it executes exactly the same instructions
+pub fn replay(program: &[i64], trace: &[TraceEntry]) -> Result<(), Divergence> {
+    let input = trace
+        .iter()
+        .filter_map(|entry| match entry.io {
+            Some(IoEvent::Input(value)) => Some(value),
+            _ => None,
+        })
+        .collect();
+    let mut replay_io = ReplayInputOutput { input };
+    let mut computer = IntcodeComputer::new(program, true);
+
+    let mut step = 0;
+    loop {
+        let mut actual = Vec::new();
+        let status = computer
+            .step_traced(&mut replay_io, &mut actual)
+            .map_err(|err| Divergence::Failed(step, err))?;
+        for entry in actual {
+            match trace.get(step) {
+                Some(expected) if *expected != entry => {
+                    return Err(Divergence::Mismatch(
+                        step,
+                        Box::new(expected.clone()),
+                        Box::new(entry),
+                    ))
+                }
+                Some(_) => step += 1,
+                None => return Err(Divergence::Extra(step, Box::new(entry))),
+            }
+        }
+        if status != Status::Running {
+            break;
+        }
+    }
+    match trace.get(step) {
+        Some(expected) => {
+            Err(Divergence::Missing(step, Box::new(expected.clone())))
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimpleInputOutput;
+
+    const PROGRAM: [i64; 13] = [3, 11, 1001, 11, 5, 12, 4, 12, 99, 0, 0, 0, 0];
+
+    fn record(program: &[i64], input: &[i64]) -> Vec<TraceEntry> {
+        let mut test_io = SimpleInputOutput::new(input);
+        let mut computer = IntcodeComputer::new(program, false);
+        let mut trace = Vec::new();
+        assert_eq!(computer.run_traced(&mut test_io, &mut trace), Ok(true));
+        trace
+    }
+
+    #[test]
+    fn replay_other_engine() {
+        // Traces recorded by the first engine replay on this one
+        let program = [
+            3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31,
+            1106, 0, 36, 98, 0, 0, 1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104,
+            999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20, 1105, 1, 46, 98, 99,
+        ];
+        let mut test_io = intcode::SimpleInputOutput::new(&[8]);
+        let mut writer = intcode::trace::TraceWriter::new(Vec::new()).unwrap();
+        assert_eq!(
+            intcode::execute_traced(&program, &mut test_io, &mut writer),
+            Ok(())
+        );
+        assert_eq!(test_io.get_output(), vec![1000]);
+        let bytes = writer.finish().unwrap();
+        let trace = read_trace(bytes.as_slice()).unwrap();
+        assert_eq!(replay(&program, &trace), Ok(()));
+    }
+
+    #[test]
+    fn replay_runs() {
+        let trace = record(&PROGRAM, &[42]);
+        assert_eq!(replay(&PROGRAM, &trace), Ok(()));
+
+        let mut changed = PROGRAM;
+        changed[4] = 6;
+        match replay(&changed, &trace) {
+            Err(Divergence::Mismatch(1, expected, actual)) => {
+                assert_eq!(expected.write, Some((12, 47)));
+                assert_eq!(actual.write, Some((12, 48)));
+            }
+            other => panic!("unexpected replay result {:?}", other),
+        }
+
+        assert_eq!(
+            replay(&PROGRAM, &trace[..2]),
+            Err(Divergence::Extra(2, Box::new(trace[2].clone())))
+        );
+        let mut longer = trace.clone();
+        longer.push(trace[3].clone());
+        assert_eq!(
+            replay(&PROGRAM, &longer),
+            Err(Divergence::Missing(4, Box::new(trace[3].clone())))
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use crate::cfg::{BasicBlock, Cfg};
+use crate::disasm::{Instruction, Operand};
+use crate::memory::{Memory, PagedMemory};
+use crate::{
+    InputOutput, InstrState, IntcodeComputer, IntcodeError, ADD_OP, BASE_OP,
+    EQ_OP, HALT_OP, IN_OP, JIF_OP, JIT_OP, LT_OP, MULT_OP, OUT_OP,
+};
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+const VALUES_PER_LINE: usize = 12;
+
+/// Room left after the program in `Native`'s flat memory, where programs
+/// usually keep their stack
+const STACK_ROOM: usize = 256;
+
+/// Memory and relative base of a transpiled program, which hands them over
+/// to the interpreter when it can't carry on natively
+///
+/// The program and the stack after it, where almost every access goes, are
+/// kept in a flat vector; only addresses past that are paged.
+pub struct Native {
+    flat: Vec<i64>,
+    rest: PagedMemory,
+    base: i64,
+}
+
+impl Native {
+    pub fn new(program: &[i64]) -> Self {
+        let mut flat = Vec::with_capacity(program.len() + STACK_ROOM);
+        flat.extend_from_slice(program);
+        flat.resize(program.len() + STACK_ROOM, 0);
+        Self {
+            flat,
+            rest: PagedMemory::default(),
+            base: 0,
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, addr: usize) -> i64 {
+        match self.flat.get(addr) {
+            Some(&value) => value,
+            None => self.rest.get(addr - self.flat.len()),
+        }
+    }
+
+    #[inline]
+    pub fn set(&mut self, addr: usize, value: i64) {
+        match self.flat.get_mut(addr) {
+            Some(cell) => *cell = value,
+            None => self.rest.set(addr - self.flat.len(), value),
+        }
+    }
+
+    /// Address held by the position parameter at `param`; `None` if it's
+    /// negative
+    #[inline]
+    pub fn pos(&self, param: usize) -> Option<usize> {
+        Self::addr(self.get(param))
+    }
+
+    /// Address the relative parameter at `param` refers to; `None` if it's
+    /// negative or overflows
+    #[inline]
+    pub fn rel(&self, param: usize) -> Option<usize> {
+        Self::addr(self.base.checked_add(self.get(param))?)
+    }
+
+    /// Moves the relative base; `None`, leaving it as it was, if that
+    /// overflows
+    #[inline]
+    pub fn add_base(&mut self, offset: i64) -> Option<()> {
+        self.base = self.base.checked_add(offset)?;
+        Some(())
+    }
+
+    #[inline]
+    pub fn addr(value: i64) -> Option<usize> {
+        usize::try_from(value).ok()
+    }
+
+    /// The error for the instruction at `instr_ptr`, such as
+    /// `IntcodeError::NoInput`
+    pub fn error(
+        &self,
+        instr_ptr: usize,
+        error: fn(InstrState) -> IntcodeError,
+    ) -> IntcodeError {
+        error(InstrState::new(instr_ptr, self.get(instr_ptr), self.base))
+    }
+
+    /// Carries on with the interpreter from `instr_ptr`, which is also where
+    /// errors are reported
+    pub fn interpret(
+        self,
+        instr_ptr: usize,
+        io: &mut dyn InputOutput,
+    ) -> Result<bool, IntcodeError> {
+        let mut computer = IntcodeComputer::new(&self.flat, false);
+        for (offset, value) in self.rest.cells() {
+            computer.memory.set(self.flat.len() + offset, value);
+        }
+        computer.base = self.base;
+        computer.instr_ptr = instr_ptr;
+        computer.run(io)
+    }
+}
+
+/// Rust source for a module with the program in `PROGRAM` and a function
+/// `name(io: &mut dyn InputOutput) -> Result<bool, IntcodeError>` that runs
+/// it like a non-blocking `IntcodeComputer` would, minus the limits
+///
+/// Each basic block found by the disassembler becomes straight-line code,
+/// with a `match` on the instruction pointer between blocks. Parameters are
+/// read from memory, since programs often change them, but anything else,
+/// such as jumps to code it didn't find or writes replacing an instruction,
+/// is left to the interpreter. The module has no inner attributes, so it can
+/// be brought in with `include!`.
+pub fn transpile(program: &[i64], name: &str) -> String {
+    let cfg = Cfg::new(program);
+    let mut source = String::new();
+    writeln!(source, "// Generated by intcode2's transpiler; do not edit")
+        .unwrap();
+    writeln!(source).unwrap();
+    writeln!(source, "use intcode2::transpile::Native;").unwrap();
+    writeln!(source, "use intcode2::{{InputOutput, IntcodeError}};").unwrap();
+    writeln!(source).unwrap();
+    writeln!(source, "pub const PROGRAM: [i64; {}] = [", program.len())
+        .unwrap();
+    for values in program.chunks(VALUES_PER_LINE) {
+        writeln!(source, "    {},", join(values, ", ")).unwrap();
+    }
+    writeln!(source, "];").unwrap();
+    writeln!(source).unwrap();
+
+    let instrs = cfg
+        .blocks()
+        .flat_map(|block| block.instrs.iter().map(|instr| instr.addr))
+        .collect::<Vec<_>>();
+    writeln!(source, "fn is_instr(addr: usize) -> bool {{").unwrap();
+    if instrs.is_empty() {
+        writeln!(source, "    false").unwrap();
+    } else {
+        writeln!(source, "    matches!(").unwrap();
+        writeln!(source, "        addr,").unwrap();
+        for (line, addrs) in instrs.chunks(VALUES_PER_LINE).enumerate() {
+            let prefix = if line == 0 { "" } else { "| " };
+            writeln!(source, "        {}{}", prefix, join(addrs, " | "))
+                .unwrap();
+        }
+        writeln!(source, "    )").unwrap();
+    }
+    writeln!(source, "}}").unwrap();
+    writeln!(source).unwrap();
+
+    writeln!(source, "#[allow(unused_mut, clippy::all)]").unwrap();
+    writeln!(
+        source,
+        "pub fn {}(io: &mut dyn InputOutput) -> Result<bool, IntcodeError> {{",
+        name
+    )
+    .unwrap();
+    writeln!(source, "    let mut m = Native::new(&PROGRAM);").unwrap();
+    writeln!(source, "    let mut ip = 0;").unwrap();
+    writeln!(source, "    loop {{").unwrap();
+    writeln!(source, "        match ip {{").unwrap();
+    for block in cfg.blocks() {
+        writeln!(source, "            {} => {{", block.start()).unwrap();
+        for line in block_code(block) {
+            writeln!(source, "                {}", line).unwrap();
+        }
+        writeln!(source, "            }}").unwrap();
+    }
+    writeln!(source, "            _ => return m.interpret(ip, io),").unwrap();
+    writeln!(source, "        }}").unwrap();
+    writeln!(source, "    }}").unwrap();
+    writeln!(source, "}}").unwrap();
+    source
+}
+
+fn join<T: ToString>(values: &[T], separator: &str) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Statements for a block; it ends setting `ip` to the next block or
+/// returning
+fn block_code(block: &BasicBlock) -> Vec<String> {
+    let mut lines = Vec::new();
+    for instr in &block.instrs {
+        lines.push(format!("// {:04}  {}", instr.addr, instr));
+        instr_code(instr, &mut lines);
+    }
+    let last = &block.instrs[block.instrs.len() - 1];
+    if ![JIT_OP, JIF_OP, HALT_OP].contains(&last.opcode) {
+        lines.push(format!("ip = {};", block.end()));
+    }
+    lines
+}
+
+/// Hands over to the interpreter at the instruction, before it changes
+/// anything, for it to fail or carry on as it would
+fn bail(instr: &Instruction) -> String {
+    format!("return m.interpret({}, io)", instr.addr)
+}
+
+fn instr_code(instr: &Instruction, lines: &mut Vec<String>) {
+    match instr.opcode {
+        ADD_OP | MULT_OP | LT_OP | EQ_OP => {
+            let val1 = read(instr, 0, lines);
+            let val2 = read(instr, 1, lines);
+            let dest = dest(instr, 2, lines);
+            let checked = |op| {
+                format!(
+                    "match {}.{}({}) {{ Some(value) => value, None => {} }}",
+                    val1,
+                    op,
+                    val2,
+                    bail(instr)
+                )
+            };
+            let value = match instr.opcode {
+                ADD_OP => checked("checked_add"),
+                MULT_OP => checked("checked_mul"),
+                LT_OP => format!("i64::from({} < {})", val1, val2),
+                _ => format!("i64::from({} == {})", val1, val2),
+            };
+            lines.push(format!("m.set({}, {});", dest, value));
+        }
+        IN_OP => {
+            let dest = dest(instr, 0, lines);
+            lines.push(format!(
+                "let value = match io.provide_input() {{ Some(value) => value, \
+                 None => return Err(m.error({}, IntcodeError::NoInput)) }};",
+                instr.addr
+            ));
+            lines.push(format!("m.set({}, value);", dest));
+        }
+        OUT_OP => {
+            let value = read(instr, 0, lines);
+            lines.push(format!(
+                "if io.take_output({}).is_none() {{ \
+                 return Err(m.error({}, IntcodeError::OutputRejected)); }}",
+                value, instr.addr
+            ));
+        }
+        JIT_OP | JIF_OP => {
+            let cond = read(instr, 0, lines);
+            let target = read(instr, 1, lines);
+            let test = if instr.opcode == JIT_OP { "!=" } else { "==" };
+            lines.push(format!(
+                "ip = if {} {} 0 {{ match Native::addr({}) {{ \
+                 Some(addr) => addr, None => {} }} }} else {{ {} }};",
+                cond,
+                test,
+                target,
+                bail(instr),
+                instr.addr + instr.size()
+            ));
+        }
+        BASE_OP => {
+            let value = read(instr, 0, lines);
+            lines.push(format!(
+                "if m.add_base({}).is_none() {{ {}; }}",
+                value,
+                bail(instr)
+            ));
+        }
+        _ => lines.push("return Ok(true);".to_string()),
+    }
+}
+
+/// Expression for the value of a parameter, binding its address first
+/// unless it's immediate
+fn read(instr: &Instruction, param: usize, lines: &mut Vec<String>) -> String {
+    let at = instr.addr + param + 1;
+    match instr.operands[param] {
+        Operand::Immediate(_) => format!("m.get({})", at),
+        _ => {
+            let temp = format!("p{}", param + 1);
+            lines.push(address(instr, param, &temp));
+            format!("m.get({})", temp)
+        }
+    }
+}
+
+/// Binds `dest` to the address the parameter writes to, if that doesn't
+/// replace an instruction
+fn dest(instr: &Instruction, param: usize, lines: &mut Vec<String>) -> String {
+    lines.push(address(instr, param, "dest"));
+    lines.push(format!("if is_instr(dest) {{ {}; }}", bail(instr)));
+    "dest".to_string()
+}
+
+fn address(instr: &Instruction, param: usize, temp: &str) -> String {
+    let mode = match instr.operands[param] {
+        Operand::Relative(_) => "rel",
+        _ => "pos",
+    };
+    format!(
+        "let {} = match m.{}({}) {{ Some(addr) => addr, None => {} }};",
+        temp,
+        mode,
+        instr.addr + param + 1,
+        bail(instr)
+    )
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+mod fixture;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use crate::SimpleInputOutput;
+
+    // Outputs 3n + 1 for each input n until it reads 0, calling a function
+    // for the product; it changes its own code to add 10 instead of 1 after
+    // a negative input, and to halt instead of printing after one over 1000
+    const SOURCE: &str = "
+                rbo   stack
+        loop:   in    [n]
+                jf    [n], done
+                lt    [n], 0, [flag]
+                jf    [flag], big
+                add   10, 0, [back + 2]
+        big:    lt    1000, [n], [flag]
+                jf    [flag], call
+                add   99, 0, [print]
+        call:   add   [n], 0, rel[1]
+                add   back, 0, rel[0]
+                jt    1, triple
+        back:   add   rel[1], 1, [n]
+        print:  out   [n]
+                jt    1, loop
+        done:   hlt
+        triple: rbo   2
+                mul   rel[-1], 3, rel[-1]
+                rbo   -2
+                jt    1, rel[0]
+        n:      .data 0
+        flag:   .data 0
+        stack:  .data 0
+        ";
+
+    fn interpret(
+        program: &[i64],
+        input: &[i64],
+    ) -> (Result<bool, IntcodeError>, Vec<i64>) {
+        let mut test_io = SimpleInputOutput::new(input);
+        let result = IntcodeComputer::new(program, false).run(&mut test_io);
+        (result, test_io.get_output())
+    }
+
+    fn native(input: &[i64]) -> (Result<bool, IntcodeError>, Vec<i64>) {
+        let mut test_io = SimpleInputOutput::new(input);
+        let result = fixture::run(&mut test_io);
+        (result, test_io.get_output())
+    }
+
+    #[test]
+    fn fixture_is_current() {
+        let program = assemble(SOURCE).unwrap();
+        assert_eq!(
+            transpile(&program, "run"),
+            include_str!("transpile/fixture.rs")
+        );
+    }
+
+    #[test]
+    fn matches_interpreter() {
+        let inputs: [&[i64]; 5] = [
+            &[1, 2, 0],
+            &[5, -1, 5, 0],
+            &[3, 2000, 4],
+            &[4],
+            &[i64::MAX, 0],
+        ];
+        for input in &inputs {
+            assert_eq!(native(input), interpret(&fixture::PROGRAM, input));
+        }
+        assert_eq!(native(&[1, 2, -1, 5, 0]), (Ok(true), vec![4, 7, 7, 25]));
+        assert_eq!(native(&[3, 2000, 4]), (Ok(true), vec![10]));
+        assert!(matches!(native(&[]).0, Err(IntcodeError::NoInput(_))));
+        assert!(matches!(
+            native(&[i64::MAX, 0]).0,
+            Err(IntcodeError::Overflow(_))
+        ));
+    }
+
+    #[test]
+    fn native_memory() {
+        let far = 3 + STACK_ROOM + 5000;
+        let mut memory = Native::new(&[4, far as i64, 99]);
+        assert_eq!(memory.get(far), 0);
+        memory.set(far, 42);
+        memory.set(3, 7);
+        assert_eq!((memory.get(far), memory.get(3)), (42, 7));
+        let mut test_io = SimpleInputOutput::default();
+        assert_eq!(memory.interpret(0, &mut test_io), Ok(true));
+        assert_eq!(test_io.get_output(), vec![42]);
+    }
+
+    #[test]
+    fn guards() {
+        let source = transpile(&[1101, 1, 1, 0, 99], "run");
+        assert!(source.contains("matches!(\n        addr,\n        0 | 4\n"));
+        assert!(
+            source.contains("if is_instr(dest) { return m.interpret(0, io); }")
+        );
+        assert!(source.contains("            _ => return m.interpret(ip, io),"));
+    }
+}
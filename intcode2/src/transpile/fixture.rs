@@ -0,0 +1,124 @@
+// Generated by intcode2's transpiler; do not edit
+
+use intcode2::transpile::Native;
+use intcode2::{InputOutput, IntcodeError};
+
+pub const PROGRAM: [i64; 64] = [
+    109, 63, 3, 61, 1006, 61, 49, 1007, 61, 0, 62, 1006,
+    62, 18, 1101, 10, 0, 42, 107, 1000, 61, 62, 1006, 62,
+    29, 1101, 99, 0, 44, 21001, 61, 0, 1, 21101, 40, 0,
+    0, 1105, 1, 50, 1201, 1, 1, 61, 4, 61, 1105, 1,
+    2, 99, 109, 2, 21202, -1, 3, -1, 109, -2, 2105, 1,
+    0, 0, 0, 0,
+];
+
+fn is_instr(addr: usize) -> bool {
+    matches!(
+        addr,
+        0 | 2 | 4 | 7 | 11 | 14 | 18 | 22 | 25 | 29 | 33 | 37
+        | 40 | 44 | 46 | 49 | 50 | 52 | 56 | 58
+    )
+}
+
+#[allow(unused_mut, clippy::all)]
+pub fn run(io: &mut dyn InputOutput) -> Result<bool, IntcodeError> {
+    let mut m = Native::new(&PROGRAM);
+    let mut ip = 0;
+    loop {
+        match ip {
+            0 => {
+                // 0000  rbo   63
+                if m.add_base(m.get(1)).is_none() { return m.interpret(0, io); }
+                ip = 2;
+            }
+            2 => {
+                // 0002  in    [61]
+                let dest = match m.pos(3) { Some(addr) => addr, None => return m.interpret(2, io) };
+                if is_instr(dest) { return m.interpret(2, io); }
+                let value = match io.provide_input() { Some(value) => value, None => return Err(m.error(2, IntcodeError::NoInput)) };
+                m.set(dest, value);
+                // 0004  jf    [61], 49
+                let p1 = match m.pos(5) { Some(addr) => addr, None => return m.interpret(4, io) };
+                ip = if m.get(p1) == 0 { match Native::addr(m.get(6)) { Some(addr) => addr, None => return m.interpret(4, io) } } else { 7 };
+            }
+            7 => {
+                // 0007  lt    [61], 0, [62]
+                let p1 = match m.pos(8) { Some(addr) => addr, None => return m.interpret(7, io) };
+                let dest = match m.pos(10) { Some(addr) => addr, None => return m.interpret(7, io) };
+                if is_instr(dest) { return m.interpret(7, io); }
+                m.set(dest, i64::from(m.get(p1) < m.get(9)));
+                // 0011  jf    [62], 18
+                let p1 = match m.pos(12) { Some(addr) => addr, None => return m.interpret(11, io) };
+                ip = if m.get(p1) == 0 { match Native::addr(m.get(13)) { Some(addr) => addr, None => return m.interpret(11, io) } } else { 14 };
+            }
+            14 => {
+                // 0014  add   10, 0, [42]
+                let dest = match m.pos(17) { Some(addr) => addr, None => return m.interpret(14, io) };
+                if is_instr(dest) { return m.interpret(14, io); }
+                m.set(dest, match m.get(15).checked_add(m.get(16)) { Some(value) => value, None => return m.interpret(14, io) });
+                ip = 18;
+            }
+            18 => {
+                // 0018  lt    1000, [61], [62]
+                let p2 = match m.pos(20) { Some(addr) => addr, None => return m.interpret(18, io) };
+                let dest = match m.pos(21) { Some(addr) => addr, None => return m.interpret(18, io) };
+                if is_instr(dest) { return m.interpret(18, io); }
+                m.set(dest, i64::from(m.get(19) < m.get(p2)));
+                // 0022  jf    [62], 29
+                let p1 = match m.pos(23) { Some(addr) => addr, None => return m.interpret(22, io) };
+                ip = if m.get(p1) == 0 { match Native::addr(m.get(24)) { Some(addr) => addr, None => return m.interpret(22, io) } } else { 25 };
+            }
+            25 => {
+                // 0025  add   99, 0, [44]
+                let dest = match m.pos(28) { Some(addr) => addr, None => return m.interpret(25, io) };
+                if is_instr(dest) { return m.interpret(25, io); }
+                m.set(dest, match m.get(26).checked_add(m.get(27)) { Some(value) => value, None => return m.interpret(25, io) });
+                ip = 29;
+            }
+            29 => {
+                // 0029  add   [61], 0, rel[1]
+                let p1 = match m.pos(30) { Some(addr) => addr, None => return m.interpret(29, io) };
+                let dest = match m.rel(32) { Some(addr) => addr, None => return m.interpret(29, io) };
+                if is_instr(dest) { return m.interpret(29, io); }
+                m.set(dest, match m.get(p1).checked_add(m.get(31)) { Some(value) => value, None => return m.interpret(29, io) });
+                // 0033  add   40, 0, rel[0]
+                let dest = match m.rel(36) { Some(addr) => addr, None => return m.interpret(33, io) };
+                if is_instr(dest) { return m.interpret(33, io); }
+                m.set(dest, match m.get(34).checked_add(m.get(35)) { Some(value) => value, None => return m.interpret(33, io) });
+                // 0037  jt    1, 50
+                ip = if m.get(38) != 0 { match Native::addr(m.get(39)) { Some(addr) => addr, None => return m.interpret(37, io) } } else { 40 };
+            }
+            40 => {
+                // 0040  add   rel[1], 1, [61]
+                let p1 = match m.rel(41) { Some(addr) => addr, None => return m.interpret(40, io) };
+                let dest = match m.pos(43) { Some(addr) => addr, None => return m.interpret(40, io) };
+                if is_instr(dest) { return m.interpret(40, io); }
+                m.set(dest, match m.get(p1).checked_add(m.get(42)) { Some(value) => value, None => return m.interpret(40, io) });
+                // 0044  out   [61]
+                let p1 = match m.pos(45) { Some(addr) => addr, None => return m.interpret(44, io) };
+                if io.take_output(m.get(p1)).is_none() { return Err(m.error(44, IntcodeError::OutputRejected)); }
+                // 0046  jt    1, 2
+                ip = if m.get(47) != 0 { match Native::addr(m.get(48)) { Some(addr) => addr, None => return m.interpret(46, io) } } else { 49 };
+            }
+            49 => {
+                // 0049  hlt
+                return Ok(true);
+            }
+            50 => {
+                // 0050  rbo   2
+                if m.add_base(m.get(51)).is_none() { return m.interpret(50, io); }
+                // 0052  mul   rel[-1], 3, rel[-1]
+                let p1 = match m.rel(53) { Some(addr) => addr, None => return m.interpret(52, io) };
+                let dest = match m.rel(55) { Some(addr) => addr, None => return m.interpret(52, io) };
+                if is_instr(dest) { return m.interpret(52, io); }
+                m.set(dest, match m.get(p1).checked_mul(m.get(54)) { Some(value) => value, None => return m.interpret(52, io) });
+                // 0056  rbo   -2
+                if m.add_base(m.get(57)).is_none() { return m.interpret(56, io); }
+                // 0058  jt    1, rel[0]
+                let p2 = match m.rel(60) { Some(addr) => addr, None => return m.interpret(58, io) };
+                ip = if m.get(59) != 0 { match Native::addr(m.get(p2)) { Some(addr) => addr, None => return m.interpret(58, io) } } else { 61 };
+            }
+            _ => return m.interpret(ip, io),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::memory::Memory;
+use crate::{
+    InputOutput, InstrState, IntcodeComputer, IntcodeError, Status,
+    IMMEDIATE_MODE,
+};
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Which accesses to a watched range are reported
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WatchMode {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl WatchMode {
+    fn covers(self, access: Access) -> bool {
+        match self {
+            WatchMode::Read => access == Access::Read,
+            WatchMode::Write => access == Access::Write,
+            WatchMode::ReadWrite => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Watchpoint {
+    pub range: Range<usize>,
+    pub mode: WatchMode,
+}
+
+/// An instruction reading or writing a watched memory position; reads have
+/// the same old and new value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WatchEvent<W = i64> {
+    pub instr_ptr: usize,
+    pub addr: usize,
+    pub access: Access,
+    pub old_value: W,
+    pub new_value: W,
+}
+
+impl<W: fmt::Display> fmt::Display for WatchEvent<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.access {
+            Access::Read => write!(
+                f,
+                "read [{}] = {} at {}",
+                self.addr, self.old_value, self.instr_ptr
+            ),
+            Access::Write => write!(
+                f,
+                "write [{}] {} -> {} at {}",
+                self.addr, self.old_value, self.new_value, self.instr_ptr
+            ),
+        }
+    }
+}
+
+pub trait Watcher<W = i64> {
+    fn notify(&mut self, event: WatchEvent<W>);
+}
+
+impl<W, F: FnMut(WatchEvent<W>)> Watcher<W> for F {
+    fn notify(&mut self, event: WatchEvent<W>) {
+        self(event)
+    }
+}
+
+impl<M: Memory> IntcodeComputer<M> {
+    /// Watches positions in `range`; `run_to_breakpoint` pauses after an
+    /// instruction touches them and `run_watched` reports every access
+    pub fn add_watchpoint(&mut self, range: Range<usize>, mode: WatchMode) {
+        self.watchpoints.push(Watchpoint { range, mode });
+    }
+
+    /// Removes the watchpoints on exactly `range`
+    pub fn remove_watchpoint(&mut self, range: Range<usize>) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints
+            .retain(|watchpoint| watchpoint.range != range);
+        self.watchpoints.len() < count
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Like `run`, calling `watcher` for every access to a watched position
+    pub fn run_watched(
+        &mut self,
+        io: &mut dyn InputOutput<M::Word>,
+        watcher: &mut dyn Watcher<M::Word>,
+    ) -> Result<bool, IntcodeError> {
+        loop {
+            match self.step_watched(io, watcher)? {
+                Status::Blocked => return Ok(false),
+                Status::Halted => return Ok(true),
+                _ => {}
+            }
+        }
+    }
+
+    /// Executes a single instruction and calls `watcher` for each watched
+    /// position it read or wrote, in parameter order
+    pub fn step_watched(
+        &mut self,
+        io: &mut dyn InputOutput<M::Word>,
+        watcher: &mut dyn Watcher<M::Word>,
+    ) -> Result<Status<M::Word>, IntcodeError> {
+        if self.watchpoints.is_empty() {
+            return self.step(io);
+        }
+        let state = self.fetch_instr();
+        let accesses = self.watched_accesses(&state);
+        let status = self.execute(state, io)?;
+        if status == Status::Blocked {
+            return Ok(status);
+        }
+        for (access, addr, old_value) in accesses {
+            let new_value = match access {
+                Access::Read => old_value.clone(),
+                Access::Write => self.get_memory(addr),
+            };
+            watcher.notify(WatchEvent {
+                instr_ptr: state.instr_ptr,
+                addr,
+                access,
+                old_value,
+                new_value,
+            });
+        }
+        Ok(status)
+    }
+
+    /// Executes a single instruction, reporting the first watched access it
+    /// made as a `Status::Watchpoint`
+    pub(crate) fn step_to_watchpoint(
+        &mut self,
+        io: &mut dyn InputOutput<M::Word>,
+    ) -> Result<Status<M::Word>, IntcodeError> {
+        let mut hit = None;
+        let status = self.step_watched(io, &mut |event| {
+            hit.get_or_insert(event);
+        })?;
+        Ok(hit.map_or(status, Status::Watchpoint))
+    }
+
+    /// Watched positions the instruction is about to access, with their
+    /// current values; parameters in immediate mode are part of the
+    /// instruction and don't count as reads
+    fn watched_accesses(
+        &self,
+        state: &InstrState,
+    ) -> Vec<(Access, usize, M::Word)> {
+        let (count, writes) =
+            self.instr_shape(state.opcode).unwrap_or((0, false));
+        // Invalid operands make the instruction fail, so they are skipped
+        (1..=count)
+            .filter_map(|param| {
+                if writes && param == count {
+                    let addr = self.get_addr(state, param).ok()?;
+                    Some((Access::Write, addr))
+                } else if state.modes[param - 1] == IMMEDIATE_MODE {
+                    None
+                } else {
+                    let addr = self.get_pos(state, param).ok()?;
+                    Some((Access::Read, addr))
+                }
+            })
+            .filter(|&(access, addr)| {
+                self.watchpoints.iter().any(|watchpoint| {
+                    watchpoint.mode.covers(access)
+                        && watchpoint.range.contains(&addr)
+                })
+            })
+            .map(|(access, addr)| (access, addr, self.get_memory(addr)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimpleInputOutput;
+
+    // Counts up to 15 and halts, keeping the counter at 100 and the result of
+    // the comparison at 101
+    const COUNTER: [i64; 16] = [
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0,
+        99,
+    ];
+
+    #[test]
+    fn callback() {
+        let mut computer = IntcodeComputer::new(&COUNTER, false);
+        computer.add_watchpoint(100..101, WatchMode::Write);
+        computer.add_watchpoint(101..102, WatchMode::Read);
+        let mut events = Vec::new();
+        let mut test_io = SimpleInputOutput::default();
+        let result =
+            computer.run_watched(&mut test_io, &mut |event| events.push(event));
+        assert_eq!(result, Ok(true));
+        assert_eq!(events.len(), 32);
+
+        let write = |old_value, new_value| WatchEvent {
+            instr_ptr: 4,
+            addr: 100,
+            access: Access::Write,
+            old_value,
+            new_value,
+        };
+        let read = |value| WatchEvent {
+            instr_ptr: 12,
+            addr: 101,
+            access: Access::Read,
+            old_value: value,
+            new_value: value,
+        };
+        assert_eq!(events[..2], [write(0, 1), read(0)]);
+        assert_eq!(events[30..], [write(15, 16), read(1)]);
+        assert_eq!(events[0].to_string(), "write [100] 0 -> 1 at 4");
+        assert_eq!(events[1].to_string(), "read [101] = 0 at 12");
+    }
+
+    #[test]
+    fn pause() {
+        let mut computer = IntcodeComputer::new(&COUNTER, false);
+        computer.add_watchpoint(100..102, WatchMode::ReadWrite);
+        let mut test_io = SimpleInputOutput::default();
+
+        // Incrementing the counter reads it before writing it
+        let status = computer.run_to_breakpoint(&mut test_io);
+        assert_eq!(
+            status,
+            Ok(Status::Watchpoint(WatchEvent {
+                instr_ptr: 4,
+                addr: 100,
+                access: Access::Read,
+                old_value: 0,
+                new_value: 0,
+            }))
+        );
+        assert_eq!(computer.instr_ptr(), 8);
+        let status = computer.run_to_breakpoint(&mut test_io);
+        assert!(matches!(
+            status,
+            Ok(Status::Watchpoint(WatchEvent { instr_ptr: 8, .. }))
+        ));
+
+        assert!(computer.remove_watchpoint(100..102));
+        assert!(!computer.remove_watchpoint(100..102));
+        assert_eq!(
+            computer.run_to_breakpoint(&mut test_io),
+            Ok(Status::Halted)
+        );
+    }
+}